@@ -0,0 +1,116 @@
+use crate::error::Result;
+use keepass_ng::Uuid;
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Files handed to other programs by `open_temporary`; wiped by `wipe_temporary_files`.
+static TEMPORARY_FILES: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// `name` without directories, made unique among `existing` with a " (2)", " (3)"... suffix before the extension.
+pub fn unique_name(existing: &[&str], name: &str) -> String {
+    let name = Path::new(name)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "attachment".to_string());
+    if !existing.contains(&name.as_str()) {
+        return name;
+    }
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem.to_string(), format!(".{extension}")),
+        _ => (name.clone(), String::new()),
+    };
+    (2..)
+        .map(|number| format!("{stem} ({number}){extension}"))
+        .find(|candidate| !existing.contains(&candidate.as_str()))
+        .unwrap_or(name)
+}
+
+/// Writes `data` to a private temporary directory and opens it with the program registered for its type.
+pub fn open_temporary(name: &str, data: &[u8]) -> Result<PathBuf> {
+    let path = write_temporary(name, data)?;
+    launch(&path)?;
+    Ok(path)
+}
+
+/// Overwrites every file written by `open_temporary` with zeros and removes it with its directory.
+pub fn wipe_temporary_files() {
+    let paths = std::mem::take(&mut *TEMPORARY_FILES.lock().unwrap());
+    for path in paths {
+        if let Err(error) = wipe(&path) {
+            log::warn!("Could not wipe temporary attachment {}: {error}", path.display());
+        }
+    }
+}
+
+fn write_temporary(name: &str, data: &[u8]) -> Result<PathBuf> {
+    let directory = std::env::temp_dir().join(format!("mypass-{}", Uuid::new_v4()));
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(&directory)?;
+    let path = directory.join(unique_name(&[], name));
+    let mut file = File::create(&path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    TEMPORARY_FILES.lock().unwrap().push(path.clone());
+    Ok(path)
+}
+
+fn wipe(path: &Path) -> Result<()> {
+    if let Ok(metadata) = fs::metadata(path) {
+        let mut file = fs::OpenOptions::new().write(true).open(path)?;
+        file.write_all(&vec![0; metadata.len() as usize])?;
+        file.sync_all()?;
+        drop(file);
+        fs::remove_file(path)?;
+    }
+    if let Some(directory) = path.parent() {
+        fs::remove_dir(directory).ok();
+    }
+    Ok(())
+}
+
+fn launch(path: &Path) -> Result<()> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = std::process::Command::new("open");
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = std::process::Command::new("xdg-open");
+    command.arg(path).spawn()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{unique_name, wipe_temporary_files, write_temporary};
+
+    #[test]
+    fn names_are_stripped_and_made_unique() {
+        assert_eq!(unique_name(&[], "/home/me/.ssh/id_ed25519"), "id_ed25519");
+        assert_eq!(unique_name(&["server.pem"], "server.pem"), "server (2).pem");
+        assert_eq!(unique_name(&["key", "key (2)"], "key"), "key (3)");
+        assert_eq!(unique_name(&[".env"], ".env"), ".env (2)");
+        assert_eq!(unique_name(&[], ""), "attachment");
+    }
+
+    #[test]
+    fn temporary_files_are_wiped() {
+        let path = write_temporary("../secret.txt", b"top secret").unwrap();
+        assert_eq!(path.file_name().unwrap(), "secret.txt");
+        assert_eq!(std::fs::read(&path).unwrap(), b"top secret");
+        let directory = path.parent().unwrap().to_path_buf();
+        wipe_temporary_files();
+        assert!(!path.exists());
+        assert!(!directory.exists());
+    }
+}
@@ -0,0 +1,379 @@
+use crate::error::Result;
+use crate::keepass::KpDb;
+use crate::placeholders;
+use keepass_ng::{
+    Uuid,
+    db::{Entry, NodeIterator, NodePtr, node_is_group},
+};
+use regex::RegexBuilder;
+use std::time::Duration;
+use zeroize::Zeroizing;
+
+/// The sequence KeePass types when neither the window association nor the entry sets one.
+pub const DEFAULT_SEQUENCE: &str = "{USERNAME}{TAB}{PASSWORD}{ENTER}";
+/// Pause between two keystrokes until a `{DELAY=...}` changes it.
+pub const DEFAULT_KEY_DELAY: Duration = Duration::from_millis(10);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+    /// The Windows or Super key.
+    pub meta: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Tab,
+    Enter,
+    Space,
+    Backspace,
+    Delete,
+    Insert,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Function(u8),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Typed one character at a time, without interpreting the sequence syntax; wiped when dropped, since it
+    /// usually holds the password.
+    Text(Zeroizing<String>),
+    Key(Key, Modifiers),
+    /// `{DELAY n}`: waits once.
+    Delay(Duration),
+    /// `{DELAY=n}`: sets the pause between keystrokes from here on.
+    KeyDelay(Duration),
+}
+
+/// Sends keystrokes to the focused window.
+pub trait Output {
+    /// Presses and releases `key` while `modifiers` are held; characters that need Shift get it from the backend.
+    fn key(&mut self, key: Key, modifiers: Modifiers) -> Result<()>;
+
+    fn pause(&mut self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// An entry that can be typed into a window, with the sequence chosen for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub uuid: Uuid,
+    pub title: String,
+    pub sequence: String,
+}
+
+/// Turns a KeePass auto-type sequence into actions, resolving `{USERNAME}`, `{REF:...}` and the other placeholders of `entry`.
+pub fn compile(root: Option<&NodePtr>, entry: &Entry, sequence: &str) -> Result<Vec<Action>> {
+    let mut actions = Vec::new();
+    let mut modifiers = Modifiers::default();
+    let mut rest = sequence;
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '+' => modifiers.shift = true,
+            '^' => modifiers.control = true,
+            '%' => modifiers.alt = true,
+            '@' => modifiers.meta = true,
+            '~' => actions.push(Action::Key(Key::Enter, std::mem::take(&mut modifiers))),
+            '{' => {
+                // `{}}` types a closing brace; every other name ends at the first `}`.
+                let end = if rest.starts_with("}}") {
+                    1
+                } else {
+                    rest.find('}').ok_or_else(|| format!("Missing }} after {{{rest}"))?
+                };
+                let name = &rest[..end];
+                rest = &rest[end + 1..];
+                placeholder(root, entry, name, std::mem::take(&mut modifiers), &mut actions)?;
+            }
+            c if modifiers != Modifiers::default() => actions.push(Action::Key(Key::Char(c), std::mem::take(&mut modifiers))),
+            c => push_text(&mut actions, &c.to_string()),
+        }
+    }
+    Ok(actions)
+}
+
+fn placeholder(root: Option<&NodePtr>, entry: &Entry, name: &str, modifiers: Modifiers, actions: &mut Vec<Action>) -> Result<()> {
+    let upper = name.to_ascii_uppercase();
+    if let Some(milliseconds) = upper.strip_prefix("DELAY=") {
+        actions.push(Action::KeyDelay(milliseconds_arg(milliseconds)?));
+        return Ok(());
+    }
+    if let Some(milliseconds) = upper.strip_prefix("DELAY ") {
+        actions.push(Action::Delay(milliseconds_arg(milliseconds)?));
+        return Ok(());
+    }
+    // `{TAB 3}` presses a key three times.
+    let (key_name, count) = upper
+        .rsplit_once(' ')
+        .and_then(|(key_name, count)| Some((key_name, count.trim().parse().ok()?)))
+        .unwrap_or((upper.as_str(), 1));
+    if let Some(key) = key_for(key_name) {
+        actions.extend(std::iter::repeat_n(Action::Key(key, modifiers), count));
+        return Ok(());
+    }
+    let placeholder = format!("{{{name}}}");
+    let value = placeholders::resolve(root, entry, &placeholder)?;
+    if value == placeholder {
+        return Err(format!("Unknown placeholder {placeholder}").into());
+    }
+    push_text(actions, &value);
+    Ok(())
+}
+
+fn milliseconds_arg(value: &str) -> Result<Duration> {
+    let milliseconds = value.trim().parse().map_err(|_| format!("Not a delay in milliseconds: {value}"))?;
+    Ok(Duration::from_millis(milliseconds))
+}
+
+fn key_for(name: &str) -> Option<Key> {
+    let key = match name {
+        "TAB" => Key::Tab,
+        "ENTER" => Key::Enter,
+        "SPACE" => Key::Space,
+        "BACKSPACE" | "BS" | "BKSP" => Key::Backspace,
+        "DELETE" | "DEL" => Key::Delete,
+        "INSERT" | "INS" => Key::Insert,
+        "ESC" => Key::Escape,
+        "UP" => Key::Up,
+        "DOWN" => Key::Down,
+        "LEFT" => Key::Left,
+        "RIGHT" => Key::Right,
+        "HOME" => Key::Home,
+        "END" => Key::End,
+        "PGUP" => Key::PageUp,
+        "PGDN" => Key::PageDown,
+        "+" | "^" | "%" | "@" | "~" | "{" | "}" | "(" | ")" | "[" | "]" => Key::Char(name.chars().next()?),
+        _ => {
+            let number = name.strip_prefix('F')?.parse().ok().filter(|number| (1..=24).contains(number))?;
+            Key::Function(number)
+        }
+    };
+    Some(key)
+}
+
+fn push_text(actions: &mut Vec<Action>, text: &str) {
+    if let Some(Action::Text(typed)) = actions.last_mut() {
+        typed.push_str(text);
+    } else if !text.is_empty() {
+        actions.push(Action::Text(Zeroizing::new(text.to_string())));
+    }
+}
+
+/// Types `actions` through `output`, pausing between keystrokes.
+pub fn perform(actions: &[Action], output: &mut dyn Output) -> Result<()> {
+    let mut key_delay = DEFAULT_KEY_DELAY;
+    for action in actions {
+        match action {
+            Action::Text(text) => {
+                for c in text.chars() {
+                    let key = match c {
+                        '\t' => Key::Tab,
+                        '\n' => Key::Enter,
+                        c => Key::Char(c),
+                    };
+                    output.key(key, Modifiers::default())?;
+                    output.pause(key_delay);
+                }
+            }
+            Action::Key(key, modifiers) => {
+                output.key(*key, *modifiers)?;
+                output.pause(key_delay);
+            }
+            Action::Delay(duration) => output.pause(*duration),
+            Action::KeyDelay(duration) => key_delay = *duration,
+        }
+    }
+    Ok(())
+}
+
+/// Whether a KeePass window pattern matches `title`: `*` and `?` are wildcards, `//...//` is a regular expression.
+pub fn window_matches(pattern: &str, title: &str) -> bool {
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+        return false;
+    }
+    let regex = match pattern.strip_prefix("//").and_then(|pattern| pattern.strip_suffix("//")) {
+        Some(regex) => regex.to_string(),
+        None => {
+            let wildcards = pattern
+                .split('*')
+                .map(|part| part.split('?').map(regex::escape).collect::<Vec<_>>().join("."))
+                .collect::<Vec<_>>()
+                .join(".*");
+            format!("^{wildcards}$")
+        }
+    };
+    RegexBuilder::new(&regex)
+        .case_insensitive(true)
+        .build()
+        .is_ok_and(|regex| regex.is_match(title))
+}
+
+/// The entries outside the recycle bin that can be typed into the window titled `window_title`.
+pub fn candidates(kpdb: &KpDb, window_title: &str) -> Vec<Candidate> {
+    let Some(root) = kpdb.get_root() else {
+        return Vec::new();
+    };
+    NodeIterator::new(&root)
+        .filter(|node| !node_is_group(node))
+        .filter(|node| !kpdb.is_recycled(node.borrow().get_uuid()))
+        .filter_map(|node| {
+            node.borrow()
+                .downcast_ref::<Entry>()
+                .and_then(|entry| candidate(entry, window_title))
+        })
+        .collect()
+}
+
+fn candidate(entry: &Entry, window_title: &str) -> Option<Candidate> {
+    let autotype = entry.get_autotype();
+    if autotype.is_some_and(|autotype| !autotype.enabled) {
+        return None;
+    }
+    let default_sequence = non_empty(autotype.and_then(|autotype| autotype.default_sequence.as_deref())).unwrap_or(DEFAULT_SEQUENCE);
+    let title = entry.get_title().unwrap_or("").trim();
+    let association = autotype
+        .into_iter()
+        .flat_map(|autotype| &autotype.associations)
+        .find(|association| {
+            association
+                .window
+                .as_deref()
+                .is_some_and(|window| window_matches(window, window_title))
+        });
+    let sequence = match association {
+        Some(association) => non_empty(association.sequence.as_deref()).unwrap_or(default_sequence),
+        // Like KeePass, an entry whose title appears in the window title matches without an association.
+        None if !title.is_empty() && window_title.to_lowercase().contains(&title.to_lowercase()) => default_sequence,
+        None => return None,
+    };
+    Some(Candidate {
+        uuid: entry.get_uuid(),
+        title: title.to_string(),
+        sequence: sequence.to_string(),
+    })
+}
+
+fn non_empty(sequence: Option<&str>) -> Option<&str> {
+    sequence.map(str::trim).filter(|sequence| !sequence.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, Key, Modifiers, Output, candidates, compile, perform, window_matches};
+    use crate::error::Result;
+    use crate::keepass::KpDb;
+    use keepass_ng::db::{AutoType, AutoTypeAssociation, Entry, with_node, with_node_mut};
+    use std::time::Duration;
+    use zeroize::Zeroizing;
+
+    /// Writes what would have been typed, with special keys in angle brackets.
+    #[derive(Default)]
+    struct Recorder {
+        typed: String,
+        pauses: Vec<Duration>,
+    }
+
+    impl Output for Recorder {
+        fn key(&mut self, key: Key, modifiers: Modifiers) -> Result<()> {
+            match (key, modifiers == Modifiers::default()) {
+                (Key::Char(c), true) => self.typed.push(c),
+                (key, true) => self.typed.push_str(&format!("<{key:?}>")),
+                (key, false) => self.typed.push_str(&format!("<{modifiers:?} {key:?}>")),
+            }
+            Ok(())
+        }
+
+        fn pause(&mut self, duration: Duration) {
+            self.pauses.push(duration);
+        }
+    }
+
+    fn entry() -> (KpDb, Entry) {
+        let mut kpdb = KpDb::new();
+        let root = kpdb.get_root().unwrap().borrow().get_uuid();
+        let node = kpdb.create_new_entry(root).unwrap();
+        with_node_mut::<Entry, _, _>(&node, |entry| {
+            entry.set_title(Some("Mail"));
+            entry.set_username(Some("alice"));
+            entry.set_password(Some("P@ss{1}"));
+            entry.set_url(Some("https://mail.example.com/login"));
+        });
+        let entry = with_node::<Entry, _, _>(&node, Entry::clone).unwrap();
+        (kpdb, entry)
+    }
+
+    #[test]
+    fn types_sequences_with_keys_modifiers_and_delays() {
+        let (kpdb, entry) = entry();
+        let root = kpdb.get_root();
+        let actions = compile(
+            root.as_ref(),
+            &entry,
+            "{USERNAME}{TAB}{PASSWORD}{DELAY=50}{ENTER}{DELAY 500}^a{TAB 2}{{}{}}{URL:HOST}~",
+        )
+        .unwrap();
+        let mut recorder = Recorder::default();
+        perform(&actions, &mut recorder).unwrap();
+        let control = Modifiers {
+            control: true,
+            ..Modifiers::default()
+        };
+        assert_eq!(
+            recorder.typed,
+            format!("alice<Tab>P@ss{{1}}<Enter><{control:?} Char('a')><Tab><Tab>{{}}mail.example.com<Enter>")
+        );
+        assert!(recorder.pauses.contains(&Duration::from_millis(500)));
+        assert_eq!(recorder.pauses.last(), Some(&Duration::from_millis(50)));
+        assert_eq!(actions[0], Action::Text(Zeroizing::new("alice".to_string())));
+
+        assert!(compile(root.as_ref(), &entry, "{NOSUCHKEY}").is_err());
+        assert!(compile(root.as_ref(), &entry, "{DELAY x}").is_err());
+    }
+
+    #[test]
+    fn matches_window_patterns() {
+        assert!(window_matches("*Firefox*", "Inbox - Mozilla Firefox"));
+        assert!(window_matches("inbox - mozilla firefox", "Inbox - Mozilla Firefox"));
+        assert!(!window_matches("Inbox", "Inbox - Mozilla Firefox"));
+        assert!(window_matches("Login ?", "Login 2"));
+        assert!(window_matches("//^Inbox.*(Firefox|Chrome)$//", "Inbox - Mozilla Firefox"));
+        assert!(!window_matches("a.b", "axb"));
+    }
+
+    #[test]
+    fn chooses_entries_by_association_or_title() {
+        let (kpdb, entry) = entry();
+        let node = kpdb.get_node_by_id(entry.get_uuid()).unwrap();
+        assert_eq!(candidates(&kpdb, "Mail - Inbox").len(), 1);
+        assert!(candidates(&kpdb, "Terminal").is_empty());
+
+        with_node_mut::<Entry, _, _>(&node, |entry| {
+            entry.set_autotype(Some(AutoType {
+                enabled: true,
+                default_sequence: Some("{PASSWORD}{ENTER}".to_string()),
+                associations: vec![AutoTypeAssociation {
+                    window: Some("*Terminal*".to_string()),
+                    sequence: None,
+                }],
+                ..AutoType::default()
+            }));
+        });
+        let found = candidates(&kpdb, "Terminal - ssh");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].sequence, "{PASSWORD}{ENTER}");
+    }
+}
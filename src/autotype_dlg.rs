@@ -0,0 +1,47 @@
+use crate::autotype::Candidate;
+use wxdragon::{BoxSizer, Button, ButtonEvents, Choice, Dialog, Orientation, SizerFlag, StaticText, WxWidget};
+
+/// Lets the user pick which of several matching entries to type into `window_title`.
+pub fn choose(parent: &dyn WxWidget, window_title: &str, candidates: &[Candidate]) -> Option<usize> {
+    let dialog = Dialog::builder(parent, "Auto-Type").with_size(480, 180).build();
+    let prompt = StaticText::builder(&dialog)
+        .with_label(&format!("Several entries match \"{window_title}\". Type which one?"))
+        .build();
+    let choices = candidates
+        .iter()
+        .map(|candidate| {
+            if candidate.title.is_empty() {
+                "(no title)".to_string()
+            } else {
+                candidate.title.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+    let entries = Choice::builder(&dialog).with_choices(choices).build();
+    entries.set_selection(0);
+
+    let root = BoxSizer::builder(Orientation::Vertical).build();
+    root.add(&prompt, 0, SizerFlag::All | SizerFlag::Expand, 12);
+    root.add(&entries, 0, SizerFlag::Left | SizerFlag::Right | SizerFlag::Expand, 12);
+    let actions = BoxSizer::builder(Orientation::Horizontal).build();
+    let spacer = StaticText::builder(&dialog).with_label("").build();
+    let cancel = Button::builder(&dialog).with_id(wxdragon::ID_CANCEL).with_label("Cancel").build();
+    let ok = Button::builder(&dialog).with_label("Type").build();
+    actions.add(&spacer, 1, SizerFlag::Expand, 0);
+    actions.add(&cancel, 0, SizerFlag::All, 4);
+    actions.add(&ok, 0, SizerFlag::All, 4);
+    root.add_sizer(&actions, 0, SizerFlag::All | SizerFlag::Expand, 8);
+    dialog.set_sizer(root, true);
+    dialog.set_escape_id(wxdragon::ID_CANCEL);
+
+    let dialog_for_cancel = dialog;
+    cancel.on_click(move |_| dialog_for_cancel.end_modal(wxdragon::ID_CANCEL));
+    let dialog_for_ok = dialog;
+    ok.on_click(move |_| dialog_for_ok.end_modal(wxdragon::ID_OK));
+
+    dialog.center();
+    let result = dialog.show_modal();
+    let selection = entries.get_selection().map(|selection| selection as usize);
+    dialog.destroy();
+    selection.filter(|_| result == wxdragon::ID_OK)
+}
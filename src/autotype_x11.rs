@@ -0,0 +1,330 @@
+use crate::autotype::{Key, Modifiers, Output};
+use crate::error::Result;
+use std::{
+    ffi::{CStr, c_int, c_long, c_uchar, c_ulong},
+    ptr,
+    time::Duration,
+};
+use x11::{keysym, xlib, xtest};
+
+/// Modifiers that may still be held from the global hotkey when typing starts.
+const MODIFIER_KEYSYMS: [u32; 9] = [
+    keysym::XK_Shift_L,
+    keysym::XK_Shift_R,
+    keysym::XK_Control_L,
+    keysym::XK_Control_R,
+    keysym::XK_Alt_L,
+    keysym::XK_Alt_R,
+    keysym::XK_Super_L,
+    keysym::XK_Super_R,
+    keysym::XK_ISO_Level3_Shift,
+];
+
+/// The empty keysym.
+const NO_SYMBOL: c_ulong = 0;
+
+/// How long clients get to pick up a change of the keyboard mapping.
+const REMAP_DELAY: Duration = Duration::from_millis(50);
+
+/// A top-level window of the X server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActiveWindow {
+    pub id: xlib::Window,
+    pub title: String,
+}
+
+struct Display(*mut xlib::Display);
+
+impl Display {
+    fn open() -> Result<Self> {
+        // SAFETY: a null name opens the display named by $DISPLAY; the result is checked before use.
+        let display = unsafe { xlib::XOpenDisplay(ptr::null()) };
+        if display.is_null() {
+            return Err("Auto-Type needs an X11 display; Wayland sessions are not supported".into());
+        }
+        Ok(Self(display))
+    }
+
+    fn atom(&self, name: &CStr) -> xlib::Atom {
+        // SAFETY: the display is open and `name` is NUL-terminated.
+        unsafe { xlib::XInternAtom(self.0, name.as_ptr(), xlib::False) }
+    }
+
+    /// Reads up to 64 KiB of a window property of type `kind` as raw items.
+    fn property(&self, window: xlib::Window, property: xlib::Atom, kind: xlib::Atom) -> Option<(c_int, Vec<u8>)> {
+        let mut actual_kind = 0;
+        let mut format = 0;
+        let mut items = 0;
+        let mut remaining = 0;
+        let mut data: *mut c_uchar = ptr::null_mut();
+        // SAFETY: all out-pointers are valid; `data` is freed with XFree below.
+        let status = unsafe {
+            xlib::XGetWindowProperty(
+                self.0,
+                window,
+                property,
+                0,
+                16384,
+                xlib::False,
+                kind,
+                &mut actual_kind,
+                &mut format,
+                &mut items,
+                &mut remaining,
+                &mut data,
+            )
+        };
+        if status != xlib::Success as c_int || data.is_null() {
+            return None;
+        }
+        // Xlib hands out 32-bit items as C longs.
+        let item_size = match format {
+            8 => 1,
+            16 => 2,
+            32 => size_of::<c_long>(),
+            _ => 0,
+        };
+        // SAFETY: Xlib returned `items` items of `item_size` bytes at `data`.
+        let bytes = unsafe { std::slice::from_raw_parts(data, items as usize * item_size) }.to_vec();
+        // SAFETY: `data` was allocated by Xlib.
+        unsafe { xlib::XFree(data.cast()) };
+        (actual_kind != 0).then_some((format, bytes))
+    }
+
+    fn keycode(&self, keysym: c_ulong) -> Option<xlib::KeyCode> {
+        // SAFETY: the display is open.
+        let keycode = unsafe { xlib::XKeysymToKeycode(self.0, keysym) };
+        (keycode != 0).then_some(keycode)
+    }
+
+    /// The shift level of the first group at which `keycode` produces `keysym`: 0 plain, 1 Shift, 2 AltGr and
+    /// 3 Shift+AltGr.
+    fn level(&self, keycode: xlib::KeyCode, keysym: c_ulong) -> Option<(xlib::KeyCode, u32)> {
+        (0..4)
+            // SAFETY: the display is open and `keycode` came from it.
+            .find(|level| unsafe { xlib::XkbKeycodeToKeysym(self.0, keycode, 0, *level as c_int) } == keysym)
+            .map(|level| (keycode, level))
+    }
+
+    /// The highest keycode the layout binds to nothing.
+    fn unused_keycode(&self) -> Option<xlib::KeyCode> {
+        let mut min = 0;
+        let mut max = 0;
+        let mut per_keycode = 0;
+        // SAFETY: the display is open and the out-pointers are valid.
+        let keysyms = unsafe {
+            xlib::XDisplayKeycodes(self.0, &mut min, &mut max);
+            xlib::XGetKeyboardMapping(self.0, min as xlib::KeyCode, max - min + 1, &mut per_keycode)
+        };
+        if keysyms.is_null() {
+            return None;
+        }
+        let per_keycode = per_keycode.max(0) as usize;
+        // SAFETY: Xlib returned `per_keycode` keysyms for each keycode from `min` to `max`.
+        let mapping = unsafe { std::slice::from_raw_parts(keysyms, (max - min + 1) as usize * per_keycode) };
+        let unused = (per_keycode > 0)
+            .then(|| {
+                mapping
+                    .chunks(per_keycode)
+                    .rposition(|bound| bound.iter().all(|keysym| *keysym == NO_SYMBOL))
+            })
+            .flatten()
+            .map(|offset| (min as usize + offset) as xlib::KeyCode);
+        // SAFETY: `keysyms` was allocated by Xlib.
+        unsafe { xlib::XFree(keysyms.cast()) };
+        unused
+    }
+
+    /// Makes `keycode` produce `keysym` with and without Shift; `NO_SYMBOL` unbinds it again.
+    fn bind(&self, keycode: xlib::KeyCode, keysym: c_ulong) {
+        let mut keysyms = [keysym, keysym];
+        // SAFETY: the display is open and `keysyms` holds two keysyms for the one keycode.
+        unsafe {
+            xlib::XChangeKeyboardMapping(self.0, keycode.into(), 2, keysyms.as_mut_ptr(), 1);
+            xlib::XSync(self.0, xlib::False);
+        }
+    }
+
+    fn send(&self, keycode: xlib::KeyCode, pressed: bool) {
+        // SAFETY: the display is open; XTest only needs a valid keycode.
+        unsafe {
+            xtest::XTestFakeKeyEvent(self.0, keycode.into(), pressed.into(), 0);
+            xlib::XFlush(self.0);
+        }
+    }
+}
+
+impl Drop for Display {
+    fn drop(&mut self) {
+        // SAFETY: the display was opened by `open` and is closed once.
+        unsafe { xlib::XCloseDisplay(self.0) };
+    }
+}
+
+/// The window that has the keyboard focus, according to the window manager.
+pub fn active_window() -> Result<ActiveWindow> {
+    let display = Display::open()?;
+    // SAFETY: the display is open.
+    let root = unsafe { xlib::XDefaultRootWindow(display.0) };
+    let (_, data) = display
+        .property(root, display.atom(c"_NET_ACTIVE_WINDOW"), xlib::XA_WINDOW)
+        .ok_or("The window manager does not report the active window")?;
+    let id = data
+        .get(..size_of::<c_ulong>())
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(c_ulong::from_ne_bytes)
+        .filter(|id| *id != 0)
+        .ok_or("No window is active")?;
+    let title = display
+        .property(id, display.atom(c"_NET_WM_NAME"), display.atom(c"UTF8_STRING"))
+        .or_else(|| display.property(id, xlib::XA_WM_NAME, xlib::XA_STRING))
+        .map(|(_, bytes)| String::from_utf8_lossy(&bytes).into_owned())
+        .unwrap_or_default();
+    Ok(ActiveWindow { id, title })
+}
+
+/// Types through the XTest extension into whichever window has the focus.
+pub struct XTestOutput {
+    display: Display,
+    /// The spare keycode bound to a character the layout lacks, and that character's keysym.
+    scratch: Option<(xlib::KeyCode, c_ulong)>,
+}
+
+impl XTestOutput {
+    pub fn open() -> Result<Self> {
+        let output = Self {
+            display: Display::open()?,
+            scratch: None,
+        };
+        for keysym in MODIFIER_KEYSYMS {
+            if let Some(keycode) = output.display.keycode(keysym.into()) {
+                output.display.send(keycode, false);
+            }
+        }
+        Ok(output)
+    }
+
+    /// Asks the window manager to give `window` the focus again, for example after a selection dialog.
+    pub fn activate(&self, window: &ActiveWindow) {
+        let display = &self.display;
+        // SAFETY: the event is fully initialised before it is sent to the open display.
+        unsafe {
+            let root = xlib::XDefaultRootWindow(display.0);
+            let mut event: xlib::XEvent = std::mem::zeroed();
+            event.client_message.type_ = xlib::ClientMessage;
+            event.client_message.window = window.id;
+            event.client_message.message_type = display.atom(c"_NET_ACTIVE_WINDOW");
+            event.client_message.format = 32;
+            // Source indication 2: the request comes from a pager-like tool acting for the user.
+            event.client_message.data.set_long(0, 2);
+            xlib::XSendEvent(
+                display.0,
+                root,
+                xlib::False,
+                xlib::SubstructureRedirectMask | xlib::SubstructureNotifyMask,
+                &mut event,
+            );
+            xlib::XFlush(display.0);
+        }
+    }
+
+    /// Binds `keysym` to a keycode the layout leaves empty, reusing the one bound last time.
+    fn scratch_keycode(&mut self, keysym: c_ulong) -> Option<xlib::KeyCode> {
+        if let Some((keycode, bound)) = self.scratch {
+            if bound == keysym {
+                return Some(keycode);
+            }
+            // Clients look the new binding up only when they handle the mapping change, so let them finish
+            // reading the previous character first.
+            std::thread::sleep(REMAP_DELAY);
+        }
+        let keycode = match self.scratch {
+            Some((keycode, _)) => keycode,
+            None => self.display.unused_keycode()?,
+        };
+        self.display.bind(keycode, keysym);
+        self.scratch = Some((keycode, keysym));
+        std::thread::sleep(REMAP_DELAY);
+        Some(keycode)
+    }
+}
+
+impl Output for XTestOutput {
+    fn key(&mut self, key: Key, modifiers: Modifiers) -> Result<()> {
+        let keysym = keysym_for(key);
+        let level3_shift = self.display.keycode(keysym::XK_ISO_Level3_Shift.into());
+        // Characters on the shifted levels of their key, like `A`, `!` or `@` on AltGr+Q, need Shift or AltGr.
+        let found = self
+            .display
+            .keycode(keysym)
+            .and_then(|keycode| self.display.level(keycode, keysym))
+            .filter(|(_, level)| *level < 2 || level3_shift.is_some());
+        let (keycode, level) = match found {
+            Some(found) => found,
+            // Like xdotool, type characters the layout lacks through a spare keycode bound to them for the occasion.
+            None => (
+                self.scratch_keycode(keysym)
+                    .ok_or_else(|| format!("The keyboard layout has no key for {key:?}"))?,
+                0,
+            ),
+        };
+        let is_char = matches!(key, Key::Char(_));
+        let held = [
+            (
+                modifiers.shift || (is_char && level % 2 == 1),
+                self.display.keycode(keysym::XK_Shift_L.into()),
+            ),
+            (is_char && level >= 2, level3_shift),
+            (modifiers.control, self.display.keycode(keysym::XK_Control_L.into())),
+            (modifiers.alt, self.display.keycode(keysym::XK_Alt_L.into())),
+            (modifiers.meta, self.display.keycode(keysym::XK_Super_L.into())),
+        ]
+        .into_iter()
+        .filter(|(pressed, _)| *pressed)
+        .filter_map(|(_, modifier)| modifier)
+        .collect::<Vec<_>>();
+        for modifier in &held {
+            self.display.send(*modifier, true);
+        }
+        self.display.send(keycode, true);
+        self.display.send(keycode, false);
+        for modifier in held.iter().rev() {
+            self.display.send(*modifier, false);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for XTestOutput {
+    fn drop(&mut self) {
+        if let Some((keycode, _)) = self.scratch {
+            std::thread::sleep(REMAP_DELAY);
+            self.display.bind(keycode, NO_SYMBOL);
+        }
+    }
+}
+
+fn keysym_for(key: Key) -> c_ulong {
+    let keysym = match key {
+        // Latin-1 characters are their own keysyms; everything else uses the Unicode keysym range.
+        Key::Char(c) if matches!(c as u32, 0x20..=0x7e | 0xa0..=0xff) => c as u32,
+        Key::Char(c) => 0x0100_0000 + c as u32,
+        Key::Tab => keysym::XK_Tab,
+        Key::Enter => keysym::XK_Return,
+        Key::Space => keysym::XK_space,
+        Key::Backspace => keysym::XK_BackSpace,
+        Key::Delete => keysym::XK_Delete,
+        Key::Insert => keysym::XK_Insert,
+        Key::Escape => keysym::XK_Escape,
+        Key::Up => keysym::XK_Up,
+        Key::Down => keysym::XK_Down,
+        Key::Left => keysym::XK_Left,
+        Key::Right => keysym::XK_Right,
+        Key::Home => keysym::XK_Home,
+        Key::End => keysym::XK_End,
+        Key::PageUp => keysym::XK_Page_Up,
+        Key::PageDown => keysym::XK_Page_Down,
+        Key::Function(number) => keysym::XK_F1 + u32::from(number) - 1,
+    };
+    keysym.into()
+}
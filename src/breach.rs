@@ -0,0 +1,187 @@
+use crate::error::Result;
+use sha1::{Digest, Sha1};
+use std::{
+    cmp::Ordering,
+    fs::{self, File},
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+/// Length of a SHA-1 hash in hex and of the prefixes HIBP names its range files after.
+const HASH_LENGTH: usize = 40;
+const RANGE_PREFIX_LENGTH: usize = 5;
+
+/// A local copy of the Have I Been Pwned SHA-1 password list; passwords never leave the machine.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BreachList {
+    /// One file of `HASH:COUNT` lines sorted by hash, searched with a binary search on disk.
+    Sorted(PathBuf),
+    /// A directory of range files such as `5BAA6.txt` holding `SUFFIX:COUNT` lines.
+    Ranges(PathBuf),
+}
+
+/// What the list says about one password.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lookup {
+    /// Seen this many times in breaches.
+    Breached(u64),
+    NotListed,
+    /// The range file the hash belongs in is missing, as in a partial download.
+    NotChecked,
+}
+
+impl BreachList {
+    /// Opens a sorted hash file, a directory of range files, or any range file inside that directory.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            return Ok(BreachList::Ranges(path.to_path_buf()));
+        }
+        let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("");
+        if stem.len() == RANGE_PREFIX_LENGTH
+            && stem.chars().all(|c| c.is_ascii_hexdigit())
+            && let Some(directory) = path.parent()
+        {
+            return Ok(BreachList::Ranges(directory.to_path_buf()));
+        }
+        let mut first_line = String::new();
+        BufReader::new(File::open(path)?).read_line(&mut first_line)?;
+        if !first_line.trim().is_empty() && parse_line(&first_line).is_none() {
+            return Err(format!("{} is not a list of SHA-1 hashes", path.display()).into());
+        }
+        Ok(BreachList::Sorted(path.to_path_buf()))
+    }
+
+    /// How many times `password` was seen in breaches.
+    pub fn lookup(&self, password: &str) -> Result<Lookup> {
+        self.lookup_hash(&sha1_hex(password))
+    }
+
+    /// Looks up an upper-case hex SHA-1 hash.
+    pub fn lookup_hash(&self, hash: &str) -> Result<Lookup> {
+        let count = match self {
+            BreachList::Sorted(path) => find_sorted(path, hash)?,
+            BreachList::Ranges(directory) => {
+                let (prefix, suffix) = hash.split_at(RANGE_PREFIX_LENGTH);
+                let Some(path) = [format!("{prefix}.txt"), prefix.to_string()]
+                    .into_iter()
+                    .map(|name| directory.join(name))
+                    .find(|path| path.is_file())
+                else {
+                    return Ok(Lookup::NotChecked);
+                };
+                fs::read_to_string(path)?
+                    .lines()
+                    .filter_map(parse_line)
+                    .find(|(listed, _)| listed == suffix)
+                    .map(|(_, count)| count)
+            }
+        };
+        Ok(count.map_or(Lookup::NotListed, Lookup::Breached))
+    }
+}
+
+/// The upper-case hex SHA-1 hash HIBP lists passwords by.
+pub fn sha1_hex(password: &str) -> String {
+    Sha1::digest(password.as_bytes()).iter().map(|byte| format!("{byte:02X}")).collect()
+}
+
+/// Splits a `HASH:COUNT` line; lines without a count are counted once.
+fn parse_line(line: &str) -> Option<(String, u64)> {
+    let line = line.trim();
+    let (hash, count) = line.split_once(':').unwrap_or((line, "1"));
+    if hash.is_empty() || hash.len() > HASH_LENGTH || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((hash.to_ascii_uppercase(), count.trim().parse().unwrap_or(1)))
+}
+
+fn find_sorted(path: &Path, hash: &str) -> Result<Option<u64>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let (mut low, mut high) = (0, reader.get_ref().metadata()?.len());
+    // Every line starting before `low` sorts before `hash`; the first line starting at or after `high` does not.
+    while low < high {
+        let middle = low + (high - low) / 2;
+        match line_at_or_after(&mut reader, middle)? {
+            Some((start, length, line)) if compare(&line, hash) == Ordering::Less => low = start + length,
+            _ => high = middle,
+        }
+    }
+    Ok(line_at_or_after(&mut reader, low)?
+        .and_then(|(_, _, line)| parse_line(&line))
+        .filter(|(listed, _)| listed == hash)
+        .map(|(_, count)| count))
+}
+
+fn compare(line: &str, hash: &str) -> Ordering {
+    let listed = line.split(':').next().unwrap_or("").trim();
+    listed.to_ascii_uppercase().as_str().cmp(hash)
+}
+
+/// The first whole line starting at byte `position` or later, with its start and length in bytes.
+fn line_at_or_after(reader: &mut BufReader<File>, position: u64) -> Result<Option<(u64, u64, String)>> {
+    let mut start = position;
+    if position > 0 {
+        reader.seek(SeekFrom::Start(position - 1))?;
+        let mut skipped = Vec::new();
+        start = position - 1 + reader.read_until(b'\n', &mut skipped)? as u64;
+    } else {
+        reader.seek(SeekFrom::Start(0))?;
+    }
+    let mut line = String::new();
+    let length = reader.read_line(&mut line)? as u64;
+    Ok((length > 0).then_some((start, length, line)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BreachList, Lookup, sha1_hex};
+    use std::{fs, path::PathBuf};
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name)
+    }
+
+    #[test]
+    fn hashes_like_hibp() {
+        assert_eq!(sha1_hex("password"), "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8");
+    }
+
+    #[test]
+    fn finds_every_hash_of_a_sorted_file() {
+        let list = BreachList::open(fixture("hibp-sorted.txt")).unwrap();
+        assert_eq!(list, BreachList::Sorted(fixture("hibp-sorted.txt")));
+        assert_eq!(list.lookup("password").unwrap(), Lookup::Breached(10434004));
+        assert_eq!(list.lookup("123456").unwrap(), Lookup::Breached(52256179));
+        assert_eq!(list.lookup("hunter2").unwrap(), Lookup::Breached(48131));
+        assert_eq!(list.lookup("correct horse battery staple").unwrap(), Lookup::NotListed);
+
+        // Every listed hash, including the first and the last line, is found.
+        let data = fs::read_to_string(fixture("hibp-sorted.txt")).unwrap();
+        for line in data.lines() {
+            let (hash, count) = line.split_once(':').unwrap();
+            assert_eq!(list.lookup_hash(hash).unwrap(), Lookup::Breached(count.parse().unwrap()), "{hash}");
+        }
+        assert_eq!(list.lookup_hash(&"0".repeat(40)).unwrap(), Lookup::NotListed);
+        assert_eq!(list.lookup_hash(&"F".repeat(40)).unwrap(), Lookup::NotListed);
+    }
+
+    #[test]
+    fn reads_range_files() {
+        let list = BreachList::open(fixture("hibp-ranges")).unwrap();
+        assert_eq!(BreachList::open(fixture("hibp-ranges").join("5BAA6.txt")).unwrap(), list);
+        assert_eq!(list.lookup("password").unwrap(), Lookup::Breached(10434004));
+        assert_eq!(list.lookup("Tr0ub4dor&3").unwrap(), Lookup::Breached(3));
+        assert_eq!(
+            list.lookup_hash("5BAA6FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF").unwrap(),
+            Lookup::NotListed
+        );
+        // A partial download is missing most range files.
+        assert_eq!(list.lookup("correct horse battery staple").unwrap(), Lookup::NotChecked);
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_hash_lists() {
+        assert!(BreachList::open(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml")).is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use crate::error::Result;
+use keepass_ng::ChallengeResponseKey;
+use std::rc::Rc;
+
+/// The YubiKey slots that can hold an HMAC-SHA1 challenge-response secret.
+pub const YUBIKEY_SLOTS: [u8; 2] = [1, 2];
+
+/// Answers the HMAC-SHA1 challenge that is part of the master key of KeePassXC-style protected databases.
+///
+/// keepass-ng sends the challenge while the database is opened and again on every save, so a hardware provider
+/// may ask the user to touch the device each time.
+pub trait ChallengeResponseProvider {
+    /// Names the provider in errors, for example "YubiKey slot 2".
+    fn describe(&self) -> String;
+
+    /// The key component keepass-ng challenges while opening or saving.
+    fn key(&self) -> Result<ChallengeResponseKey>;
+}
+
+/// The first YubiKey plugged in, answering with the secret programmed into `slot`.
+pub struct YubiKey {
+    slot: u8,
+}
+
+impl YubiKey {
+    pub fn new(slot: u8) -> Result<Self> {
+        if !YUBIKEY_SLOTS.contains(&slot) {
+            return Err(format!("YubiKey slot {slot} does not exist; use 1 or 2").into());
+        }
+        Ok(Self { slot })
+    }
+}
+
+impl ChallengeResponseProvider for YubiKey {
+    fn describe(&self) -> String {
+        format!("YubiKey slot {}", self.slot)
+    }
+
+    fn key(&self) -> Result<ChallengeResponseKey> {
+        let yubikey = ChallengeResponseKey::get_yubikey(None).map_err(|error| format!("No YubiKey found: {error}"))?;
+        Ok(ChallengeResponseKey::YubikeyChallenge(yubikey, self.slot.to_string()))
+    }
+}
+
+/// The provider for YubiKey `slot`, or none when the master key has no challenge-response part.
+pub fn yubikey_provider(slot: Option<u8>) -> Result<Option<Rc<dyn ChallengeResponseProvider>>> {
+    slot.map(|slot| YubiKey::new(slot).map(|yubikey| Rc::new(yubikey) as Rc<dyn ChallengeResponseProvider>))
+        .transpose()
+}
+
+/// Hands keepass-ng a known HMAC-SHA1 secret to answer the challenge with, the way a YubiKey programmed with it would.
+#[cfg(test)]
+pub struct SoftwareHmacSha1 {
+    secret: zeroize::Zeroizing<Vec<u8>>,
+}
+
+#[cfg(test)]
+impl SoftwareHmacSha1 {
+    pub fn new(secret: &[u8]) -> Self {
+        Self {
+            secret: zeroize::Zeroizing::new(secret.to_vec()),
+        }
+    }
+}
+
+#[cfg(test)]
+impl ChallengeResponseProvider for SoftwareHmacSha1 {
+    fn describe(&self) -> String {
+        "HMAC-SHA1 secret".to_string()
+    }
+
+    fn key(&self) -> Result<ChallengeResponseKey> {
+        let secret = self.secret.iter().map(|byte| format!("{byte:02x}")).collect();
+        Ok(ChallengeResponseKey::LocalChallenge(secret))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChallengeResponseProvider, YubiKey};
+
+    #[test]
+    fn yubikey_slots_are_checked() {
+        assert_eq!(YubiKey::new(2).unwrap().describe(), "YubiKey slot 2");
+        assert!(YubiKey::new(0).is_err());
+        assert!(YubiKey::new(3).is_err());
+    }
+}
@@ -0,0 +1,538 @@
+use crate::{
+    challenge_response, custom_fields::custom_fields, error::Result, keepass::KpDb, password_generator::PasswordProfile,
+    secret::SecretString,
+};
+use clap::{ArgGroup, Args, CommandFactory, Parser, Subcommand};
+use keepass_ng::{
+    DatabaseVersion,
+    db::{Entry, Group, NodePtr, group_get_children, node_is_group, with_node, with_node_mut},
+};
+use std::{
+    ffi::OsString,
+    io::{BufRead, Write},
+};
+use zeroize::Zeroizing;
+
+/// Command-line access to a KeePass database. Without a subcommand mypass starts the GUI.
+#[derive(Parser, Debug)]
+#[command(name = "mypass", version, about)]
+#[command(group(ArgGroup::new("key_without_password").args(["key_file", "yubikey_slot"]).multiple(true)))]
+pub struct Cli {
+    /// The KDBX file to open.
+    #[arg(long, env = "DB_PATH")]
+    db: String,
+    /// A key file that is part of the master key.
+    #[arg(long, env = "KEY_FILE")]
+    key_file: Option<String>,
+    /// Read the master password from the first line of standard input instead of prompting.
+    #[arg(long, conflicts_with = "no_password")]
+    password_stdin: bool,
+    /// Open the database with the key file or the YubiKey alone.
+    #[arg(long, requires = "key_without_password")]
+    no_password: bool,
+    /// The slot of the YubiKey whose HMAC-SHA1 challenge-response is part of the master key.
+    #[arg(long, env = "YUBIKEY_SLOT", value_parser = clap::value_parser!(u8).range(1..=2))]
+    yubikey_slot: Option<u8>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Paths are group titles separated by `/`, starting below the root group; the last part of an entry path is its title.
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List the groups and entries of a group.
+    Ls { path: Option<String> },
+    /// Show the fields of an entry.
+    Show {
+        path: String,
+        /// Print the password instead of asterisks.
+        #[arg(long)]
+        show_password: bool,
+        /// Print protected custom fields instead of asterisks.
+        #[arg(long)]
+        show_protected: bool,
+    },
+    /// Print the password of an entry.
+    GetPassword { path: String },
+    /// Add an entry to an existing group.
+    Add {
+        path: String,
+        #[command(flatten)]
+        fields: EntryFields,
+    },
+    /// Change the fields of an entry.
+    Edit {
+        path: String,
+        /// Rename the entry.
+        #[arg(long)]
+        title: Option<String>,
+        #[command(flatten)]
+        fields: EntryFields,
+    },
+    /// Delete an entry or a group.
+    Rm { path: String },
+    /// Create a group inside an existing group.
+    Mkgroup { path: String },
+}
+
+#[derive(Args, Debug, Default)]
+struct EntryFields {
+    #[arg(long)]
+    username: Option<String>,
+    #[arg(long)]
+    url: Option<String>,
+    #[arg(long)]
+    notes: Option<String>,
+    /// Comma-separated tags; replaces the existing tags.
+    #[arg(long)]
+    tags: Option<String>,
+    /// Generate the password with the default generator settings.
+    #[arg(long, conflicts_with = "ask_password")]
+    generate: bool,
+    /// Prompt for the password.
+    #[arg(long)]
+    ask_password: bool,
+}
+
+impl EntryFields {
+    fn changes_nothing(&self) -> bool {
+        self.username.is_none() && self.url.is_none() && self.notes.is_none() && self.tags.is_none() && !self.generate && !self.ask_password
+    }
+}
+
+/// Stands in for passwords and protected fields, whatever their length.
+const MASK: &str = "••••••••";
+
+/// Whether the first process argument is a subcommand or one of our options. Anything else, like a file name passed
+/// by "Open with" that happens to start with `-`, or the `-psn_…` argument of macOS, is left to the window.
+pub fn is_cli_invocation(args: &[OsString]) -> bool {
+    let Some(first) = args.first().and_then(|arg| arg.to_str()) else {
+        return false;
+    };
+    let cli = Cli::command();
+    if first == "help" || cli.get_subcommands().any(|subcommand| subcommand.get_name() == first) {
+        return true;
+    }
+    if let Some(long) = first.strip_prefix("--") {
+        let name = long.split_once('=').map_or(long, |(name, _)| name);
+        return ["help", "version"].contains(&name) || cli.get_arguments().any(|arg| arg.get_long() == Some(name));
+    }
+    match first.strip_prefix('-').map(|short| short.chars().collect::<Vec<_>>()).as_deref() {
+        Some(['h' | 'V']) => true,
+        Some([short]) => cli.get_arguments().any(|arg| arg.get_short() == Some(*short)),
+        _ => false,
+    }
+}
+
+/// Runs the command line given to the process and returns its exit code.
+pub fn run() -> i32 {
+    #[cfg(windows)]
+    attach_parent_console();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    let cli = Cli::parse();
+    match execute_cli(&cli) {
+        Ok(()) => 0,
+        Err(error) => {
+            eprintln!("mypass: {error}");
+            1
+        }
+    }
+}
+
+/// Windows starts the GUI-subsystem binary without a console, so without this the output and the password prompt of
+/// a command typed into a terminal would go nowhere.
+#[cfg(windows)]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // SAFETY: AttachConsole takes no pointers; it fails harmlessly when there is no parent console or one is attached.
+    unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+}
+
+fn execute_cli(cli: &Cli) -> Result<()> {
+    let password = if cli.no_password {
+        None
+    } else if cli.password_stdin {
+        let mut line = Zeroizing::new(String::new());
+        std::io::stdin().lock().read_line(&mut line)?;
+        Some(SecretString::from(line.trim_end_matches(['\r', '\n'])))
+    } else {
+        Some(SecretString::new(rpassword::prompt_password("Master password: ")?))
+    };
+    let mut kpdb = KpDb::open(
+        &cli.db,
+        password.as_ref().map(SecretString::expose),
+        cli.key_file.as_deref(),
+        challenge_response::yubikey_provider(cli.yubikey_slot)?,
+    )?;
+    drop(password);
+
+    let ask_password = || -> Result<SecretString> {
+        let password = SecretString::new(rpassword::prompt_password("Entry password: ")?);
+        if password != SecretString::new(rpassword::prompt_password("Repeat entry password: ")?) {
+            return Err("The passwords do not match".into());
+        }
+        Ok(password)
+    };
+    let mut stdout = std::io::stdout().lock();
+    if execute(&mut kpdb, &cli.command, &mut stdout, &ask_password)? && !kpdb.save(Some(&|_: DatabaseVersion| false))? {
+        return Err("Open and save the database in the mypass window once to upgrade it to KDBX 4.1 before changing it here".into());
+    }
+    Ok(())
+}
+
+/// Runs one command against an open database and reports whether it changed anything.
+fn execute(kpdb: &mut KpDb, command: &Command, out: &mut dyn Write, ask_password: &dyn Fn() -> Result<SecretString>) -> Result<bool> {
+    match command {
+        Command::Ls { path } => {
+            let node = find_node(kpdb, path.as_deref().unwrap_or(""))?;
+            if !node_is_group(&node) {
+                writeln!(out, "{}", title_of(&node))?;
+                return Ok(false);
+            }
+            for child in group_get_children(&node).unwrap_or_default() {
+                let suffix = if node_is_group(&child) { "/" } else { "" };
+                writeln!(out, "{}{suffix}", title_of(&child))?;
+            }
+            Ok(false)
+        }
+        Command::Show {
+            path,
+            show_password,
+            show_protected,
+        } => {
+            let node = find_entry(kpdb, path)?;
+            with_node::<Entry, _, _>(&node, |entry| -> Result<()> {
+                let password = entry.get_password().unwrap_or("");
+                let shown_password = SecretString::new(if *show_password { password.to_string() } else { MASK.to_string() });
+                writeln!(out, "Title: {}", entry.get_title().unwrap_or(""))?;
+                writeln!(out, "Username: {}", entry.get_username().unwrap_or(""))?;
+                writeln!(out, "Password: {}", shown_password.expose())?;
+                writeln!(out, "URL: {}", entry.get_url().unwrap_or(""))?;
+                writeln!(out, "Tags: {}", entry.get_tags().join(", "))?;
+                writeln!(out, "Notes: {}", entry.get_notes().unwrap_or(""))?;
+                for field in custom_fields(entry) {
+                    let value = if field.protected && !*show_protected {
+                        SecretString::from(MASK)
+                    } else {
+                        field.value
+                    };
+                    writeln!(out, "{}: {}", field.name, value.expose())?;
+                }
+                Ok(())
+            })
+            .transpose()?;
+            Ok(false)
+        }
+        Command::GetPassword { path } => {
+            let node = find_entry(kpdb, path)?;
+            let password =
+                with_node::<Entry, _, _>(&node, |entry| SecretString::from(entry.get_password().unwrap_or(""))).unwrap_or_default();
+            writeln!(out, "{}", password.expose())?;
+            Ok(false)
+        }
+        Command::Add { path, fields } => {
+            let (parent_path, title) = split_last(path)?;
+            let parent = find_node(kpdb, &parent_path.join("/"))?;
+            if !node_is_group(&parent) {
+                return Err(format!("{} is not a group", parent_path.join("/")).into());
+            }
+            if kpdb.get_item(&split_path(path)).is_some() {
+                return Err(format!("{path} already exists").into());
+            }
+            let password = new_password(fields, ask_password)?;
+            let node = kpdb.create_new_entry(parent.borrow().get_uuid())?;
+            with_node_mut::<Entry, _, _>(&node, |entry| {
+                entry.set_title(Some(title));
+                apply_fields(entry, fields, password.as_ref());
+            });
+            Ok(true)
+        }
+        Command::Edit { path, title, fields } => {
+            if title.is_none() && fields.changes_nothing() {
+                return Err("Nothing to change; give at least one field option such as --username".into());
+            }
+            let node = find_entry(kpdb, path)?;
+            let password = new_password(fields, ask_password)?;
+            with_node_mut::<Entry, _, _>(&node, |entry| {
+                if let Some(title) = title {
+                    entry.set_title(Some(title));
+                }
+                apply_fields(entry, fields, password.as_ref());
+                entry.update_history();
+            });
+            kpdb.mark_data_changed();
+            Ok(true)
+        }
+        Command::Rm { path } => {
+            let node = find_node(kpdb, path)?;
+            if node.borrow().get_parent().is_none() {
+                return Err("The database root cannot be deleted".into());
+            }
+            kpdb.delete_node(node.borrow().get_uuid())?;
+            Ok(true)
+        }
+        Command::Mkgroup { path } => {
+            let (parent_path, title) = split_last(path)?;
+            let parent = find_node(kpdb, &parent_path.join("/"))?;
+            if !node_is_group(&parent) {
+                return Err(format!("{} is not a group", parent_path.join("/")).into());
+            }
+            if kpdb.get_item(&split_path(path)).is_some() {
+                return Err(format!("{path} already exists").into());
+            }
+            let group = kpdb.create_new_group(parent.borrow().get_uuid())?;
+            with_node_mut::<Group, _, _>(&group, |group| group.set_title(Some(title)));
+            Ok(true)
+        }
+    }
+}
+
+fn split_path(path: &str) -> Vec<&str> {
+    path.split('/').map(str::trim).filter(|part| !part.is_empty()).collect()
+}
+
+fn split_last(path: &str) -> Result<(Vec<&str>, &str)> {
+    let mut parts = split_path(path);
+    let title = parts.pop().ok_or("The path needs a title")?;
+    Ok((parts, title))
+}
+
+fn find_node(kpdb: &KpDb, path: &str) -> Result<NodePtr> {
+    let parts = split_path(path);
+    if parts.is_empty() {
+        return kpdb.get_root().ok_or_else(|| "No database".into());
+    }
+    kpdb.get_item(&parts).ok_or_else(|| format!("{path} was not found").into())
+}
+
+fn find_entry(kpdb: &KpDb, path: &str) -> Result<NodePtr> {
+    let node = find_node(kpdb, path)?;
+    if node_is_group(&node) {
+        return Err(format!("{path} is a group, not an entry").into());
+    }
+    Ok(node)
+}
+
+fn title_of(node: &NodePtr) -> String {
+    node.borrow().get_title().unwrap_or("").to_string()
+}
+
+fn new_password(fields: &EntryFields, ask_password: &dyn Fn() -> Result<SecretString>) -> Result<Option<SecretString>> {
+    if fields.generate {
+        return Ok(Some(SecretString::new(PasswordProfile::default().generate()?)));
+    }
+    if fields.ask_password {
+        return ask_password().map(Some);
+    }
+    Ok(None)
+}
+
+fn apply_fields(entry: &mut Entry, fields: &EntryFields, password: Option<&SecretString>) {
+    if let Some(username) = fields.username.as_deref() {
+        entry.set_username(Some(username));
+    }
+    if let Some(url) = fields.url.as_deref() {
+        entry.set_url(Some(url));
+    }
+    if let Some(notes) = fields.notes.as_deref() {
+        entry.set_notes(Some(notes));
+    }
+    if let Some(tags) = fields.tags.as_deref() {
+        entry.get_tags_mut().clear();
+        entry
+            .get_tags_mut()
+            .extend(tags.split(',').map(str::trim).filter(|tag| !tag.is_empty()).map(str::to_owned));
+    }
+    if let Some(password) = password {
+        entry.set_password(Some(password.expose()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cli, Command, EntryFields, execute, is_cli_invocation};
+    use crate::{
+        custom_fields::{CustomField, set_custom_fields},
+        error::Result,
+        keepass::KpDb,
+        secret::SecretString,
+    };
+    use clap::Parser;
+    use keepass_ng::db::{Entry, with_node_mut};
+    use std::ffi::OsString;
+
+    fn run(kpdb: &mut KpDb, command: Command) -> Result<String> {
+        let mut out = Vec::new();
+        execute(kpdb, &command, &mut out, &|| Ok(SecretString::from("s3cret")))?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn manages_groups_and_entries_by_path() {
+        let mut kpdb = KpDb::new();
+        run(&mut kpdb, Command::Mkgroup { path: "Deploy".into() }).unwrap();
+        assert!(
+            run(
+                &mut kpdb,
+                Command::Mkgroup {
+                    path: "Missing/Group".into()
+                }
+            )
+            .is_err()
+        );
+        let fields = EntryFields {
+            username: Some("ci".into()),
+            ask_password: true,
+            ..EntryFields::default()
+        };
+        run(
+            &mut kpdb,
+            Command::Add {
+                path: "Deploy/Registry".into(),
+                fields,
+            },
+        )
+        .unwrap();
+        assert!(
+            run(
+                &mut kpdb,
+                Command::Add {
+                    path: "Deploy/Registry".into(),
+                    fields: EntryFields::default()
+                }
+            )
+            .is_err()
+        );
+
+        assert_eq!(run(&mut kpdb, Command::Ls { path: None }).unwrap(), "Deploy/\n");
+        assert_eq!(
+            run(
+                &mut kpdb,
+                Command::Ls {
+                    path: Some("Deploy".into())
+                }
+            )
+            .unwrap(),
+            "Registry\n"
+        );
+        assert_eq!(
+            run(
+                &mut kpdb,
+                Command::GetPassword {
+                    path: "Deploy/Registry".into()
+                }
+            )
+            .unwrap(),
+            "s3cret\n"
+        );
+        let node = kpdb.get_item(&["Deploy", "Registry"]).unwrap();
+        with_node_mut::<Entry, _, _>(&node, |entry| {
+            set_custom_fields(
+                entry,
+                &[CustomField {
+                    name: "API key".into(),
+                    value: SecretString::from("k-123"),
+                    protected: true,
+                }],
+            )
+        })
+        .unwrap();
+        let show = |show_protected| Command::Show {
+            path: "Deploy/Registry".into(),
+            show_password: false,
+            show_protected,
+        };
+        let shown = run(&mut kpdb, show(false)).unwrap();
+        assert!(shown.contains("Username: ci") && !shown.contains("s3cret"));
+        assert!(shown.contains("Password: ••••••••") && shown.contains("API key: ••••••••") && !shown.contains("k-123"));
+        assert!(run(&mut kpdb, show(true)).unwrap().contains("API key: k-123"));
+        assert!(
+            run(
+                &mut kpdb,
+                Command::Edit {
+                    path: "Deploy/Registry".into(),
+                    title: None,
+                    fields: EntryFields::default(),
+                },
+            )
+            .is_err()
+        );
+
+        let fields = EntryFields {
+            generate: true,
+            ..EntryFields::default()
+        };
+        run(
+            &mut kpdb,
+            Command::Edit {
+                path: "Deploy/Registry".into(),
+                title: Some("Registry token".into()),
+                fields,
+            },
+        )
+        .unwrap();
+        let password = run(
+            &mut kpdb,
+            Command::GetPassword {
+                path: "Deploy/Registry token".into(),
+            },
+        )
+        .unwrap();
+        assert_ne!(password, "s3cret\n");
+
+        run(
+            &mut kpdb,
+            Command::Rm {
+                path: "Deploy/Registry token".into(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            run(
+                &mut kpdb,
+                Command::Ls {
+                    path: Some("Deploy".into())
+                }
+            )
+            .unwrap(),
+            ""
+        );
+        assert!(run(&mut kpdb, Command::Rm { path: String::new() }).is_err());
+    }
+
+    #[test]
+    fn parses_each_kind_of_master_key() {
+        let parse = |args: &[&str]| Cli::try_parse_from(["mypass", "--db", "team.kdbx"].iter().chain(args).chain(&["ls", "Deploy"]));
+        let password_only = parse(&[]).unwrap();
+        assert!(!password_only.no_password && password_only.key_file.is_none() && password_only.yubikey_slot.is_none());
+        let key_file_only = parse(&["--key-file", "team.key", "--no-password"]).unwrap();
+        assert_eq!(
+            (key_file_only.no_password, key_file_only.key_file.as_deref()),
+            (true, Some("team.key"))
+        );
+        let yubikey_only = parse(&["--yubikey-slot", "2", "--no-password"]).unwrap();
+        assert_eq!((yubikey_only.no_password, yubikey_only.yubikey_slot), (true, Some(2)));
+        assert!(parse(&["--no-password"]).is_err());
+        assert!(parse(&["--yubikey-slot", "3"]).is_err());
+    }
+
+    #[test]
+    fn only_subcommands_and_options_start_the_command_line() {
+        let args = |args: &[&str]| args.iter().map(OsString::from).collect::<Vec<_>>();
+        assert!(!is_cli_invocation(&args(&[])));
+        assert!(!is_cli_invocation(&args(&["/home/me/Passwords.kdbx"])));
+        assert!(is_cli_invocation(&args(&["ls"])));
+        assert!(is_cli_invocation(&args(&["get-password", "Deploy/Registry"])));
+        assert!(is_cli_invocation(&args(&["--db", "team.kdbx", "ls"])));
+        assert!(is_cli_invocation(&args(&["--help"])));
+        assert!(is_cli_invocation(&args(&["--db=team.kdbx", "ls"])));
+        assert!(is_cli_invocation(&args(&["-V"])));
+        assert!(!is_cli_invocation(&args(&["-backup.kdbx"])));
+        assert!(!is_cli_invocation(&args(&["-psn_0_1234567"])));
+        assert!(!is_cli_invocation(&args(&["/home/me/ls", "--help"])));
+    }
+}
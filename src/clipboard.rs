@@ -0,0 +1,68 @@
+use crate::error::Result;
+use std::{
+    cell::{Cell, RefCell},
+    hash::{DefaultHasher, Hash, Hasher},
+    time::Duration,
+};
+
+thread_local! {
+    static CLIPBOARD: RefCell<Option<arboard::Clipboard>> = const { RefCell::new(None) };
+    // Generation and fingerprint of the last value we copied; the value itself is not kept.
+    static COPIED: Cell<Option<(u64, u64)>> = const { Cell::new(None) };
+    static GENERATION: Cell<u64> = const { Cell::new(0) };
+}
+
+fn with_clipboard<T>(f: impl FnOnce(&mut arboard::Clipboard) -> std::result::Result<T, arboard::Error>) -> Result<T> {
+    CLIPBOARD.with(|clipboard| {
+        let mut clipboard = clipboard.borrow_mut();
+        if clipboard.is_none() {
+            *clipboard = Some(arboard::Clipboard::new()?);
+        }
+        let clipboard = clipboard.as_mut().ok_or("Clipboard is not available")?;
+        Ok(f(clipboard)?)
+    })
+}
+
+fn fingerprint(text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    text.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Copies `text` and, when `clear_after` is set, clears it again later unless something else was copied meanwhile.
+pub fn copy_text(text: &str, clear_after: Option<Duration>) -> Result<()> {
+    with_clipboard(|clipboard| clipboard.set_text(text))?;
+    let generation = GENERATION.get() + 1;
+    GENERATION.set(generation);
+    COPIED.set(Some((generation, fingerprint(text))));
+    if let Some(delay) = clear_after.filter(|delay| !delay.is_zero()) {
+        std::thread::spawn(move || {
+            std::thread::sleep(delay);
+            wxdragon::call_after(Box::new(move || {
+                clear_if_unchanged(generation);
+            }));
+        });
+    }
+    Ok(())
+}
+
+/// Clears the clipboard if it still holds the value copied as `generation`.
+pub fn clear_if_unchanged(generation: u64) -> bool {
+    let Some((copied_generation, copied_fingerprint)) = COPIED.get() else {
+        return false;
+    };
+    if copied_generation != generation {
+        return false;
+    }
+    COPIED.set(None);
+    let still_ours = with_clipboard(|clipboard| clipboard.get_text()).is_ok_and(|text| fingerprint(&text) == copied_fingerprint);
+    if still_ours && let Err(error) = with_clipboard(|clipboard| clipboard.clear()) {
+        log::warn!("Could not clear the clipboard: {error}");
+    }
+    still_ours
+}
+
+/// Clears the clipboard right away if it still holds a value we copied.
+pub fn clear_now() -> bool {
+    COPIED.get().is_some_and(|(generation, _)| clear_if_unchanged(generation))
+}
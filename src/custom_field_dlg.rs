@@ -0,0 +1,88 @@
+use crate::custom_fields::{CustomField, validate_name};
+use crate::secret::SecretString;
+use wxdragon::{
+    BoxSizer, Button, ButtonEvents, CheckBox, Dialog, FlexGridSizer, MessageDialog, MessageDialogStyle, Orientation, Size, SizerFlag,
+    StaticText, TextCtrl, WxWidget,
+};
+
+/// Adds a field when `editing` is `None`, otherwise edits field `editing` of `fields`, including its name.
+pub fn show(parent: &dyn WxWidget, fields: &[CustomField], editing: Option<usize>) -> Option<CustomField> {
+    let current = editing.and_then(|index| fields.get(index));
+    let title = if current.is_some() { "Edit field" } else { "Add field" };
+    let dialog = Dialog::builder(parent, title).with_size(520, 300).build();
+    dialog.set_min_size(Size::new(420, 260));
+    let grid = FlexGridSizer::builder(0, 2).with_vgap(8).with_hgap(8).build();
+    grid.add_growable_col(1, 1);
+
+    let protected = current.is_some_and(|field| field.protected);
+    let name = TextCtrl::builder(&dialog)
+        .with_value(current.map(|field| field.name.as_str()).unwrap_or(""))
+        .build();
+    let value = TextCtrl::builder(&dialog)
+        .with_value(current.map(|field| field.value.expose()).unwrap_or(""))
+        .build();
+    value.set_password_mode(protected);
+    let reveal = CheckBox::builder(&dialog).with_label("Show value").with_value(!protected).build();
+    reveal.enable(protected);
+    let protect = CheckBox::builder(&dialog)
+        .with_label("Protect in memory")
+        .with_value(protected)
+        .build();
+    protect.set_tooltip("Store the value as a protected KDBX field and mask it on screen");
+    grid.add(&StaticText::builder(&dialog).with_label("Name").build(), 0, SizerFlag::All, 4);
+    grid.add(&name, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    grid.add(&StaticText::builder(&dialog).with_label("Value").build(), 0, SizerFlag::All, 4);
+    grid.add(&value, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    grid.add(&StaticText::builder(&dialog).with_label("").build(), 0, SizerFlag::All, 4);
+    let flags = BoxSizer::builder(Orientation::Horizontal).build();
+    flags.add(&protect, 0, SizerFlag::All, 0);
+    flags.add(&reveal, 0, SizerFlag::Left, 16);
+    grid.add_sizer(&flags, 1, SizerFlag::All | SizerFlag::Expand, 4);
+
+    protect.on_toggled(move |event| {
+        let protected = event.is_checked();
+        reveal.set_value(!protected);
+        reveal.enable(protected);
+        value.set_password_mode(protected);
+    });
+    reveal.on_toggled(move |event| value.set_password_mode(!event.is_checked()));
+
+    let root = BoxSizer::builder(Orientation::Vertical).build();
+    root.add_sizer(&grid, 1, SizerFlag::All | SizerFlag::Expand, 12);
+    let actions = BoxSizer::builder(Orientation::Horizontal).build();
+    let spacer = StaticText::builder(&dialog).with_label("").build();
+    let cancel = Button::builder(&dialog).with_id(wxdragon::ID_CANCEL).with_label("Cancel").build();
+    let ok = Button::builder(&dialog).with_label("OK").build();
+    actions.add(&spacer, 1, SizerFlag::Expand, 0);
+    actions.add(&cancel, 0, SizerFlag::All, 4);
+    actions.add(&ok, 0, SizerFlag::All, 4);
+    root.add_sizer(&actions, 0, SizerFlag::All | SizerFlag::Expand, 8);
+    dialog.set_sizer(root, true);
+    dialog.set_escape_id(wxdragon::ID_CANCEL);
+
+    let dialog_for_cancel = dialog;
+    cancel.on_click(move |_| dialog_for_cancel.end_modal(wxdragon::ID_CANCEL));
+    let dialog_for_ok = dialog;
+    let fields = fields.to_vec();
+    ok.on_click(move |_| {
+        if let Err(problem) = validate_name(&fields, &name.get_value(), editing) {
+            MessageDialog::builder(&dialog_for_ok, &problem, title)
+                .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconWarning)
+                .build()
+                .show_modal();
+            return;
+        }
+        dialog_for_ok.end_modal(wxdragon::ID_OK);
+    });
+
+    dialog.center();
+    let result = dialog.show_modal();
+    let field = CustomField {
+        name: name.get_value().trim().to_string(),
+        value: SecretString::new(value.get_value()),
+        protected: protect.get_value(),
+    };
+    value.set_value("");
+    dialog.destroy();
+    (result == wxdragon::ID_OK).then_some(field)
+}
@@ -0,0 +1,122 @@
+use crate::secret::SecretString;
+use keepass_ng::db::{Entry, Value};
+
+/// Field names KDBX reserves for the standard entry fields.
+pub const STANDARD_FIELDS: [&str; 5] = ["Title", "UserName", "Password", "URL", "Notes"];
+
+/// A custom string field of an entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CustomField {
+    pub name: String,
+    pub value: SecretString,
+    /// Stored as a KDBX protected value, encrypted in memory and in the file.
+    pub protected: bool,
+}
+
+/// The custom string fields of `entry`, sorted by name.
+pub fn custom_fields(entry: &Entry) -> Vec<CustomField> {
+    let mut fields = entry
+        .fields
+        .iter()
+        .filter(|(name, _)| !STANDARD_FIELDS.contains(&name.as_str()))
+        .filter(|(_, value)| !matches!(value, Value::Bytes(_)))
+        .map(|(name, value)| CustomField {
+            name: name.clone(),
+            value: SecretString::from(entry.get(name).unwrap_or("")),
+            protected: matches!(value, Value::Protected(_)),
+        })
+        .collect::<Vec<_>>();
+    fields.sort_by(|left, right| left.name.cmp(&right.name));
+    fields
+}
+
+/// Replaces all custom string fields of `entry` with `fields`; the standard fields are left alone.
+pub fn set_custom_fields(entry: &mut Entry, fields: &[CustomField]) {
+    entry
+        .fields
+        .retain(|name, value| STANDARD_FIELDS.contains(&name.as_str()) || matches!(value, Value::Bytes(_)));
+    for field in fields {
+        let value = if field.protected {
+            Value::Protected(field.value.expose().as_bytes().into())
+        } else {
+            Value::Unprotected(field.value.expose().to_string())
+        };
+        entry.fields.insert(field.name.clone(), value);
+    }
+}
+
+/// Checks a new or renamed field against the other fields of the entry; `replacing` is the index being edited.
+pub fn validate_name(fields: &[CustomField], name: &str, replacing: Option<usize>) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("The field needs a name.".to_string());
+    }
+    if STANDARD_FIELDS.iter().any(|standard| standard.eq_ignore_ascii_case(name)) {
+        return Err(format!("{name} is a standard field."));
+    }
+    if fields
+        .iter()
+        .enumerate()
+        .any(|(index, field)| Some(index) != replacing && field.name == name)
+    {
+        return Err(format!("A field named {name} already exists."));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CustomField, custom_fields, set_custom_fields, validate_name};
+    use crate::keepass::KpDb;
+    use keepass_ng::db::{Entry, with_node_mut};
+
+    #[test]
+    fn custom_fields_round_trip_with_protection() {
+        let mut kpdb = KpDb::new();
+        let root = kpdb.get_root().unwrap().borrow().get_uuid();
+        let node = kpdb.create_new_entry(root).unwrap();
+        with_node_mut::<Entry, _, _>(&node, |entry| {
+            entry.set_title(Some("Cloud"));
+            entry.set_password(Some("secret"));
+            let fields = vec![
+                CustomField {
+                    name: "Region".to_string(),
+                    value: "eu-west-1".into(),
+                    protected: false,
+                },
+                CustomField {
+                    name: "API key".to_string(),
+                    value: "AKIA123".into(),
+                    protected: true,
+                },
+            ];
+            set_custom_fields(entry, &fields);
+            let stored = custom_fields(entry);
+            assert_eq!(
+                stored.iter().map(|field| field.name.as_str()).collect::<Vec<_>>(),
+                ["API key", "Region"]
+            );
+            assert!(stored[0].protected && !stored[1].protected);
+            assert_eq!(stored[0].value.expose(), "AKIA123");
+
+            set_custom_fields(entry, &stored[1..]);
+            assert_eq!(custom_fields(entry).len(), 1);
+            assert_eq!(entry.get_title(), Some("Cloud"));
+            assert_eq!(entry.get_password(), Some("secret"));
+        });
+    }
+
+    #[test]
+    fn names_must_be_unique_and_not_standard() {
+        let fields = vec![CustomField {
+            name: "PIN".to_string(),
+            value: "1234".into(),
+            protected: true,
+        }];
+        assert!(validate_name(&fields, "Recovery codes", None).is_ok());
+        assert!(validate_name(&fields, "PIN", None).is_err());
+        assert!(validate_name(&fields, "PIN", Some(0)).is_ok());
+        assert!(validate_name(&fields, "password", None).is_err());
+        assert!(validate_name(&fields, "  ", None).is_err());
+    }
+}
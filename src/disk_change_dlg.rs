@@ -0,0 +1,75 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use wxdragon::{BoxSizer, Button, ButtonEvents, Dialog, Orientation, SizerFlag, StaticText, WxWidget};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiskChangeAction {
+    /// Drop the in-memory database and read the file again.
+    Reload,
+    /// Merge the file into the in-memory database.
+    Merge,
+    /// Keep the in-memory database and write it over the file.
+    Overwrite,
+}
+
+thread_local! {
+    static OPEN: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether the question is already on screen, so the file watcher does not ask twice.
+pub fn is_open() -> bool {
+    OPEN.with(Cell::get)
+}
+
+/// Asks what to do about a database file another program changed; `None` when the user cancels.
+/// `saving` offers to overwrite the file, otherwise the user can only ignore the change for now.
+pub fn ask(parent: &dyn WxWidget, database_path: &str, has_unsaved_changes: bool, saving: bool) -> Option<DiskChangeAction> {
+    let dialog = Dialog::builder(parent, "Database changed on disk").with_size(560, 240).build();
+    let root = BoxSizer::builder(Orientation::Vertical).build();
+    let mut message = format!("{database_path}\nwas changed by another program since mypass opened it.");
+    if has_unsaved_changes {
+        message.push_str("\n\nReload discards your unsaved changes; Merge keeps both.");
+    }
+    if saving {
+        message.push_str("\nOverwrite replaces the other program's changes with yours.");
+    }
+    root.add(&StaticText::builder(&dialog).with_label(&message).build(), 0, SizerFlag::All, 12);
+
+    let actions = BoxSizer::builder(Orientation::Horizontal).build();
+    let spacer = StaticText::builder(&dialog).with_label("").build();
+    actions.add(&spacer, 1, SizerFlag::Expand, 0);
+    let cancel_label = if saving { "Cancel" } else { "Ignore" };
+    let cancel = Button::builder(&dialog)
+        .with_id(wxdragon::ID_CANCEL)
+        .with_label(cancel_label)
+        .build();
+    actions.add(&cancel, 0, SizerFlag::All, 4);
+    let mut choices = vec![(DiskChangeAction::Reload, "Reload"), (DiskChangeAction::Merge, "Merge")];
+    if saving {
+        choices.push((DiskChangeAction::Overwrite, "Overwrite"));
+    }
+    let chosen = Rc::new(Cell::new(None));
+    for (action, label) in choices {
+        let button = Button::builder(&dialog).with_label(label).build();
+        actions.add(&button, 0, SizerFlag::All, 4);
+        let dialog_for_button = dialog;
+        let chosen_for_button = Rc::clone(&chosen);
+        button.on_click(move |_| {
+            chosen_for_button.set(Some(action));
+            dialog_for_button.end_modal(wxdragon::ID_OK);
+        });
+    }
+    root.add_sizer(&actions, 0, SizerFlag::All | SizerFlag::Expand, 8);
+    dialog.set_sizer(root, true);
+    dialog.set_escape_id(wxdragon::ID_CANCEL);
+
+    let dialog_for_cancel = dialog;
+    cancel.on_click(move |_| dialog_for_cancel.end_modal(wxdragon::ID_CANCEL));
+
+    dialog.center();
+    OPEN.with(|open| open.set(true));
+    let result = dialog.show_modal();
+    OPEN.with(|open| open.set(false));
+    dialog.destroy();
+    if result == wxdragon::ID_OK { chosen.get() } else { None }
+}
@@ -0,0 +1,159 @@
+use crate::error::Result;
+use crate::history::{history_of, versions};
+use crate::keepass::KpDb;
+use keepass_ng::{
+    Uuid,
+    db::{Entry, Group, Node, NodePtr, group_get_children, node_is_group, with_node, with_node_mut},
+};
+
+/// How `duplicate` copies entries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DuplicateOptions {
+    /// Appends " - Copy" to the title of the duplicated entry or group.
+    pub append_copy: bool,
+    /// Replaces username and password with field references to the original entry.
+    pub reference_credentials: bool,
+    pub include_history: bool,
+}
+
+impl Default for DuplicateOptions {
+    fn default() -> Self {
+        Self {
+            append_copy: true,
+            reference_credentials: false,
+            include_history: false,
+        }
+    }
+}
+
+/// A `{REF:...}` placeholder resolving to `field` (`U` for username, `P` for password...) of entry `uuid`.
+pub fn field_reference(field: char, uuid: Uuid) -> String {
+    format!("{{REF:{field}@I:{}}}", uuid.simple().to_string().to_uppercase())
+}
+
+/// Copies the entry or group subtree `uuid` under new UUIDs, right after the original, and returns the copy.
+pub fn duplicate(kpdb: &mut KpDb, uuid: Uuid, options: DuplicateOptions) -> Result<Uuid> {
+    let node = kpdb.get_node_by_id(uuid).ok_or("No such node")?;
+    let parent = node.borrow().get_parent().ok_or("The database root cannot be duplicated")?;
+    let parent_node = kpdb.get_node_by_id(parent).ok_or("No such group")?;
+    let index = group_get_children(&parent_node)
+        .unwrap_or_default()
+        .iter()
+        .position(|child| child.borrow().get_uuid() == uuid)
+        .map_or(0, |index| index + 1);
+    let suffix = if options.append_copy { " - Copy" } else { "" };
+    let copy = copy_node(kpdb, &node, parent, index, suffix, options)?;
+    kpdb.mark_data_changed();
+    Ok(copy)
+}
+
+fn copy_node(kpdb: &KpDb, node: &NodePtr, parent: Uuid, index: usize, suffix: &str, options: DuplicateOptions) -> Result<Uuid> {
+    let db = kpdb.db.as_ref().ok_or("No database")?;
+    if !node_is_group(node) {
+        let source = with_node::<Entry, _, _>(node, |entry| entry.clone()).ok_or("No such entry")?;
+        let copy = db.create_new_entry(parent, index)?;
+        with_node_mut::<Entry, _, _>(&copy, |entry| copy_entry(&source, entry, suffix, options));
+        return Ok(copy.borrow().get_uuid());
+    }
+
+    let source = with_node::<Group, _, _>(node, |group| group.clone()).ok_or("No such group")?;
+    let copy = db.create_new_group(parent, index)?;
+    let copy_uuid = copy.borrow().get_uuid();
+    with_node_mut::<Group, _, _>(&copy, |group| {
+        let title = source.get_title().map(|title| format!("{title}{suffix}"));
+        group.set_title(title.as_deref());
+        group.set_notes(source.get_notes());
+        group.set_icon(source.get_icon());
+        group.get_times_mut().set_expires(source.get_times().get_expires());
+        group.get_times_mut().set_expiry_time(source.get_times().get_expiry_time());
+    });
+    // Only the top of the subtree gets the suffix.
+    for (index, child) in group_get_children(node).unwrap_or_default().iter().enumerate() {
+        copy_node(kpdb, child, copy_uuid, index, "", options)?;
+    }
+    Ok(copy_uuid)
+}
+
+fn copy_entry(source: &Entry, entry: &mut Entry, suffix: &str, options: DuplicateOptions) {
+    let uuid = entry.get_uuid();
+    entry.fields = source.fields.clone();
+    entry.attachments = source.attachments.clone();
+    entry.set_icon(source.get_icon());
+    entry.get_tags_mut().clear();
+    entry.get_tags_mut().extend(source.get_tags().iter().cloned());
+    entry.get_times_mut().set_expires(source.get_times().get_expires());
+    entry.get_times_mut().set_expiry_time(source.get_times().get_expiry_time());
+    if options.include_history {
+        // History versions carry the UUID of the entry they belong to.
+        let copies = versions(source)
+            .into_iter()
+            .map(|mut version| {
+                version.set_uuid(uuid);
+                version
+            })
+            .collect::<Vec<_>>();
+        entry.history = (!copies.is_empty()).then(|| history_of(copies));
+    }
+    if !suffix.is_empty() {
+        let title = format!("{}{suffix}", source.get_title().unwrap_or(""));
+        entry.set_title(Some(&title));
+    }
+    if options.reference_credentials {
+        entry.set_username(Some(&field_reference('U', source.get_uuid())));
+        entry.set_password(Some(&field_reference('P', source.get_uuid())));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DuplicateOptions, duplicate, field_reference};
+    use crate::keepass::KpDb;
+    use keepass_ng::db::{Entry, Group, Node, group_get_children, with_node, with_node_mut};
+
+    #[test]
+    fn duplicates_entries_and_group_subtrees() {
+        let mut kpdb = KpDb::new();
+        let root = kpdb.get_root().unwrap().borrow().get_uuid();
+        let group = kpdb.create_new_group(root).unwrap();
+        with_node_mut::<Group, _, _>(&group, |group| group.set_title(Some("Customer A")));
+        let group = group.borrow().get_uuid();
+        let entry = kpdb.create_new_entry(group).unwrap();
+        with_node_mut::<Entry, _, _>(&entry, |entry| {
+            entry.set_title(Some("Admin"));
+            entry.set_username(Some("root"));
+            entry.set_password(Some("hunter2"));
+            entry.get_tags_mut().push("template".to_string());
+            entry.update_history();
+        });
+        let entry = entry.borrow().get_uuid();
+
+        let options = DuplicateOptions {
+            reference_credentials: true,
+            include_history: true,
+            ..DuplicateOptions::default()
+        };
+        let copy = duplicate(&mut kpdb, entry, options).unwrap();
+        assert_ne!(copy, entry);
+        let copy = kpdb.get_node_by_id(copy).unwrap();
+        with_node::<Entry, _, _>(&copy, |copy| {
+            assert_eq!(copy.get_title(), Some("Admin - Copy"));
+            assert_eq!(copy.get_username(), Some(field_reference('U', entry).as_str()));
+            assert_eq!(copy.get_password(), Some(field_reference('P', entry).as_str()));
+            assert_eq!(copy.get_tags().len(), 1);
+            let history = copy.history.as_ref().unwrap().get_entries();
+            assert!(!history.is_empty() && history.iter().all(|version| version.get_uuid() == copy.get_uuid()));
+        });
+
+        let group_copy = duplicate(&mut kpdb, group, DuplicateOptions::default()).unwrap();
+        let group_copy = kpdb.get_node_by_id(group_copy).unwrap();
+        assert_eq!(group_copy.borrow().get_title(), Some("Customer A - Copy"));
+        let children = group_get_children(&group_copy).unwrap();
+        assert_eq!(children.len(), 2);
+        assert!(children.iter().all(|child| child.borrow().get_uuid() != entry));
+        assert_eq!(children[0].borrow().get_title(), Some("Admin"));
+        assert_eq!(children[1].borrow().get_title(), Some("Admin - Copy"));
+        let root_children = group_get_children(&kpdb.get_root().unwrap()).unwrap();
+        assert_eq!(root_children[1].borrow().get_uuid(), group_copy.borrow().get_uuid());
+        assert!(kpdb.is_data_changed());
+    }
+}
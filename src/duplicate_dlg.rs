@@ -0,0 +1,53 @@
+use crate::duplicate::DuplicateOptions;
+use wxdragon::{BoxSizer, Button, ButtonEvents, CheckBox, Dialog, Orientation, SizerFlag, StaticText, WxWidget};
+
+/// Asks how to duplicate an entry, or a group with everything in it when `is_group` is set.
+pub fn show(parent: &dyn WxWidget, is_group: bool) -> Option<DuplicateOptions> {
+    let title = if is_group { "Duplicate group" } else { "Duplicate entry" };
+    let dialog = Dialog::builder(parent, title).with_size(460, 240).build();
+    let defaults = DuplicateOptions::default();
+    let append_copy = CheckBox::builder(&dialog)
+        .with_label("Append \" - Copy\" to the title")
+        .with_value(defaults.append_copy)
+        .build();
+    let reference_credentials = CheckBox::builder(&dialog)
+        .with_label("Replace username and password with references to the original")
+        .with_value(defaults.reference_credentials)
+        .build();
+    let include_history = CheckBox::builder(&dialog)
+        .with_label("Copy history")
+        .with_value(defaults.include_history)
+        .build();
+
+    let root = BoxSizer::builder(Orientation::Vertical).build();
+    let options = BoxSizer::builder(Orientation::Vertical).build();
+    options.add(&append_copy, 0, SizerFlag::All, 4);
+    options.add(&reference_credentials, 0, SizerFlag::All, 4);
+    options.add(&include_history, 0, SizerFlag::All, 4);
+    root.add_sizer(&options, 1, SizerFlag::All | SizerFlag::Expand, 12);
+    let actions = BoxSizer::builder(Orientation::Horizontal).build();
+    let spacer = StaticText::builder(&dialog).with_label("").build();
+    let cancel = Button::builder(&dialog).with_id(wxdragon::ID_CANCEL).with_label("Cancel").build();
+    let ok = Button::builder(&dialog).with_label("OK").build();
+    actions.add(&spacer, 1, SizerFlag::Expand, 0);
+    actions.add(&cancel, 0, SizerFlag::All, 4);
+    actions.add(&ok, 0, SizerFlag::All, 4);
+    root.add_sizer(&actions, 0, SizerFlag::All | SizerFlag::Expand, 8);
+    dialog.set_sizer(root, true);
+    dialog.set_escape_id(wxdragon::ID_CANCEL);
+
+    let dialog_for_cancel = dialog;
+    cancel.on_click(move |_| dialog_for_cancel.end_modal(wxdragon::ID_CANCEL));
+    let dialog_for_ok = dialog;
+    ok.on_click(move |_| dialog_for_ok.end_modal(wxdragon::ID_OK));
+
+    dialog.center();
+    let result = dialog.show_modal();
+    let options = DuplicateOptions {
+        append_copy: append_copy.get_value(),
+        reference_credentials: reference_credentials.get_value(),
+        include_history: include_history.get_value(),
+    };
+    dialog.destroy();
+    (result == wxdragon::ID_OK).then_some(options)
+}
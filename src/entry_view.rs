@@ -1,19 +1,36 @@
 use crate::add_detail_row;
+use crate::attachments;
+use crate::clipboard;
+use crate::custom_field_dlg;
+use crate::custom_fields::{CustomField, custom_fields, set_custom_fields};
+use crate::error::Error;
 use crate::favicon::{FaviconDownloader, image_from_bytes};
+use crate::history;
+use crate::history_view::build_history_page;
 use crate::icon_cache::icon_for_emoji;
 use crate::icon_picker::show_icon_picker;
 use crate::keepass::KpDb;
+use crate::lock;
+use crate::password_generator_dlg;
+use crate::placeholders;
+use crate::remember_recent_entry;
+use crate::secret::SecretString;
+use crate::settings::Settings;
+use crate::totp::{self, LEGACY_SEED_FIELD, LEGACY_SETTINGS_FIELD, OTP_FIELD, Otp};
+use crate::totp_dlg;
 use chrono::{Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, Timelike};
 use keepass_ng::db::{AutoType, Entry, Icon, Node, NodePtr, with_node, with_node_mut};
 use std::{
     cell::{Cell, RefCell},
+    fs,
     rc::Rc,
     sync::{Arc, Mutex},
 };
 use wxdragon::{
-    Bitmap, BoxSizer, Button, ButtonEvents, CheckBox, Choice, DatePickerCtrl, DatePickerCtrlStyle, Dialog, FlexGridSizer, Frame,
-    HyperlinkCtrl, ListColumnFormat, ListCtrl, ListCtrlStyle, MessageDialog, MessageDialogStyle, Notebook, Orientation, Panel, Size,
-    SizerFlag, StaticBitmap, StaticText, TextCtrl, TextCtrlStyle, TimePickerCtrl, Timer, WindowEvents, WxWidget,
+    Bitmap, BoxSizer, Button, ButtonEvents, CheckBox, Choice, DatePickerCtrl, DatePickerCtrlStyle, Dialog, FileDialog, FileDialogStyle,
+    FileDropTarget, FlexGridSizer, Frame, HyperlinkCtrl, ListColumnFormat, ListCtrl, ListCtrlStyle, MessageDialog, MessageDialogStyle,
+    Notebook, Orientation, Panel, Size, SizerFlag, StaticBitmap, StaticText, TextCtrl, TextCtrlStyle, TextEntryDialog, TimePickerCtrl,
+    Timer, WindowEvents, WxWidget,
 };
 
 pub fn build_entry_view(parent: &Panel, frame: Frame, node: &NodePtr, refresh: Rc<dyn Fn()>, kpdb: Rc<RefCell<Option<KpDb>>>) {
@@ -71,12 +88,23 @@ pub fn build_entry_view(parent: &Panel, frame: Frame, node: &NodePtr, refresh: R
 
     let notebook = Notebook::builder(parent).build();
 
+    // Field references and placeholders are shown, copied and opened with their resolved values.
+    let resolved = |field: &str| placeholders::resolved_field(kpdb.borrow().as_ref(), &entry, field);
+    let username = resolved("UserName");
+    let url = resolved("URL");
+    let notes = resolved("Notes");
     let general_page = Panel::builder(&notebook).build();
     let general_sizer = BoxSizer::builder(Orientation::Vertical).build();
     let general_grid = FlexGridSizer::builder(0, 2).with_vgap(8).with_hgap(16).build();
-    add_detail_row(&general_grid, &general_page, "Username", entry.get_username().unwrap_or(""));
+    let username_label = StaticText::builder(&general_page).with_label("Username").build();
+    let username_value = StaticText::builder(&general_page).with_label(&username).build();
+    let username_controls = BoxSizer::builder(Orientation::Horizontal).build();
+    username_controls.add(&username_value, 1, SizerFlag::AlignCenterVertical, 0);
+    username_controls.add(&copy_button(&general_page, node, "Copy username", &username), 0, SizerFlag::Left, 4);
+    general_grid.add(&username_label, 0, SizerFlag::All | SizerFlag::AlignCenterVertical, 4);
+    general_grid.add_sizer(&username_controls, 1, SizerFlag::All | SizerFlag::Expand, 4);
     let password_label = StaticText::builder(&general_page).with_label("Password").build();
-    let password = entry.get_password().unwrap_or("").to_owned();
+    let password = resolved("Password");
     let password_panel = Panel::builder(&general_page).build();
     let password_value = TextCtrl::builder(&password_panel)
         .with_value(&password)
@@ -99,19 +127,68 @@ pub fn build_entry_view(parent: &Panel, frame: Frame, node: &NodePtr, refresh: R
     let password_controls = BoxSizer::builder(Orientation::Horizontal).build();
     password_controls.add(&password_value, 1, SizerFlag::All | SizerFlag::Expand, 0);
     password_controls.add(&password_toggle, 0, SizerFlag::All, 4);
+    password_controls.add(
+        &copy_button(&password_panel, node, "Copy password", &password),
+        0,
+        SizerFlag::All,
+        4,
+    );
     password_panel.set_sizer(password_controls, true);
     general_grid.add(&password_label, 0, SizerFlag::All | SizerFlag::AlignCenterVertical, 4);
-    password_panel.set_min_size(Size::new(282, 38));
+    password_panel.set_min_size(Size::new(324, 38));
     general_grid.add(&password_panel, 1, SizerFlag::All, 4);
     let url_label = StaticText::builder(&general_page).with_label("URL").build();
     general_grid.add(&url_label, 0, SizerFlag::All | SizerFlag::AlignCenterVertical, 4);
-    if let Some(url) = entry.get_url().filter(|url| !url.is_empty()) {
-        let url_link = HyperlinkCtrl::builder(&general_page).with_label(url).with_url(url).build();
-        general_grid.add(&url_link, 1, SizerFlag::AlignLeft | SizerFlag::AlignCenterVertical, 4);
+    if !url.is_empty() {
+        let url_link = HyperlinkCtrl::builder(&general_page).with_label(&url).with_url(&url).build();
+        let url_controls = BoxSizer::builder(Orientation::Horizontal).build();
+        url_controls.add(&url_link, 0, SizerFlag::AlignCenterVertical, 0);
+        url_controls.add(&copy_button(&general_page, node, "Copy URL", &url), 0, SizerFlag::Left, 4);
+        general_grid.add_sizer(&url_controls, 1, SizerFlag::AlignLeft | SizerFlag::AlignCenterVertical, 4);
     } else {
         let empty_url = StaticText::builder(&general_page).with_label("").build();
         general_grid.add(&empty_url, 1, SizerFlag::AlignCenterVertical, 4);
     }
+    match Otp::from_entry(&entry) {
+        Some(Ok(otp)) => {
+            let totp_label = StaticText::builder(&general_page).with_label("TOTP").build();
+            let totp_value = StaticText::builder(&general_page).with_label(&otp_label(&otp)).build();
+            let totp_copy = Button::builder(&general_page).with_size(Size::new(38, 38)).build();
+            if let Some(bitmap) = icon_for_emoji("📋", 28) {
+                totp_copy.set_bitmap_label(&bitmap);
+            }
+            totp_copy.set_tooltip("Copy the one-time code");
+            let node_for_totp = node.clone();
+            let kpdb_for_totp = Rc::clone(&kpdb);
+            let refresh_for_totp = Rc::clone(&refresh);
+            let is_hotp = matches!(otp.kind, totp::Kind::Hotp { .. });
+            totp_copy.on_click(move |_| match copy_one_time_code(&totp_copy, &node_for_totp, &kpdb_for_totp) {
+                // The view shows the next HOTP code now.
+                Ok(()) if is_hotp => refresh_for_totp(),
+                Ok(()) => {}
+                Err(error) => {
+                    MessageDialog::builder(&totp_copy, &error, "Copy failed")
+                        .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                        .build()
+                        .show_modal();
+                }
+            });
+            let totp_controls = BoxSizer::builder(Orientation::Horizontal).build();
+            totp_controls.add(&totp_value, 0, SizerFlag::AlignCenterVertical, 0);
+            totp_controls.add(&totp_copy, 0, SizerFlag::Left, 4);
+            general_grid.add(&totp_label, 0, SizerFlag::All | SizerFlag::AlignCenterVertical, 4);
+            general_grid.add_sizer(&totp_controls, 1, SizerFlag::All | SizerFlag::Expand, 4);
+            if !is_hotp {
+                let totp_timer = Rc::new(Timer::new(&general_page));
+                totp_timer.on_tick(move |_| totp_value.set_label(&otp_label(&otp)));
+                totp_timer.start(1000, false);
+                let totp_timer_for_destroy = Rc::clone(&totp_timer);
+                general_page.on_destroy(move |_| totp_timer_for_destroy.stop());
+            }
+        }
+        Some(Err(error)) => add_detail_row(&general_grid, &general_page, "TOTP", &format!("Invalid settings: {error}")),
+        None => {}
+    }
     let expiry = entry
         .get_times()
         .get_expiry_time()
@@ -126,7 +203,7 @@ pub fn build_entry_view(parent: &Panel, frame: Frame, node: &NodePtr, refresh: R
     add_detail_row(&general_grid, &general_page, "Expires", &expiry);
     add_detail_row(&general_grid, &general_page, "Last Modified", &last_modified);
     add_detail_row(&general_grid, &general_page, "Tags", &entry.get_tags().join(", "));
-    add_detail_row(&general_grid, &general_page, "Notes", entry.get_notes().unwrap_or(""));
+    add_detail_row(&general_grid, &general_page, "Notes", &notes);
     general_sizer.add_sizer(&general_grid, 0, SizerFlag::All | SizerFlag::Expand, 12);
     general_page.set_sizer(general_sizer, true);
 
@@ -136,22 +213,48 @@ pub fn build_entry_view(parent: &Panel, frame: Frame, node: &NodePtr, refresh: R
     let attributes_sizer = BoxSizer::builder(Orientation::Vertical).build();
     let attributes_title = StaticText::builder(&attributes_panel).with_label("Attributes").build();
     attributes_sizer.add(&attributes_title, 0, SizerFlag::All | SizerFlag::Expand, 4);
-    let mut attributes = entry.additional_attributes();
-    attributes.sort_by(|left, right| left.0.cmp(&right.0));
-    let attributes_text = attributes
-        .iter()
-        .map(|(name, value)| format!("{}\n{}\n\n", name, value))
-        .collect::<String>();
-    let attributes_text = if attributes_text.is_empty() {
-        "No additional attributes".to_string()
-    } else {
-        attributes_text
-    };
-    let attributes_value = TextCtrl::builder(&attributes_panel)
-        .with_value(&attributes_text)
-        .with_style(TextCtrlStyle::MultiLine | TextCtrlStyle::ReadOnly | TextCtrlStyle::WordWrap)
+    let attributes = Rc::new(
+        custom_fields(&entry)
+            .into_iter()
+            .map(|field| CustomField {
+                value: resolved(&field.name).into(),
+                ..field
+            })
+            .collect::<Vec<_>>(),
+    );
+    let attributes_list = ListCtrl::builder(&attributes_panel)
+        .with_style(ListCtrlStyle::Report | ListCtrlStyle::SingleSel | ListCtrlStyle::VRules | ListCtrlStyle::HRules)
         .build();
-    attributes_sizer.add(&attributes_value, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    attributes_list.insert_column(0, "Name", ListColumnFormat::Left, 140);
+    attributes_list.insert_column(1, "Value", ListColumnFormat::Left, -1);
+    attributes_list.set_tooltip("Double-click an attribute to copy its value");
+    let attributes_for_fill = Rc::clone(&attributes);
+    let fill_attributes = move |reveal: bool| {
+        attributes_list.delete_all_items();
+        for (index, field) in attributes_for_fill.iter().enumerate() {
+            let row = index as i64;
+            if attributes_list.insert_item(row, &field.name, None) >= 0 {
+                let value = if field.protected && !reveal {
+                    "••••••••"
+                } else {
+                    field.value.expose()
+                };
+                attributes_list.set_item_text_by_column(row, 1, value);
+            }
+        }
+    };
+    fill_attributes(false);
+    let node_for_attributes = node.clone();
+    attributes_list.on_item_activated(move |event| {
+        let row = event.get_item_index();
+        if let Some(field) = usize::try_from(row).ok().and_then(|row| attributes.get(row)) {
+            copy_to_clipboard(&attributes_list, &node_for_attributes, field.value.expose());
+        }
+    });
+    let reveal_attributes = CheckBox::builder(&attributes_panel).with_label("Show protected values").build();
+    reveal_attributes.on_toggled(move |event| fill_attributes(event.is_checked()));
+    attributes_sizer.add(&attributes_list, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    attributes_sizer.add(&reveal_attributes, 0, SizerFlag::All, 4);
     attributes_panel.set_sizer(attributes_sizer, true);
 
     let attachments_panel = Panel::builder(&advanced_page).build();
@@ -175,9 +278,192 @@ pub fn build_entry_view(parent: &Panel, frame: Frame, node: &NodePtr, refresh: R
         }
         attachments.set_item_text_by_column(row, 1, &format!("{} bytes", attachment.data.get().len()));
     }
+    attachments.set_tooltip("Drop files here to attach them; double-click an attachment to open it");
     attachments_sizer.add(&attachments, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    let attachment_buttons = BoxSizer::builder(Orientation::Horizontal).build();
+    let add_attachment = Button::builder(&attachments_panel).with_label("Add...").build();
+    let save_attachment = Button::builder(&attachments_panel).with_label("Save as...").build();
+    let open_attachment = Button::builder(&attachments_panel).with_label("Open").build();
+    let rename_attachment = Button::builder(&attachments_panel).with_label("Rename...").build();
+    let delete_attachment = Button::builder(&attachments_panel).with_label("Delete").build();
+    for button in [
+        add_attachment,
+        save_attachment,
+        open_attachment,
+        rename_attachment,
+        delete_attachment,
+    ] {
+        attachment_buttons.add(&button, 0, SizerFlag::All, 2);
+    }
+    attachments_sizer.add_sizer(&attachment_buttons, 0, SizerFlag::All, 2);
     attachments_panel.set_sizer(attachments_sizer, true);
 
+    let entry_uuid = entry.get_uuid();
+    let attachment_names = attachment_names.into_iter().cloned().collect::<Vec<_>>();
+    let selected_attachment = Rc::new(RefCell::new(None::<String>));
+    let update_attachment_buttons = move |selected: bool| {
+        for button in [save_attachment, open_attachment, rename_attachment, delete_attachment] {
+            button.enable(selected);
+        }
+    };
+    update_attachment_buttons(false);
+    let selected_for_list = Rc::clone(&selected_attachment);
+    let names_for_list = attachment_names.clone();
+    attachments.on_item_selected(move |event| {
+        let name = usize::try_from(event.get_item_index())
+            .ok()
+            .and_then(|row| names_for_list.get(row))
+            .cloned();
+        update_attachment_buttons(name.is_some());
+        *selected_for_list.borrow_mut() = name;
+    });
+
+    let attach_files = {
+        let kpdb = Rc::clone(&kpdb);
+        let refresh = Rc::clone(&refresh);
+        let names = attachment_names.clone();
+        Rc::new(move |paths: Vec<String>| {
+            let mut names = names.clone();
+            for path in paths {
+                let result = fs::read(&path).map_err(Error::from).and_then(|data| {
+                    let existing = names.iter().map(String::as_str).collect::<Vec<_>>();
+                    let name = attachments::unique_name(&existing, &path);
+                    let mut kpdb = kpdb.borrow_mut();
+                    kpdb.as_mut().ok_or("No database loaded")?.attach(entry_uuid, &name, data)?;
+                    names.push(name);
+                    Ok(())
+                });
+                if let Err(error) = result {
+                    MessageDialog::builder(&attachments, &format!("Could not attach {path}: {error}"), "Attachments")
+                        .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                        .build()
+                        .show_modal();
+                }
+            }
+            refresh();
+        })
+    };
+    let attach_files_for_drop = Rc::clone(&attach_files);
+    FileDropTarget::builder(&attachments)
+        .with_on_drop_files(move |paths, _, _| {
+            attach_files_for_drop(paths);
+            true
+        })
+        .build();
+    add_attachment.on_click(move |_| {
+        let file_dialog = FileDialog::builder(&attachments)
+            .with_message("Choose files to attach")
+            .with_style(FileDialogStyle::Open | FileDialogStyle::FileMustExist | FileDialogStyle::Multiple)
+            .with_wildcard("All files (*.*)|*.*")
+            .build();
+        if file_dialog.show_modal() == wxdragon::ID_OK {
+            attach_files(file_dialog.get_paths());
+        }
+    });
+
+    let kpdb_for_save = Rc::clone(&kpdb);
+    let selected_for_save = Rc::clone(&selected_attachment);
+    save_attachment.on_click(move |_| {
+        let Some(name) = selected_for_save.borrow().clone() else {
+            return;
+        };
+        let Some(data) = kpdb_for_save.borrow().as_ref().and_then(|db| db.attachment_data(entry_uuid, &name)) else {
+            return;
+        };
+        let file_dialog = FileDialog::builder(&attachments)
+            .with_message("Save the attachment as")
+            .with_default_file(&name)
+            .with_style(FileDialogStyle::Save | FileDialogStyle::OverwritePrompt)
+            .with_wildcard("All files (*.*)|*.*")
+            .build();
+        if file_dialog.show_modal() == wxdragon::ID_OK
+            && let Some(path) = file_dialog.get_path()
+            && let Err(error) = fs::write(&path, &data)
+        {
+            MessageDialog::builder(&attachments, &format!("Could not save {path}: {error}"), "Attachments")
+                .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                .build()
+                .show_modal();
+        }
+    });
+
+    let kpdb_for_open = Rc::clone(&kpdb);
+    let selected_for_open = Rc::clone(&selected_attachment);
+    let open_selected = Rc::new(move || {
+        let Some(name) = selected_for_open.borrow().clone() else {
+            return;
+        };
+        let Some(data) = kpdb_for_open.borrow().as_ref().and_then(|db| db.attachment_data(entry_uuid, &name)) else {
+            return;
+        };
+        if let Err(error) = attachments::open_temporary(&name, &data) {
+            MessageDialog::builder(&attachments, &format!("Could not open {name}: {error}"), "Attachments")
+                .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                .build()
+                .show_modal();
+        }
+    });
+    let open_selected_for_button = Rc::clone(&open_selected);
+    open_attachment.on_click(move |_| open_selected_for_button());
+    attachments.on_item_activated(move |_| open_selected());
+
+    let kpdb_for_rename = Rc::clone(&kpdb);
+    let refresh_after_rename = Rc::clone(&refresh);
+    let selected_for_rename = Rc::clone(&selected_attachment);
+    rename_attachment.on_click(move |_| {
+        let Some(name) = selected_for_rename.borrow().clone() else {
+            return;
+        };
+        let rename_dialog = TextEntryDialog::builder(&attachments, "New name of the attachment", "Rename attachment")
+            .with_default_value(&name)
+            .build();
+        let new_name = (rename_dialog.show_modal() == wxdragon::ID_OK)
+            .then(|| rename_dialog.get_value())
+            .flatten()
+            .map(|new_name| new_name.trim().to_string())
+            .filter(|new_name| !new_name.is_empty() && *new_name != name);
+        rename_dialog.destroy();
+        let Some(new_name) = new_name else {
+            return;
+        };
+        let result = kpdb_for_rename
+            .borrow_mut()
+            .as_mut()
+            .ok_or_else(|| Error::from("No database loaded"))
+            .and_then(|db| db.rename_attachment(entry_uuid, &name, &new_name));
+        match result {
+            Ok(()) => refresh_after_rename(),
+            Err(error) => {
+                MessageDialog::builder(&attachments, &error.to_string(), "Rename attachment")
+                    .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                    .build()
+                    .show_modal();
+            }
+        }
+    });
+
+    let kpdb_for_delete = Rc::clone(&kpdb);
+    let refresh_after_delete = Rc::clone(&refresh);
+    delete_attachment.on_click(move |_| {
+        let Some(name) = selected_attachment.borrow().clone() else {
+            return;
+        };
+        let confirm = MessageDialog::builder(&attachments, &format!("Delete the attachment {name}?"), "Delete attachment")
+            .with_style(MessageDialogStyle::YesNo | MessageDialogStyle::IconWarning)
+            .build();
+        let answer = confirm.show_modal();
+        confirm.destroy();
+        if answer != wxdragon::ID_YES {
+            return;
+        }
+        if let Some(db) = kpdb_for_delete.borrow_mut().as_mut()
+            && let Err(error) = db.remove_attachment(entry_uuid, &name)
+        {
+            log::warn!("Could not delete attachment {name}: {error}");
+        }
+        refresh_after_delete();
+    });
+
     advanced_sizer.add(&attributes_panel, 1, SizerFlag::All | SizerFlag::Expand, 4);
     advanced_sizer.add(&attachments_panel, 1, SizerFlag::All | SizerFlag::Expand, 4);
     advanced_page.set_sizer(advanced_sizer, true);
@@ -209,9 +495,36 @@ pub fn build_entry_view(parent: &Panel, frame: Frame, node: &NodePtr, refresh: R
     autotype_sizer.add(&autotype_list, 1, SizerFlag::All | SizerFlag::Expand, 4);
     autotype_page.set_sizer(autotype_sizer, true);
 
+    let node_for_restore = node.clone();
+    let kpdb_for_restore = Rc::clone(&kpdb);
+    let refresh_after_restore = Rc::clone(&refresh);
+    let node_for_delete = node.clone();
+    let kpdb_for_delete = Rc::clone(&kpdb);
+    let history_page = build_history_page(
+        &notebook,
+        &entry,
+        Rc::new(RefCell::new(history::versions(&entry))),
+        Rc::new(move |index| {
+            if with_node_mut::<Entry, _, _>(&node_for_restore, |entry| history::restore(entry, index)).unwrap_or(false) {
+                if let Some(db) = kpdb_for_restore.borrow_mut().as_mut() {
+                    db.mark_data_changed();
+                }
+                refresh_after_restore();
+            }
+        }),
+        Rc::new(move |index| {
+            if with_node_mut::<Entry, _, _>(&node_for_delete, |entry| history::remove_version(entry, index)).unwrap_or(false)
+                && let Some(db) = kpdb_for_delete.borrow_mut().as_mut()
+            {
+                db.mark_data_changed();
+            }
+        }),
+    );
+
     notebook.add_page(&general_page, "General", true, None);
     notebook.add_page(&advanced_page, "Advanced", false, None);
     notebook.add_page(&autotype_page, "Autotype", false, None);
+    notebook.add_page(&history_page, "History", false, None);
     sizer.add(&notebook, 1, SizerFlag::All | SizerFlag::Expand, 0);
     parent.set_sizer(sizer, true);
     parent.layout();
@@ -257,9 +570,20 @@ pub fn show_entry_editor(parent: &dyn WxWidget, node: &NodePtr, kpdb: Rc<RefCell
             password_toggle.set_bitmap_label(&bitmap);
         }
     });
+    let generate_password = Button::builder(&password_panel).with_size(Size::new(38, 38)).build();
+    if let Some(bitmap) = icon_for_emoji("🎲", 28) {
+        generate_password.set_bitmap_label(&bitmap);
+    }
+    generate_password.set_tooltip("Generate password");
+    generate_password.on_click(move |_| {
+        if let Some(generated) = password_generator_dlg::show(&dialog) {
+            password.set_value(generated.expose());
+        }
+    });
     let password_controls = BoxSizer::builder(Orientation::Horizontal).build();
     password_controls.add(&password, 1, SizerFlag::All | SizerFlag::Expand, 0);
     password_controls.add(&password_toggle, 0, SizerFlag::All, 4);
+    password_controls.add(&generate_password, 0, SizerFlag::All, 4);
     password_panel.set_sizer(password_controls, true);
     let url = TextCtrl::builder(&entry_page).with_value(entry.get_url().unwrap_or("")).build();
     let download_favicon = Button::builder(&entry_page).with_size(Size::new(38, 34)).build();
@@ -388,23 +712,127 @@ pub fn show_entry_editor(parent: &dyn WxWidget, node: &NodePtr, kpdb: Rc<RefCell
 
     let advanced_page = Panel::builder(&notebook).build();
     let advanced_sizer = BoxSizer::builder(Orientation::Vertical).build();
-    let attributes = TextCtrl::builder(&advanced_page)
-        .with_value(
-            &entry
-                .additional_attributes()
-                .iter()
-                .map(|(name, value)| format!("{}\n{}\n\n", name, value))
-                .collect::<String>(),
-        )
-        .with_style(TextCtrlStyle::MultiLine)
+    let fields = Rc::new(RefCell::new(custom_fields(&entry)));
+    let fields_list = ListCtrl::builder(&advanced_page)
+        .with_style(ListCtrlStyle::Report | ListCtrlStyle::SingleSel | ListCtrlStyle::VRules | ListCtrlStyle::HRules)
         .build();
+    fields_list.insert_column(0, "Name", ListColumnFormat::Left, 180);
+    fields_list.insert_column(1, "Value", ListColumnFormat::Left, 300);
+    fields_list.insert_column(2, "Protected", ListColumnFormat::Left, -1);
+    let reveal_fields = CheckBox::builder(&advanced_page).with_label("Show protected values").build();
+    fill_custom_fields(&fields_list, &fields.borrow(), false);
+    let field_buttons = BoxSizer::builder(Orientation::Horizontal).build();
+    let add_field = Button::builder(&advanced_page).with_label("Add...").build();
+    let edit_field = Button::builder(&advanced_page).with_label("Edit...").build();
+    let delete_field = Button::builder(&advanced_page).with_label("Delete").build();
+    field_buttons.add(&add_field, 0, SizerFlag::All, 2);
+    field_buttons.add(&edit_field, 0, SizerFlag::All, 2);
+    let setup_totp = Button::builder(&advanced_page).with_label("TOTP...").build();
+    setup_totp.set_tooltip("Set up one-time passwords from a secret or an otpauth:// URI");
+    field_buttons.add(&delete_field, 0, SizerFlag::All, 2);
+    field_buttons.add(&setup_totp, 0, SizerFlag::All, 2);
+    field_buttons.add(&reveal_fields, 0, SizerFlag::Left | SizerFlag::AlignCenterVertical, 12);
     advanced_sizer.add(
         &StaticText::builder(&advanced_page).with_label("Additional attributes").build(),
         0,
         SizerFlag::All,
         4,
     );
-    advanced_sizer.add(&attributes, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    advanced_sizer.add(&fields_list, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    advanced_sizer.add_sizer(&field_buttons, 0, SizerFlag::All, 2);
+
+    let selected_field = Rc::new(Cell::new(None::<usize>));
+    edit_field.enable(false);
+    delete_field.enable(false);
+    let selected_for_list = Rc::clone(&selected_field);
+    fields_list.on_item_selected(move |event| {
+        let index = usize::try_from(event.get_item_index()).ok();
+        selected_for_list.set(index);
+        edit_field.enable(index.is_some());
+        delete_field.enable(index.is_some());
+    });
+    let fields_for_reveal = Rc::clone(&fields);
+    reveal_fields.on_toggled(move |event| fill_custom_fields(&fields_list, &fields_for_reveal.borrow(), event.is_checked()));
+    let edit_custom_field = {
+        let fields = Rc::clone(&fields);
+        let selected = Rc::clone(&selected_field);
+        Rc::new(move |editing: Option<usize>| {
+            let Some(field) = custom_field_dlg::show(&dialog, &fields.borrow(), editing) else {
+                return;
+            };
+            let mut fields = fields.borrow_mut();
+            match editing.filter(|index| *index < fields.len()) {
+                Some(index) => fields[index] = field,
+                None => fields.push(field),
+            }
+            fields.sort_by(|left, right| left.name.cmp(&right.name));
+            selected.set(None);
+            edit_field.enable(false);
+            delete_field.enable(false);
+            fill_custom_fields(&fields_list, &fields, reveal_fields.get_value());
+        })
+    };
+    let edit_for_add = Rc::clone(&edit_custom_field);
+    add_field.on_click(move |_| edit_for_add(None));
+    let edit_for_button = Rc::clone(&edit_custom_field);
+    let selected_for_edit = Rc::clone(&selected_field);
+    edit_field.on_click(move |_| {
+        if let Some(index) = selected_for_edit.get() {
+            edit_for_button(Some(index));
+        }
+    });
+    fields_list.on_item_activated(move |event| edit_custom_field(usize::try_from(event.get_item_index()).ok()));
+    let fields_for_totp = Rc::clone(&fields);
+    let selected_for_totp = Rc::clone(&selected_field);
+    setup_totp.on_click(move |_| {
+        let current = {
+            let fields = fields_for_totp.borrow();
+            let field = |name: &str| {
+                fields
+                    .iter()
+                    .find(|field| field.name == name)
+                    .map(|field| field.value.expose().to_string())
+            };
+            field(OTP_FIELD).or_else(|| {
+                let seed = field(LEGACY_SEED_FIELD)?;
+                Otp::from_legacy(&seed, field(LEGACY_SETTINGS_FIELD).as_deref())
+                    .ok()
+                    .map(|otp| otp.to_uri())
+            })
+        };
+        let Some(otp) = totp_dlg::show(&dialog, current.as_deref(), &title.get_value(), &username.get_value()) else {
+            return;
+        };
+        let mut fields = fields_for_totp.borrow_mut();
+        // The otpauth URI replaces KeePassXC's legacy fields.
+        fields.retain(|field| ![OTP_FIELD, LEGACY_SEED_FIELD, LEGACY_SETTINGS_FIELD].contains(&field.name.as_str()));
+        if let Some(uri) = otp {
+            fields.push(CustomField {
+                name: OTP_FIELD.to_string(),
+                value: SecretString::new(uri),
+                protected: true,
+            });
+            fields.sort_by(|left, right| left.name.cmp(&right.name));
+        }
+        selected_for_totp.set(None);
+        edit_field.enable(false);
+        delete_field.enable(false);
+        fill_custom_fields(&fields_list, &fields, reveal_fields.get_value());
+    });
+    let fields_for_delete = Rc::clone(&fields);
+    delete_field.on_click(move |_| {
+        let Some(index) = selected_field.get() else {
+            return;
+        };
+        let mut fields = fields_for_delete.borrow_mut();
+        if index < fields.len() {
+            fields.remove(index);
+        }
+        selected_field.set(None);
+        edit_field.enable(false);
+        delete_field.enable(false);
+        fill_custom_fields(&fields_list, &fields, reveal_fields.get_value());
+    });
     let attachments = ListCtrl::builder(&advanced_page)
         .with_style(ListCtrlStyle::Report | ListCtrlStyle::VRules | ListCtrlStyle::HRules)
         .build();
@@ -575,10 +1003,74 @@ pub fn show_entry_editor(parent: &dyn WxWidget, node: &NodePtr, kpdb: Rc<RefCell
     properties_sizer.add_sizer(&properties_grid, 0, SizerFlag::All | SizerFlag::Expand, 12);
     properties_page.set_sizer(properties_sizer, true);
 
+    // Restoring fills the form, so the version becomes current only when the dialog is confirmed; `history::restore`
+    // then brings back what the form does not show, like attachments and window associations.
+    let history_versions = Rc::new(RefCell::new(history::versions(&entry)));
+    let history_edited = Rc::new(Cell::new(false));
+    let restored_version = Rc::new(Cell::new(None));
+    let history_versions_for_restore = Rc::clone(&history_versions);
+    let selected_icon_for_restore = Rc::clone(&selected_icon);
+    let kpdb_for_restore = Rc::clone(&kpdb);
+    let fields_for_restore = Rc::clone(&fields);
+    let restored_version_for_restore = Rc::clone(&restored_version);
+    let history_edited_for_delete = Rc::clone(&history_edited);
+    let restored_version_for_delete = Rc::clone(&restored_version);
+    let history_page = build_history_page(
+        &notebook,
+        &entry,
+        Rc::clone(&history_versions),
+        Rc::new(move |index| {
+            let versions = history_versions_for_restore.borrow();
+            let Some(version) = versions.get(index) else {
+                return;
+            };
+            title.set_value(version.get_title().unwrap_or(""));
+            username.set_value(version.get_username().unwrap_or(""));
+            password.set_value(version.get_password().unwrap_or(""));
+            url.set_value(version.get_url().unwrap_or(""));
+            notes.set_value(version.get_notes().unwrap_or(""));
+            tags.set_value(&version.get_tags().join(", "));
+            let version_expires = version.get_times().get_expires();
+            expires.set_value(version_expires);
+            expiry_date.enable(version_expires);
+            expiry_time.enable(version_expires);
+            presets.enable(version_expires);
+            if let Some(expiry) = version.get_times().get_expiry_time().filter(|_| version_expires) {
+                let wx_expiry = datetime_to_wx(expiry);
+                expiry_date.set_value(&wx_expiry);
+                expiry_time.set_value(&wx_expiry);
+            }
+            selected_icon_for_restore.set(version.get_icon());
+            set_icon_button_bitmap(&title_icon_button, &version.get_icon(), &kpdb_for_restore);
+            let version_autotype = version.get_autotype();
+            autotype_enabled.set_value(version_autotype.map(|autotype| autotype.enabled).unwrap_or(false));
+            default_sequence.set_value(
+                version_autotype
+                    .and_then(|autotype| autotype.default_sequence.as_deref())
+                    .unwrap_or(""),
+            );
+            let mut fields = fields_for_restore.borrow_mut();
+            *fields = custom_fields(version);
+            fill_custom_fields(&fields_list, &fields, reveal_fields.get_value());
+            restored_version_for_restore.set(Some(index));
+            notebook.set_selection(0);
+        }),
+        Rc::new(move |index| {
+            history_edited_for_delete.set(true);
+            // Later versions move up one place; a deleted restored version can no longer be brought back.
+            restored_version_for_delete.set(match restored_version_for_delete.get() {
+                Some(restored) if restored > index => Some(restored - 1),
+                Some(restored) if restored == index => None,
+                restored => restored,
+            });
+        }),
+    );
+
     notebook.add_page(&entry_page, "Entry", true, None);
     notebook.add_page(&advanced_page, "Advanced", false, None);
     notebook.add_page(&autotype_page, "Auto-Type", false, None);
     notebook.add_page(&properties_page, "Properties", false, None);
+    notebook.add_page(&history_page, "History", false, None);
     dialog_sizer.add(&notebook, 1, SizerFlag::All | SizerFlag::Expand, 8);
 
     let button_sizer = BoxSizer::builder(Orientation::Horizontal).build();
@@ -600,6 +1092,12 @@ pub fn show_entry_editor(parent: &dyn WxWidget, node: &NodePtr, kpdb: Rc<RefCell
     ok.on_click(move |_| {
         let selected_icon_value = selected_icon_for_ok.get();
         with_node_mut::<Entry, _, _>(&node_for_ok, |entry| {
+            if history_edited.get() {
+                entry.history = Some(history::history_of(history_versions.borrow().clone()));
+            }
+            if let Some(index) = restored_version.get() {
+                history::restore(entry, index);
+            }
             let title_value = title.get_value();
             let username_value = username.get_value();
             let password_value = password.get_value();
@@ -651,6 +1149,7 @@ pub fn show_entry_editor(parent: &dyn WxWidget, node: &NodePtr, kpdb: Rc<RefCell
             };
             entry.set_autotype(Some(auto_type));
             entry.set_icon(selected_icon_value);
+            set_custom_fields(entry, &fields.borrow());
             entry.update_history();
         });
         if let Some(db) = kpdb.borrow_mut().as_mut() {
@@ -664,6 +1163,72 @@ pub fn show_entry_editor(parent: &dyn WxWidget, node: &NodePtr, kpdb: Rc<RefCell
     res
 }
 
+/// Copies `value` with the configured auto-clear timeout and remembers the entry for the tray menu.
+pub(crate) fn copy_to_clipboard(parent: &dyn WxWidget, node: &NodePtr, value: &str) {
+    lock::note_activity();
+    let timeout = Settings::shared().borrow().clipboard_timeout();
+    match clipboard::copy_text(value, timeout) {
+        Ok(()) => remember_recent_entry(node),
+        Err(error) => {
+            MessageDialog::builder(parent, &error.to_string(), "Copy failed")
+                .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                .build()
+                .show_modal();
+        }
+    }
+}
+
+/// Copies the current one-time code of entry `node`; HOTP entries move on to their next code.
+pub(crate) fn copy_one_time_code(parent: &dyn WxWidget, node: &NodePtr, kpdb: &Rc<RefCell<Option<KpDb>>>) -> Result<(), String> {
+    let (code, advanced) = with_node_mut::<Entry, _, _>(node, totp::take_code)
+        .ok_or("Select an entry first")?
+        .map_err(|error| error.to_string())?;
+    if advanced && let Some(db) = kpdb.borrow_mut().as_mut() {
+        db.mark_data_changed();
+    }
+    copy_to_clipboard(parent, node, &code);
+    Ok(())
+}
+
+fn otp_label(otp: &Otp) -> String {
+    let now = totp::unix_now();
+    let code = totp::display_code(&otp.code_at(now));
+    match otp.remaining_at(now) {
+        Some(seconds) => format!("{code}   ({seconds} s)"),
+        None => code,
+    }
+}
+
+fn copy_button(parent: &Panel, node: &NodePtr, tooltip: &str, value: &str) -> Button {
+    let button = Button::builder(parent).with_size(Size::new(38, 38)).build();
+    if let Some(bitmap) = icon_for_emoji("📋", 28) {
+        button.set_bitmap_label(&bitmap);
+    }
+    button.set_tooltip(tooltip);
+    button.enable(!value.is_empty());
+    let node = node.clone();
+    let value = SecretString::from(value);
+    button.on_click(move |_| copy_to_clipboard(&button, &node, value.expose()));
+    button
+}
+
+fn fill_custom_fields(list: &ListCtrl, fields: &[CustomField], reveal: bool) {
+    list.delete_all_items();
+    for (index, field) in fields.iter().enumerate() {
+        let row = index as i64;
+        if list.insert_item(row, &field.name, None) < 0 {
+            continue;
+        }
+        let value = if field.protected && !reveal {
+            "••••••••".to_string()
+        } else {
+            field.value.expose().replace('\n', " ⏎ ")
+        };
+        list.set_item_text_by_column(row, 1, &value);
+        list.set_item_text_by_column(row, 2, if field.protected { "Yes" } else { "" });
+    }
+}
+
 fn format_option_time<T: ToString>(time: Option<T>) -> String {
     time.map(|time| time.to_string()).unwrap_or_default()
 }
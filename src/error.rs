@@ -27,6 +27,21 @@ pub enum Error {
     #[error("image conversion error: {0}")]
     Image(#[from] image::ImageError),
 
+    #[error("regex error: {0}")]
+    Regex(#[from] regex::Error),
+
+    #[error("clipboard error: {0}")]
+    Clipboard(#[from] arboard::Error),
+
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("ZIP archive error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
     #[error("{0}")]
     Str(String),
 
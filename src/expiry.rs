@@ -0,0 +1,141 @@
+use crate::keepass::KpDb;
+use chrono::NaiveDateTime;
+use keepass_ng::{
+    Uuid,
+    db::{Entry, Group, NodePtr, with_node},
+};
+
+/// An entry or group that expired or is about to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Expiring {
+    pub uuid: Uuid,
+    pub title: String,
+    pub is_group: bool,
+    pub expiry: NaiveDateTime,
+}
+
+impl Expiring {
+    pub fn is_expired(&self, now: NaiveDateTime) -> bool {
+        self.expiry <= now
+    }
+
+    /// The title with when it expires, as shown in the tray menu.
+    pub fn describe(&self, now: NaiveDateTime) -> String {
+        let kind = if self.is_group { " (group)" } else { "" };
+        if self.is_expired(now) {
+            format!("{}{kind} - expired", self.title)
+        } else {
+            format!("{}{kind} - expires {}", self.title, self.expiry.format("%Y-%m-%d"))
+        }
+    }
+}
+
+/// Entries and groups outside the recycle bin that expired or expire within `within_days` of the local time `now`,
+/// soonest first.
+pub fn expiring(kpdb: &KpDb, now: NaiveDateTime, within_days: i64) -> Vec<Expiring> {
+    let recycle_bin = kpdb.recycle_bin().map(|bin| bin.borrow().get_uuid());
+    let groups = kpdb
+        .group_paths()
+        .into_iter()
+        .filter(|(uuid, _)| Some(*uuid) != recycle_bin && !kpdb.is_recycled(*uuid))
+        .filter_map(|(uuid, _)| kpdb.get_node_by_id(uuid))
+        .collect::<Vec<_>>();
+    let mut expiring = kpdb
+        .live_entries()
+        .iter()
+        .chain(&groups)
+        .filter_map(expiry_of)
+        .filter(|item| (item.expiry - now).num_days() < within_days)
+        .collect::<Vec<_>>();
+    expiring.sort_by(|left, right| left.expiry.cmp(&right.expiry).then_with(|| left.title.cmp(&right.title)));
+    expiring
+}
+
+fn expiry_of(node: &NodePtr) -> Option<Expiring> {
+    let item = |uuid, title: Option<&str>, is_group, expiry| Expiring {
+        uuid,
+        title: title.filter(|title| !title.trim().is_empty()).unwrap_or("(no title)").to_string(),
+        is_group,
+        expiry,
+    };
+    with_node::<Entry, _, _>(node, |entry| {
+        let times = entry.get_times();
+        times
+            .get_expiry_time()
+            .filter(|_| times.get_expires())
+            .map(|expiry| item(entry.get_uuid(), entry.get_title(), false, expiry))
+    })
+    .or_else(|| {
+        with_node::<Group, _, _>(node, |group| {
+            let times = group.get_times();
+            times
+                .get_expiry_time()
+                .filter(|_| times.get_expires())
+                .map(|expiry| item(group.get_uuid(), group.get_title(), true, expiry))
+        })
+    })
+    .flatten()
+}
+
+/// A one-line summary such as "1 expired, 2 expiring soon" for the tray tooltip and notification.
+pub fn summary(items: &[Expiring], now: NaiveDateTime) -> String {
+    let expired = items.iter().filter(|item| item.is_expired(now)).count();
+    match (expired, items.len() - expired) {
+        (0, soon) => format!("{soon} expiring soon"),
+        (expired, 0) => format!("{expired} expired"),
+        (expired, soon) => format!("{expired} expired, {soon} expiring soon"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expiring, summary};
+    use crate::keepass::KpDb;
+    use chrono::{Duration, NaiveDate, NaiveDateTime};
+    use keepass_ng::db::{Entry, Group, NodePtr, with_node_mut};
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 6, 1).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    fn set_entry_expiry(node: &NodePtr, title: &str, expiry: NaiveDateTime) {
+        with_node_mut::<Entry, _, _>(node, |entry| {
+            entry.set_title(Some(title));
+            entry.get_times_mut().set_expires(true);
+            entry.get_times_mut().set_expiry_time(Some(expiry));
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn lists_expired_and_soon_expiring_nodes_soonest_first() {
+        let mut kpdb = KpDb::new();
+        let root = kpdb.get_root().unwrap().borrow().get_uuid();
+        let group = kpdb.create_new_group(root).unwrap();
+        with_node_mut::<Group, _, _>(&group, |group| {
+            group.set_title(Some("Contractors"));
+            group.get_times_mut().set_expires(true);
+            group.get_times_mut().set_expiry_time(Some(now() + Duration::days(3)));
+        })
+        .unwrap();
+        let group_uuid = group.borrow().get_uuid();
+        set_entry_expiry(&kpdb.create_new_entry(group_uuid).unwrap(), "Expired", now() - Duration::days(2));
+        set_entry_expiry(&kpdb.create_new_entry(root).unwrap(), "Soon", now() + Duration::days(10));
+        set_entry_expiry(&kpdb.create_new_entry(root).unwrap(), "Later", now() + Duration::days(90));
+        let never = kpdb.create_new_entry(root).unwrap();
+        with_node_mut::<Entry, _, _>(&never, |entry| entry.set_title(Some("Never"))).unwrap();
+
+        let items = expiring(&kpdb, now(), 14);
+        let described = items.iter().map(|item| item.describe(now())).collect::<Vec<_>>();
+        assert_eq!(
+            described,
+            [
+                "Expired - expired",
+                "Contractors (group) - expires 2025-06-04",
+                "Soon - expires 2025-06-11"
+            ]
+        );
+        assert_eq!(summary(&items, now()), "1 expired, 2 expiring soon");
+        assert_eq!(summary(&items[1..], now()), "2 expiring soon");
+    }
+}
@@ -0,0 +1,586 @@
+use crate::custom_fields::{STANDARD_FIELDS, custom_fields};
+use crate::error::Result;
+use crate::keepass::KpDb;
+use crate::placeholders;
+use crate::secret::SecretString;
+use crate::totp::OTP_FIELD;
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use keepass_ng::{
+    Uuid,
+    db::{Entry, Group, Icon, Node, NodePtr, Times, with_node},
+};
+use serde::{Serialize, Serializer};
+use std::{fmt::Write, fs, path::Path};
+
+/// File formats `export` can write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json,
+    /// A printable emergency sheet.
+    Html,
+    /// The unencrypted XML that KeePass 2.x imports and exports.
+    KeePassXml,
+}
+
+impl Format {
+    pub const ALL: [Format; 4] = [Format::Csv, Format::Json, Format::Html, Format::KeePassXml];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Format::Csv => "CSV",
+            Format::Json => "JSON",
+            Format::Html => "HTML emergency sheet",
+            Format::KeePassXml => "KeePass 2.x XML (unencrypted)",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+            Format::Html => "html",
+            Format::KeePassXml => "xml",
+        }
+    }
+
+    /// Human-readable formats show references and placeholders resolved, the others keep them for re-importing.
+    fn resolves_placeholders(self) -> bool {
+        matches!(self, Format::Csv | Format::Html)
+    }
+}
+
+/// A CSV column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+    Group,
+    Title,
+    UserName,
+    Password,
+    Url,
+    Notes,
+    Totp,
+    Tags,
+    Expires,
+    Created,
+    Modified,
+}
+
+impl Column {
+    pub const ALL: [Column; 11] = [
+        Column::Group,
+        Column::Title,
+        Column::UserName,
+        Column::Password,
+        Column::Url,
+        Column::Notes,
+        Column::Totp,
+        Column::Tags,
+        Column::Expires,
+        Column::Created,
+        Column::Modified,
+    ];
+
+    /// The columns of a KeePassXC CSV export, which most password managers can read back.
+    pub const DEFAULT: [Column; 7] = [
+        Column::Group,
+        Column::Title,
+        Column::UserName,
+        Column::Password,
+        Column::Url,
+        Column::Notes,
+        Column::Totp,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Column::Group => "Group",
+            Column::Title => "Title",
+            Column::UserName => "Username",
+            Column::Password => "Password",
+            Column::Url => "URL",
+            Column::Notes => "Notes",
+            Column::Totp => "TOTP",
+            Column::Tags => "Tags",
+            Column::Expires => "Expires",
+            Column::Created => "Created",
+            Column::Modified => "Last Modified",
+        }
+    }
+}
+
+/// A group subtree as it is written to an export.
+#[derive(Serialize)]
+pub struct ExportedGroup {
+    #[serde(serialize_with = "serialize_uuid")]
+    pub uuid: Uuid,
+    pub name: String,
+    pub notes: String,
+    pub entries: Vec<ExportedEntry>,
+    pub groups: Vec<ExportedGroup>,
+    #[serde(skip)]
+    pub icon: Icon,
+    #[serde(skip)]
+    pub times: Times,
+}
+
+impl ExportedGroup {
+    /// The number of entries in the whole subtree.
+    pub fn entry_count(&self) -> usize {
+        self.entries.len() + self.groups.iter().map(ExportedGroup::entry_count).sum::<usize>()
+    }
+}
+
+#[derive(Serialize)]
+pub struct ExportedEntry {
+    #[serde(serialize_with = "serialize_uuid")]
+    pub uuid: Uuid,
+    pub title: String,
+    pub username: String,
+    pub password: SecretString,
+    pub url: String,
+    pub notes: String,
+    pub tags: Vec<String>,
+    pub fields: Vec<ExportedField>,
+    pub attachments: Vec<ExportedAttachment>,
+    pub expires: Option<String>,
+    pub created: Option<String>,
+    pub modified: Option<String>,
+    #[serde(skip)]
+    pub icon: Icon,
+    #[serde(skip)]
+    pub times: Times,
+}
+
+impl ExportedEntry {
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|field| field.name == name)
+            .map(|field| field.value.expose())
+    }
+}
+
+#[derive(Serialize)]
+pub struct ExportedField {
+    pub name: String,
+    pub value: SecretString,
+    pub protected: bool,
+}
+
+#[derive(Serialize)]
+pub struct ExportedAttachment {
+    pub name: String,
+    #[serde(serialize_with = "serialize_base64")]
+    pub data: Vec<u8>,
+}
+
+fn serialize_uuid<S: Serializer>(uuid: &Uuid, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&uuid.to_string())
+}
+
+fn serialize_base64<S: Serializer>(data: &[u8], serializer: S) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_str(&BASE64.encode(data))
+}
+
+/// Reads group `uuid` with everything below it; the recycle bin is left out unless it is `uuid` itself.
+pub fn collect(kpdb: &KpDb, uuid: Uuid, format: Format) -> Result<ExportedGroup> {
+    let node = kpdb.get_node_by_id(uuid).ok_or("No such group")?;
+    let recycle_bin = kpdb.recycle_bin().map(|bin| bin.borrow().get_uuid()).filter(|bin| *bin != uuid);
+    collect_group(kpdb, &node, recycle_bin, format.resolves_placeholders()).ok_or_else(|| "Only groups can be exported".into())
+}
+
+fn collect_group(kpdb: &KpDb, node: &NodePtr, recycle_bin: Option<Uuid>, resolve: bool) -> Option<ExportedGroup> {
+    let mut group = with_node::<Group, _, _>(node, |group| ExportedGroup {
+        uuid: group.get_uuid(),
+        name: group.get_title().unwrap_or("").to_string(),
+        notes: group.get_notes().unwrap_or("").to_string(),
+        entries: Vec::new(),
+        groups: Vec::new(),
+        icon: group.get_icon(),
+        times: group.get_times().clone(),
+    })?;
+    group.entries = kpdb
+        .get_entries(node)
+        .iter()
+        .filter_map(|entry| with_node::<Entry, _, _>(entry, |entry| collect_entry(kpdb, entry, resolve)))
+        .collect();
+    group.groups = kpdb
+        .get_groups(node)
+        .iter()
+        .filter(|child| Some(child.borrow().get_uuid()) != recycle_bin)
+        .filter_map(|child| collect_group(kpdb, child, recycle_bin, resolve))
+        .collect();
+    Some(group)
+}
+
+fn collect_entry(kpdb: &KpDb, entry: &Entry, resolve: bool) -> ExportedEntry {
+    let value = |field: &str| {
+        if resolve {
+            placeholders::resolved_field(Some(kpdb), entry, field)
+        } else {
+            entry.get(field).unwrap_or("").to_string()
+        }
+    };
+    let times = entry.get_times();
+    ExportedEntry {
+        uuid: entry.get_uuid(),
+        title: value("Title"),
+        username: value("UserName"),
+        password: SecretString::new(value("Password")),
+        url: value("URL"),
+        notes: value("Notes"),
+        tags: entry.get_tags().to_vec(),
+        fields: custom_fields(entry)
+            .into_iter()
+            .map(|field| ExportedField {
+                value: SecretString::new(value(&field.name)),
+                name: field.name,
+                protected: field.protected,
+            })
+            .collect(),
+        attachments: entry
+            .attachments
+            .iter()
+            .map(|(name, attachment)| ExportedAttachment {
+                name: name.clone(),
+                data: attachment.data.get().to_vec(),
+            })
+            .collect(),
+        expires: times
+            .get_expires()
+            .then(|| times.get_expiry_time().map(|time| timestamp(&time)))
+            .flatten(),
+        created: times.get_creation().map(|time| timestamp(&time)),
+        modified: times.get_last_modification().map(|time| timestamp(&time)),
+        icon: entry.get_icon(),
+        times: times.clone(),
+    }
+}
+
+fn timestamp(time: &chrono::NaiveDateTime) -> String {
+    time.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+/// Writes an export to `path` so that only its owner can read it, since it holds every password in clear text.
+pub fn write_file(path: &Path, contents: &str) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)?;
+    // The mode only applies to new files; an overwritten one keeps its own.
+    #[cfg(unix)]
+    file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+    std::io::Write::write_all(&mut file, contents.as_bytes())?;
+    Ok(())
+}
+
+/// Writes `group` as `format`; `columns` only matter for CSV.
+pub fn export(group: &ExportedGroup, format: Format, columns: &[Column], database_name: &str) -> Result<String> {
+    match format {
+        Format::Csv => to_csv(group, columns),
+        Format::Json => Ok(serde_json::to_string_pretty(group)?),
+        Format::Html => Ok(to_html(group, database_name)),
+        Format::KeePassXml => Ok(to_keepass_xml(group, database_name)),
+    }
+}
+
+/// Every entry in `group` and below, with its group path starting at `group` itself.
+fn flatten(group: &ExportedGroup) -> Vec<(Vec<&str>, &ExportedEntry)> {
+    fn walk<'a>(group: &'a ExportedGroup, path: &mut Vec<&'a str>, out: &mut Vec<(Vec<&'a str>, &'a ExportedEntry)>) {
+        path.push(&group.name);
+        out.extend(group.entries.iter().map(|entry| (path.clone(), entry)));
+        for child in &group.groups {
+            walk(child, path, out);
+        }
+        path.pop();
+    }
+    let mut out = Vec::new();
+    walk(group, &mut Vec::new(), &mut out);
+    out
+}
+
+fn to_csv(group: &ExportedGroup, columns: &[Column]) -> Result<String> {
+    if columns.is_empty() {
+        return Err("Choose at least one column".into());
+    }
+    let mut writer = csv::WriterBuilder::new()
+        .quote_style(csv::QuoteStyle::Always)
+        .from_writer(Vec::new());
+    writer.write_record(columns.iter().map(|column| column.label()))?;
+    for (path, entry) in flatten(group) {
+        let record = columns.iter().map(|column| match column {
+            Column::Group => path.join("/"),
+            Column::Title => entry.title.clone(),
+            Column::UserName => entry.username.clone(),
+            Column::Password => entry.password.expose().to_string(),
+            Column::Url => entry.url.clone(),
+            Column::Notes => entry.notes.clone(),
+            Column::Totp => entry.field(OTP_FIELD).unwrap_or("").to_string(),
+            Column::Tags => entry.tags.join(","),
+            Column::Expires => entry.expires.clone().unwrap_or_default(),
+            Column::Created => entry.created.clone().unwrap_or_default(),
+            Column::Modified => entry.modified.clone().unwrap_or_default(),
+        });
+        writer.write_record(record)?;
+    }
+    let data = writer.into_inner().map_err(|error| error.to_string())?;
+    String::from_utf8(data).map_err(|error| error.to_string().into())
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            // XML 1.0 cannot carry other control characters at all.
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; font-size: 10pt; margin: 1.5cm; }
+h1 { font-size: 16pt; margin-bottom: 0; }
+h2 { font-size: 12pt; margin-top: 1.5em; border-bottom: 1px solid #888; }
+p.warning { border: 2px solid #b00; padding: 0.5em; }
+table { width: 100%; border-collapse: collapse; }
+th, td { border: 1px solid #888; padding: 0.3em; text-align: left; vertical-align: top; }
+tr { page-break-inside: avoid; }
+td.secret { font-family: monospace; font-size: 11pt; word-break: break-all; }
+td.notes { white-space: pre-wrap; }";
+
+fn to_html(group: &ExportedGroup, database_name: &str) -> String {
+    let mut html = String::new();
+    let title = escape(database_name);
+    let printed = chrono::Local::now().format("%Y-%m-%d %H:%M");
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Emergency sheet: {title}</title>\n<style>\n{HTML_STYLE}\n</style>\n</head>\n<body>\n<h1>Emergency sheet: {title}</h1>\n<p>Printed {printed}, {} entries.</p>\n<p class=\"warning\">This sheet holds passwords in plain text. Keep it locked away and destroy old copies.</p>\n",
+        group.entry_count()
+    );
+    let mut by_group: Vec<(Vec<&str>, Vec<&ExportedEntry>)> = Vec::new();
+    for (path, entry) in flatten(group) {
+        match by_group.last_mut() {
+            Some((last, entries)) if *last == path => entries.push(entry),
+            _ => by_group.push((path, vec![entry])),
+        }
+    }
+    for (path, entries) in by_group {
+        let _ = writeln!(html, "<h2>{}</h2>", escape(&path.join(" / ")));
+        html.push_str("<table>\n<tr><th>Title</th><th>Username</th><th>Password</th><th>URL</th><th>Notes</th></tr>\n");
+        for entry in entries {
+            let mut notes = escape(&entry.notes);
+            for field in &entry.fields {
+                let _ = write!(notes, "\n{}: {}", escape(&field.name), escape(field.value.expose()));
+            }
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td class=\"secret\">{}</td><td class=\"secret\">{}</td><td>{}</td><td class=\"notes\">{}</td></tr>",
+                escape(&entry.title),
+                escape(&entry.username),
+                escape(entry.password.expose()),
+                escape(&entry.url),
+                notes.trim_start()
+            );
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn to_keepass_xml(group: &ExportedGroup, database_name: &str) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\" standalone=\"yes\"?>\n<KeePassFile>\n");
+    let _ = write!(
+        xml,
+        "\t<Meta>\n\t\t<Generator>mypass</Generator>\n\t\t<DatabaseName>{}</DatabaseName>\n\t</Meta>\n\t<Root>\n",
+        escape(database_name)
+    );
+    write_xml_group(&mut xml, group, 2);
+    xml.push_str("\t</Root>\n</KeePassFile>\n");
+    xml
+}
+
+fn write_xml_group(xml: &mut String, group: &ExportedGroup, depth: usize) {
+    let indent = "\t".repeat(depth);
+    let _ = writeln!(xml, "{indent}<Group>");
+    let _ = writeln!(xml, "{indent}\t<UUID>{}</UUID>", BASE64.encode(group.uuid.as_bytes()));
+    let _ = writeln!(xml, "{indent}\t<Name>{}</Name>", escape(&group.name));
+    let _ = writeln!(xml, "{indent}\t<Notes>{}</Notes>", escape(&group.notes));
+    write_xml_icon(xml, &group.icon, depth + 1);
+    write_xml_times(xml, &group.times, depth + 1);
+    let _ = writeln!(xml, "{indent}\t<IsExpanded>True</IsExpanded>");
+    for entry in &group.entries {
+        write_xml_entry(xml, entry, depth + 1);
+    }
+    for child in &group.groups {
+        write_xml_group(xml, child, depth + 1);
+    }
+    let _ = writeln!(xml, "{indent}</Group>");
+}
+
+fn write_xml_entry(xml: &mut String, entry: &ExportedEntry, depth: usize) {
+    let indent = "\t".repeat(depth);
+    let _ = writeln!(xml, "{indent}<Entry>");
+    let _ = writeln!(xml, "{indent}\t<UUID>{}</UUID>", BASE64.encode(entry.uuid.as_bytes()));
+    write_xml_icon(xml, &entry.icon, depth + 1);
+    let _ = writeln!(xml, "{indent}\t<Tags>{}</Tags>", escape(&entry.tags.join(";")));
+    write_xml_times(xml, &entry.times, depth + 1);
+    let standard = [
+        entry.title.as_str(),
+        entry.username.as_str(),
+        entry.password.expose(),
+        entry.url.as_str(),
+        entry.notes.as_str(),
+    ];
+    let fields = STANDARD_FIELDS
+        .into_iter()
+        .zip(standard)
+        .map(|(name, value)| (name, value, name == "Password"))
+        .chain(
+            entry
+                .fields
+                .iter()
+                .map(|field| (field.name.as_str(), field.value.expose(), field.protected)),
+        );
+    for (name, value, protected) in fields {
+        let protection = if protected { " ProtectInMemory=\"True\"" } else { "" };
+        let _ = writeln!(
+            xml,
+            "{indent}\t<String>\n{indent}\t\t<Key>{}</Key>\n{indent}\t\t<Value{protection}>{}</Value>\n{indent}\t</String>",
+            escape(name),
+            escape(value)
+        );
+    }
+    // KeePass reads attachment data inline when a value has no pool reference.
+    for attachment in &entry.attachments {
+        let _ = writeln!(
+            xml,
+            "{indent}\t<Binary>\n{indent}\t\t<Key>{}</Key>\n{indent}\t\t<Value>{}</Value>\n{indent}\t</Binary>",
+            escape(&attachment.name),
+            BASE64.encode(&attachment.data)
+        );
+    }
+    let _ = writeln!(xml, "{indent}</Entry>");
+}
+
+fn write_xml_icon(xml: &mut String, icon: &Icon, depth: usize) {
+    let indent = "\t".repeat(depth);
+    match icon {
+        Icon::BuiltIn(icon_id) => {
+            let _ = writeln!(xml, "{indent}<IconID>{icon_id}</IconID>");
+        }
+        // Custom icon data is not exported, so fall back to the default key icon.
+        Icon::Custom(_) => {
+            let _ = writeln!(xml, "{indent}<IconID>0</IconID>");
+        }
+    }
+}
+
+fn write_xml_times(xml: &mut String, times: &Times, depth: usize) {
+    let indent = "\t".repeat(depth);
+    let _ = writeln!(xml, "{indent}<Times>");
+    let stamps = [
+        ("CreationTime", times.get_creation().map(|time| timestamp(&time))),
+        ("LastModificationTime", times.get_last_modification().map(|time| timestamp(&time))),
+        ("LastAccessTime", times.get_last_access().map(|time| timestamp(&time))),
+        ("ExpiryTime", times.get_expiry_time().map(|time| timestamp(&time))),
+    ];
+    for (name, stamp) in stamps {
+        if let Some(stamp) = stamp {
+            let _ = writeln!(xml, "{indent}\t<{name}>{stamp}</{name}>");
+        }
+    }
+    let expires = if times.get_expires() { "True" } else { "False" };
+    let _ = writeln!(xml, "{indent}\t<Expires>{expires}</Expires>");
+    let _ = writeln!(xml, "{indent}\t<UsageCount>{}</UsageCount>", times.get_usage_count());
+    let _ = writeln!(xml, "{indent}</Times>");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Column, Format, collect, export, write_file};
+    use crate::keepass::KpDb;
+    use keepass_ng::db::{Entry, Group, Node, with_node_mut};
+
+    fn sample() -> KpDb {
+        let mut kpdb = KpDb::new();
+        let root = kpdb.get_root().unwrap().borrow().get_uuid();
+        let group = kpdb.create_new_group(root).unwrap();
+        with_node_mut::<Group, _, _>(&group, |group| group.set_title(Some("Servers")));
+        let group = group.borrow().get_uuid();
+        let entry = kpdb.create_new_entry(group).unwrap();
+        with_node_mut::<Entry, _, _>(&entry, |entry| {
+            entry.set_title(Some("db01 <primary>"));
+            entry.set_username(Some("admin"));
+            entry.set_password(Some("p&ss\"word"));
+            entry.set_notes(Some("Password is {PASSWORD}"));
+        });
+        kpdb
+    }
+
+    #[test]
+    fn exports_csv_with_chosen_columns() {
+        let kpdb = sample();
+        let root = kpdb.get_root().unwrap().borrow().get_uuid();
+        let group = collect(&kpdb, root, Format::Csv).unwrap();
+        assert_eq!(group.entry_count(), 1);
+        let csv = export(
+            &group,
+            Format::Csv,
+            &[Column::Group, Column::Title, Column::Password, Column::Notes],
+            "Team",
+        )
+        .unwrap();
+        let root_name = &group.name;
+        assert_eq!(
+            csv,
+            format!(
+                "\"Group\",\"Title\",\"Password\",\"Notes\"\n\"{root_name}/Servers\",\"db01 <primary>\",\"p&ss\"\"word\",\"Password is p&ss\"\"word\"\n"
+            )
+        );
+        assert!(export(&group, Format::Csv, &[], "Team").is_err());
+    }
+
+    #[test]
+    fn exports_structured_formats_unresolved() {
+        let kpdb = sample();
+        let root = kpdb.get_root().unwrap().borrow().get_uuid();
+        let group = collect(&kpdb, root, Format::Json).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&export(&group, Format::Json, &[], "Team").unwrap()).unwrap();
+        let entry = &json["groups"][0]["entries"][0];
+        assert_eq!(entry["password"], "p&ss\"word");
+        assert_eq!(entry["notes"], "Password is {PASSWORD}");
+
+        let xml = export(&group, Format::KeePassXml, &[], "Team").unwrap();
+        assert!(xml.contains("<DatabaseName>Team</DatabaseName>"));
+        assert!(xml.contains("<Value ProtectInMemory=\"True\">p&amp;ss&quot;word</Value>"));
+        assert!(xml.contains("<Value>db01 &lt;primary&gt;</Value>"));
+        assert_eq!(xml.matches("<Group>").count(), 2);
+
+        let html = export(&collect(&kpdb, root, Format::Html).unwrap(), Format::Html, &[], "Team").unwrap();
+        assert!(html.contains("<td class=\"notes\">Password is p&amp;ss&quot;word</td>"));
+    }
+
+    #[test]
+    fn exports_are_readable_by_their_owner_only() {
+        let path = std::env::temp_dir().join(format!("mypass-export-{}.csv", keepass_ng::Uuid::new_v4()));
+        std::fs::write(&path, "old").unwrap();
+        write_file(&path, "Title,Password\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "Title,Password\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+}
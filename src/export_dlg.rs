@@ -0,0 +1,103 @@
+use crate::export::{Column, Format};
+use keepass_ng::Uuid;
+use wxdragon::{BoxSizer, Button, ButtonEvents, CheckBox, Choice, Dialog, FlexGridSizer, Orientation, SizerFlag, StaticText, WxWidget};
+
+/// What the user chose to export.
+pub struct ExportOptions {
+    pub format: Format,
+    /// The group whose subtree is exported; the root exports the whole database.
+    pub group: Uuid,
+    pub columns: Vec<Column>,
+}
+
+/// `groups` are the groups that can be exported with their paths, the root first; `selected` is preselected.
+pub fn show(parent: &dyn WxWidget, groups: &[(Uuid, String)], selected: Uuid) -> Option<ExportOptions> {
+    let dialog = Dialog::builder(parent, "Export").with_size(560, 420).build();
+    let warning = StaticText::builder(&dialog)
+        .with_label(
+            "Exported files are NOT encrypted. Anyone who can read the file or the printout\n\
+             sees every password in it. Store it safely and delete it when it is no longer needed.",
+        )
+        .build();
+
+    let grid = FlexGridSizer::builder(0, 2).with_vgap(8).with_hgap(8).build();
+    grid.add_growable_col(1, 1);
+    let group = Choice::builder(&dialog)
+        .with_choices(groups.iter().map(|(_, path)| path.clone()).collect())
+        .build();
+    group.set_selection(groups.iter().position(|(uuid, _)| *uuid == selected).unwrap_or(0) as u32);
+    let format = Choice::builder(&dialog)
+        .with_choices(Format::ALL.iter().map(|format| format.label().to_string()).collect())
+        .build();
+    format.set_selection(0);
+    grid.add(&StaticText::builder(&dialog).with_label("Export").build(), 0, SizerFlag::All, 4);
+    grid.add(&group, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    grid.add(&StaticText::builder(&dialog).with_label("Format").build(), 0, SizerFlag::All, 4);
+    grid.add(&format, 1, SizerFlag::All | SizerFlag::Expand, 4);
+
+    let columns_grid = FlexGridSizer::builder(0, 3).with_vgap(4).with_hgap(12).build();
+    let columns = Column::ALL
+        .iter()
+        .map(|column| {
+            let check = CheckBox::builder(&dialog)
+                .with_label(column.label())
+                .with_value(Column::DEFAULT.contains(column))
+                .build();
+            columns_grid.add(&check, 0, SizerFlag::All, 2);
+            (*column, check)
+        })
+        .collect::<Vec<_>>();
+    let columns_label = StaticText::builder(&dialog).with_label("CSV columns").build();
+    let columns_for_format = columns.iter().map(|(_, check)| *check).collect::<Vec<_>>();
+    format.on_selection_changed(move |event| {
+        let is_csv = event.get_selection().and_then(|selection| Format::ALL.get(selection as usize)) == Some(&Format::Csv);
+        columns_label.enable(is_csv);
+        for check in &columns_for_format {
+            check.enable(is_csv);
+        }
+    });
+
+    let root = BoxSizer::builder(Orientation::Vertical).build();
+    root.add(&warning, 0, SizerFlag::All | SizerFlag::Expand, 12);
+    root.add_sizer(&grid, 0, SizerFlag::Left | SizerFlag::Right | SizerFlag::Expand, 12);
+    root.add(&columns_label, 0, SizerFlag::Left | SizerFlag::Right | SizerFlag::Top, 16);
+    root.add_sizer(&columns_grid, 1, SizerFlag::All | SizerFlag::Expand, 12);
+    let actions = BoxSizer::builder(Orientation::Horizontal).build();
+    let spacer = StaticText::builder(&dialog).with_label("").build();
+    let cancel = Button::builder(&dialog).with_id(wxdragon::ID_CANCEL).with_label("Cancel").build();
+    let ok = Button::builder(&dialog).with_label("Export...").build();
+    actions.add(&spacer, 1, SizerFlag::Expand, 0);
+    actions.add(&cancel, 0, SizerFlag::All, 4);
+    actions.add(&ok, 0, SizerFlag::All, 4);
+    root.add_sizer(&actions, 0, SizerFlag::All | SizerFlag::Expand, 8);
+    dialog.set_sizer(root, true);
+    dialog.set_escape_id(wxdragon::ID_CANCEL);
+
+    let dialog_for_cancel = dialog;
+    cancel.on_click(move |_| dialog_for_cancel.end_modal(wxdragon::ID_CANCEL));
+    let dialog_for_ok = dialog;
+    ok.on_click(move |_| dialog_for_ok.end_modal(wxdragon::ID_OK));
+
+    dialog.center();
+    let result = dialog.show_modal();
+    let options = format
+        .get_selection()
+        .and_then(|selection| Format::ALL.get(selection as usize).copied())
+        .zip(
+            group
+                .get_selection()
+                .and_then(|selection| groups.get(selection as usize))
+                .map(|(uuid, _)| *uuid),
+        )
+        .map(|(format, group)| ExportOptions {
+            format,
+            group,
+            columns: columns
+                .iter()
+                .filter(|(_, check)| check.get_value())
+                .map(|(column, _)| *column)
+                .collect(),
+        });
+    dialog.destroy();
+    options.filter(|_| result == wxdragon::ID_OK)
+}
@@ -92,8 +92,10 @@ impl FaviconDownloader {
             if let Some(username) = proxy.username.as_deref() {
                 proxy_url.set_username(username).map_err(|_| "invalid proxy username")?;
             }
-            if let Some(password) = proxy.password.as_deref() {
-                proxy_url.set_password(Some(password)).map_err(|_| "invalid proxy password")?;
+            if let Some(password) = proxy.password.as_ref() {
+                proxy_url
+                    .set_password(Some(password.expose()))
+                    .map_err(|_| "invalid proxy password")?;
             }
             builder = builder.proxy(reqwest::Proxy::all(proxy_url)?);
         }
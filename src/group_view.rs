@@ -1,10 +1,11 @@
 use crate::{
+    drag_node,
     entry_view::{bitmap_for_icon, bitmap_for_icon_fixed, set_icon_button_bitmap},
     find_tree_item,
     icon_cache::icon_for_emoji,
     icon_picker::show_icon_picker,
     keepass::KpDb,
-    node_title, show_node_view,
+    node_title, placeholders, show_node_view,
 };
 use keepass_ng::{
     Uuid,
@@ -94,24 +95,7 @@ pub fn build_group_view(
     let children = group_get_children(group).unwrap_or_default();
     let mut row_icons = Vec::with_capacity(children.len());
     for child in &children {
-        let child_icon = child.borrow().get_icon();
-        let (icon_label, image_index) = match child_icon {
-            Icon::BuiltIn(icon_id) => {
-                let image_index = icon_for_emoji(&icon_id.to_string(), 20).map(|bitmap| image_list.add_bitmap(&bitmap));
-                (String::new(), image_index)
-            }
-            Icon::Custom(uuid) => {
-                let image_index = kpdb
-                    .borrow()
-                    .as_ref()
-                    .and_then(|db| db.db.as_ref())
-                    .and_then(|db| db.meta.custom_icon(uuid))
-                    .and_then(|icon| bitmap_for_icon_fixed(&icon.data, 20))
-                    .map(|bitmap| image_list.add_bitmap(&bitmap));
-                (String::new(), image_index)
-            }
-        };
-        row_icons.push((icon_label, image_index));
+        row_icons.push((String::new(), list_image_index(&image_list, child, kpdb)));
     }
     list.set_image_list(image_list, image_list_type::SMALL);
     list.insert_column(0, "Icon", ListColumnFormat::Left, 36);
@@ -140,9 +124,10 @@ pub fn build_group_view(
             .unwrap_or_default();
         list.set_item_text_by_column(row, 5, &last_modified);
         if let Some(entry) = child.borrow().downcast_ref::<Entry>() {
-            list.set_item_text_by_column(row, 3, entry.get_username().unwrap_or(""));
-            list.set_item_text_by_column(row, 4, entry.get_url().unwrap_or(""));
-            list.set_item_text_by_column(row, 6, entry.get_notes().unwrap_or(""));
+            let resolved = |field: &str| placeholders::resolved_field(kpdb.borrow().as_ref(), entry, field);
+            list.set_item_text_by_column(row, 3, &resolved("UserName"));
+            list.set_item_text_by_column(row, 4, &resolved("URL"));
+            list.set_item_text_by_column(row, 6, &resolved("Notes"));
         }
     }
 
@@ -183,10 +168,35 @@ pub fn build_group_view(
         status_bar_for_activation.set_status_text("Node selected", 0);
     });
 
+    list.on_begin_drag(move |event| {
+        let row = event.get_item_index();
+        if row < 0 {
+            return;
+        }
+        if let Some(data) = list.get_custom_data(row as u64)
+            && let Some(uuid) = data.downcast_ref::<Uuid>()
+        {
+            drag_node(&list, *uuid);
+        }
+    });
+
     sizer.add(&list, 1, SizerFlag::All | SizerFlag::Expand, 4);
     parent.set_sizer(sizer, true);
 }
 
+pub(crate) fn list_image_index(image_list: &ImageList, node: &NodePtr, kpdb: &Rc<RefCell<Option<KpDb>>>) -> Option<i32> {
+    let bitmap = match node.borrow().get_icon() {
+        Icon::BuiltIn(icon_id) => icon_for_emoji(&icon_id.to_string(), 20),
+        Icon::Custom(uuid) => kpdb
+            .borrow()
+            .as_ref()
+            .and_then(|db| db.db.as_ref())
+            .and_then(|db| db.meta.custom_icon(uuid))
+            .and_then(|icon| bitmap_for_icon_fixed(&icon.data, 20)),
+    }?;
+    Some(image_list.add_bitmap(&bitmap))
+}
+
 pub(crate) fn show_group_editor(parent: &dyn WxWidget, node: &NodePtr, kpdb: Rc<RefCell<Option<KpDb>>>) -> wxdragon::Id {
     let Some(group) = with_node::<Group, _, _>(node, |group| group.clone()) else {
         return wxdragon::ID_CANCEL;
@@ -0,0 +1,320 @@
+use crate::breach::{BreachList, Lookup};
+use crate::error::Result;
+use crate::keepass::KpDb;
+use crate::password_generator::estimate_entropy;
+use crate::placeholders;
+use chrono::NaiveDateTime;
+use keepass_ng::{
+    Uuid,
+    db::{Entry, with_node},
+};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use zeroize::Zeroizing;
+
+/// Passwords below this many bits are reported as weak; the generator rates them "weak" too.
+pub const WEAK_PASSWORD_BITS: f64 = 40.0;
+/// Entries expiring within this many days are reported before they expire.
+pub const EXPIRING_SOON_DAYS: i64 = 14;
+
+/// What the health report looks for.
+pub struct HealthOptions<'a> {
+    /// Report passwords not changed for this many days; `None` turns the check off.
+    pub max_age_days: Option<u64>,
+    pub expiring_within_days: i64,
+    /// A local copy of the breached password list to look every password up in.
+    pub breach_list: Option<&'a BreachList>,
+}
+
+/// A problem found with one entry.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Issue {
+    Breached { count: u64 },
+    Weak { bits: f64 },
+    Reused { count: usize },
+    Old { days: i64 },
+    Expired { days: i64 },
+    ExpiringSoon { days: i64 },
+    EmptyPassword,
+    EmptyUrl,
+}
+
+impl Issue {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Issue::Breached { .. } => "Breached password",
+            Issue::Weak { .. } => "Weak password",
+            Issue::Reused { .. } => "Reused password",
+            Issue::Old { .. } => "Old password",
+            Issue::Expired { .. } => "Expired",
+            Issue::ExpiringSoon { .. } => "Expiring soon",
+            Issue::EmptyPassword => "Empty password",
+            Issue::EmptyUrl => "Empty URL",
+        }
+    }
+
+    pub fn details(&self) -> String {
+        match self {
+            Issue::Breached { count } => format!("Seen {count} times in data breaches"),
+            Issue::Weak { bits } => format!("About {bits:.0} bits of entropy"),
+            Issue::Reused { count } => format!("Used by {count} entries"),
+            Issue::Old { days } => format!("Not changed for {days} days"),
+            Issue::Expired { days: 0 } => "Expired today".to_string(),
+            Issue::Expired { days } => format!("Expired {days} days ago"),
+            Issue::ExpiringSoon { days: 0 } => "Expires today".to_string(),
+            Issue::ExpiringSoon { days } => format!("Expires in {days} days"),
+            Issue::EmptyPassword => "No password is set".to_string(),
+            Issue::EmptyUrl => "No URL is set".to_string(),
+        }
+    }
+
+    /// Position in the report; the most urgent problems come first.
+    fn rank(&self) -> u8 {
+        match self {
+            Issue::Breached { .. } => 0,
+            Issue::Expired { .. } => 1,
+            Issue::Reused { .. } => 2,
+            Issue::Weak { .. } => 3,
+            Issue::EmptyPassword => 4,
+            Issue::ExpiringSoon { .. } => 5,
+            Issue::Old { .. } => 6,
+            Issue::EmptyUrl => 7,
+        }
+    }
+}
+
+pub struct Finding {
+    pub uuid: Uuid,
+    pub title: String,
+    pub issue: Issue,
+}
+
+pub struct Report {
+    pub findings: Vec<Finding>,
+    /// Passwords the breach list had no range file for.
+    pub not_checked_for_breaches: usize,
+}
+
+/// Checks every entry outside the recycle bin, as of the local time `now`.
+pub fn check(kpdb: &KpDb, options: &HealthOptions, now: NaiveDateTime) -> Result<Report> {
+    let entries = kpdb.live_entries();
+
+    let mut findings = Vec::new();
+    let mut not_checked_for_breaches = 0;
+    let mut passwords = HashMap::<[u8; 32], Vec<usize>>::new();
+    for (index, node) in entries.iter().enumerate() {
+        let Some((uuid, title, password, url, modified, expiry)) = with_node::<Entry, _, _>(node, |entry| {
+            let times = entry.get_times();
+            (
+                entry.get_uuid(),
+                entry.get_title().unwrap_or("").to_string(),
+                Zeroizing::new(placeholders::resolved_field(Some(kpdb), entry, "Password")),
+                placeholders::resolved_field(Some(kpdb), entry, "URL"),
+                times.get_last_modification(),
+                times.get_expiry_time().filter(|_| times.get_expires()),
+            )
+        }) else {
+            continue;
+        };
+        let mut report = |issue| {
+            findings.push(Finding {
+                uuid,
+                title: title.clone(),
+                issue,
+            })
+        };
+
+        if password.is_empty() {
+            report(Issue::EmptyPassword);
+        } else {
+            match options.breach_list.map(|list| list.lookup(&password)).transpose()? {
+                Some(Lookup::Breached(count)) => report(Issue::Breached { count }),
+                Some(Lookup::NotChecked) => not_checked_for_breaches += 1,
+                Some(Lookup::NotListed) | None => {}
+            }
+            let bits = estimate_entropy(&password);
+            if bits < WEAK_PASSWORD_BITS {
+                report(Issue::Weak { bits });
+            }
+            passwords.entry(Sha256::digest(password.as_bytes()).into()).or_default().push(index);
+        }
+        if url.trim().is_empty() {
+            report(Issue::EmptyUrl);
+        }
+        if let Some(max_age_days) = options.max_age_days
+            && let Some(modified) = modified
+        {
+            let days = (now - modified).num_days();
+            if days > max_age_days as i64 {
+                report(Issue::Old { days });
+            }
+        }
+        if let Some(expiry) = expiry {
+            let days = (expiry - now).num_days();
+            if expiry <= now {
+                report(Issue::Expired { days: -days });
+            } else if days < options.expiring_within_days {
+                report(Issue::ExpiringSoon { days });
+            }
+        }
+    }
+
+    for indexes in passwords.values().filter(|indexes| indexes.len() > 1) {
+        for index in indexes {
+            if let Some((uuid, title)) = with_node::<Entry, _, _>(&entries[*index], |entry| {
+                (entry.get_uuid(), entry.get_title().unwrap_or("").to_string())
+            }) {
+                findings.push(Finding {
+                    uuid,
+                    title,
+                    issue: Issue::Reused { count: indexes.len() },
+                });
+            }
+        }
+    }
+
+    findings.sort_by(|left, right| (left.issue.rank(), left.title.to_lowercase()).cmp(&(right.issue.rank(), right.title.to_lowercase())));
+    Ok(Report {
+        findings,
+        not_checked_for_breaches,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HealthOptions, Issue, check};
+    use crate::breach::BreachList;
+    use crate::keepass::KpDb;
+    use chrono::{Duration, NaiveDate, NaiveDateTime};
+    use keepass_ng::db::{Entry, with_node_mut};
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 6, 1).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    fn add_entry(kpdb: &mut KpDb, title: &str, password: &str, url: &str, modified: NaiveDateTime, expiry: Option<NaiveDateTime>) {
+        let root = kpdb.get_root().unwrap().borrow().get_uuid();
+        let node = kpdb.create_new_entry(root).unwrap();
+        with_node_mut::<Entry, _, _>(&node, |entry| {
+            entry.set_title(Some(title));
+            entry.set_password(Some(password));
+            entry.set_url(Some(url));
+            let times = entry.get_times_mut();
+            times.set_last_modification(Some(modified));
+            times.set_expires(expiry.is_some());
+            times.set_expiry_time(expiry);
+        })
+        .unwrap();
+    }
+
+    fn issues(kpdb: &KpDb, title: &str) -> Vec<Issue> {
+        let options = HealthOptions {
+            max_age_days: Some(365),
+            expiring_within_days: 14,
+            breach_list: None,
+        };
+        check(kpdb, &options, now())
+            .unwrap()
+            .findings
+            .into_iter()
+            .filter(|finding| finding.title == title)
+            .map(|finding| finding.issue)
+            .collect()
+    }
+
+    #[test]
+    fn reports_each_kind_of_problem() {
+        let mut kpdb = KpDb::new();
+        let recent = now() - Duration::days(10);
+        add_entry(&mut kpdb, "Healthy", "k7#Vq9!mZ2@xL4$w", "https://example.com", recent, None);
+        add_entry(&mut kpdb, "Weak", "hunter2", "https://weak.example", recent, None);
+        add_entry(&mut kpdb, "Common", "Password1!", "https://common.example", recent, None);
+        add_entry(&mut kpdb, "Mail", "Shared-Secret-Passw0rd!", "https://mail.example", recent, None);
+        add_entry(&mut kpdb, "Bank", "Shared-Secret-Passw0rd!", "https://bank.example", recent, None);
+        add_entry(
+            &mut kpdb,
+            "Old",
+            "Vq9!mZ2@xL4$wk7#",
+            "https://old.example",
+            now() - Duration::days(400),
+            None,
+        );
+        add_entry(
+            &mut kpdb,
+            "Expired",
+            "mZ2@xL4$wk7#Vq9!",
+            "https://expired.example",
+            recent,
+            Some(now() - Duration::days(3)),
+        );
+        add_entry(
+            &mut kpdb,
+            "Soon",
+            "xL4$wk7#Vq9!mZ2@",
+            "https://soon.example",
+            recent,
+            Some(now() + Duration::days(5)),
+        );
+        add_entry(&mut kpdb, "Blank", "", "", recent, None);
+
+        assert!(issues(&kpdb, "Healthy").is_empty());
+        assert!(matches!(issues(&kpdb, "Weak")[..], [Issue::Weak { .. }]));
+        assert!(matches!(issues(&kpdb, "Common")[..], [Issue::Weak { .. }]));
+        assert_eq!(issues(&kpdb, "Mail"), [Issue::Reused { count: 2 }]);
+        assert_eq!(issues(&kpdb, "Bank"), [Issue::Reused { count: 2 }]);
+        assert_eq!(issues(&kpdb, "Old"), [Issue::Old { days: 400 }]);
+        assert_eq!(issues(&kpdb, "Expired"), [Issue::Expired { days: 3 }]);
+        assert_eq!(issues(&kpdb, "Soon"), [Issue::ExpiringSoon { days: 5 }]);
+        assert_eq!(issues(&kpdb, "Blank"), [Issue::EmptyPassword, Issue::EmptyUrl]);
+    }
+
+    #[test]
+    fn skips_the_age_check_when_turned_off() {
+        let mut kpdb = KpDb::new();
+        add_entry(
+            &mut kpdb,
+            "Old",
+            "Vq9!mZ2@xL4$wk7#",
+            "https://old.example",
+            now() - Duration::days(4000),
+            None,
+        );
+        let options = HealthOptions {
+            max_age_days: None,
+            expiring_within_days: 14,
+            breach_list: None,
+        };
+        assert!(check(&kpdb, &options, now()).unwrap().findings.is_empty());
+    }
+
+    #[test]
+    fn flags_breached_passwords_from_a_local_list() {
+        let mut kpdb = KpDb::new();
+        let recent = now() - Duration::days(10);
+        add_entry(&mut kpdb, "Breached", "Tr0ub4dor&3", "https://example.com", recent, None);
+        add_entry(&mut kpdb, "Safe", "k7#Vq9!mZ2@xL4$w", "https://example.com", recent, None);
+        let list = BreachList::open(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/hibp-sorted.txt")).unwrap();
+        let options = HealthOptions {
+            max_age_days: None,
+            expiring_within_days: 14,
+            breach_list: Some(&list),
+        };
+        let report = check(&kpdb, &options, now()).unwrap();
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(
+            (report.findings[0].title.as_str(), &report.findings[0].issue),
+            ("Breached", &Issue::Breached { count: 3 })
+        );
+        assert_eq!(report.not_checked_for_breaches, 0);
+
+        // The range fixture only holds a few prefixes; the safe password's range is missing.
+        let partial = BreachList::open(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/hibp-ranges")).unwrap();
+        let options = HealthOptions {
+            breach_list: Some(&partial),
+            ..options
+        };
+        let report = check(&kpdb, &options, now()).unwrap();
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.not_checked_for_breaches, 1);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{group_view::list_image_index, health::Report, keepass::KpDb, search_view::show_entry_details};
+use keepass_ng::Uuid;
+use std::{cell::RefCell, collections::HashSet, rc::Rc};
+use wxdragon::{
+    BoxSizer, Frame, HasItemData, ImageList, ListColumnFormat, ListCtrl, ListCtrlStyle, Orientation, Panel, SizerFlag, StaticText,
+    StatusBar, TreeCtrl, WxWidget, image_list_type,
+};
+
+/// Lists the findings of `report`; `notice` explains a check that could not run.
+pub fn build_health_view(
+    parent: &Panel,
+    frame: Frame,
+    report: &Report,
+    notice: Option<&str>,
+    tree: &TreeCtrl,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+    status_bar: &StatusBar,
+) {
+    let findings = &report.findings;
+    let sizer = BoxSizer::builder(Orientation::Vertical).build();
+    let affected = findings.iter().map(|finding| finding.uuid).collect::<HashSet<_>>().len();
+    let summary = if findings.is_empty() {
+        "Database health: no problems found".to_string()
+    } else {
+        format!("Database health: {} problems in {affected} entries", findings.len())
+    };
+    let title = StaticText::builder(parent).with_label(&summary).build();
+    sizer.add(&title, 0, SizerFlag::All | SizerFlag::Expand, 12);
+    let mut notices = notice.map(str::to_string).into_iter().collect::<Vec<_>>();
+    if report.not_checked_for_breaches > 0 {
+        notices.push(format!(
+            "{} passwords were not checked for breaches because their range files are missing from the list",
+            report.not_checked_for_breaches
+        ));
+    }
+    if !notices.is_empty() {
+        let notice = StaticText::builder(parent).with_label(&notices.join("\n")).build();
+        sizer.add(
+            &notice,
+            0,
+            SizerFlag::Left | SizerFlag::Right | SizerFlag::Bottom | SizerFlag::Expand,
+            12,
+        );
+    }
+
+    let list = ListCtrl::builder(parent)
+        .with_style(ListCtrlStyle::Report | ListCtrlStyle::SingleSel | ListCtrlStyle::HRules | ListCtrlStyle::VRules)
+        .build();
+    let image_list = ImageList::new(20, 20, false, 0);
+    let nodes = findings
+        .iter()
+        .map(|finding| kpdb.borrow().as_ref().and_then(|db| db.get_node_by_id(finding.uuid)))
+        .collect::<Vec<_>>();
+    let row_icons = nodes
+        .iter()
+        .map(|node| node.as_ref().and_then(|node| list_image_index(&image_list, node, kpdb)))
+        .collect::<Vec<_>>();
+    list.set_image_list(image_list, image_list_type::SMALL);
+    list.insert_column(0, "Problem", ListColumnFormat::Left, 140);
+    list.insert_column(1, "Title", ListColumnFormat::Left, 180);
+    list.insert_column(2, "Details", ListColumnFormat::Left, 200);
+    list.insert_column(3, "Group", ListColumnFormat::Left, -1);
+    for (index, finding) in findings.iter().enumerate() {
+        let row = index as i64;
+        if list.insert_item(row, finding.issue.label(), row_icons[index]) < 0 {
+            continue;
+        }
+        list.set_custom_data(row as u64, finding.uuid);
+        list.set_item_text_by_column(row, 1, &finding.title);
+        list.set_item_text_by_column(row, 2, &finding.issue.details());
+        let group_path = nodes[index]
+            .as_ref()
+            .and_then(|node| kpdb.borrow().as_ref().map(|db| db.get_group_path(node).join(" / ")))
+            .unwrap_or_default();
+        list.set_item_text_by_column(row, 3, &group_path);
+    }
+    sizer.add(&list, 1, SizerFlag::All | SizerFlag::Expand, 4);
+
+    let details = Panel::builder(parent).build();
+    sizer.add(&details, 2, SizerFlag::All | SizerFlag::Expand, 0);
+    let current_entry = Rc::new(RefCell::new(None::<Panel>));
+
+    let tree_for_activation = *tree;
+    let kpdb_for_activation = Rc::clone(kpdb);
+    let status_bar_for_activation = *status_bar;
+    list.on_item_activated(move |event| {
+        let row = event.get_item_index();
+        if row < 0 {
+            return;
+        }
+        let Some(data) = list.get_custom_data(row as u64) else {
+            return;
+        };
+        let Some(uuid) = data.downcast_ref::<Uuid>() else {
+            return;
+        };
+        show_entry_details(frame, &details, &current_entry, *uuid, &tree_for_activation, &kpdb_for_activation);
+        status_bar_for_activation.set_status_text("Entry with a health problem selected", 0);
+    });
+
+    parent.set_sizer(sizer, true);
+}
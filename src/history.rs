@@ -0,0 +1,161 @@
+use crate::custom_fields::{CustomField, custom_fields};
+use keepass_ng::db::{Entry, History, Node};
+
+/// A field whose value differs between two versions of an entry.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+    /// Passwords and protected fields are masked unless the user asks to see them.
+    pub protected: bool,
+}
+
+/// Past versions of `entry`, newest first.
+pub fn versions(entry: &Entry) -> Vec<Entry> {
+    entry
+        .history
+        .as_ref()
+        .map(|history| history.get_entries().clone())
+        .unwrap_or_default()
+}
+
+/// Field-by-field differences from `old` to `new`; attachments are compared by name and size.
+pub fn diff(old: &Entry, new: &Entry) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    let mut compare = |field: &str, old: String, new: String, protected: bool| {
+        if old != new {
+            changes.push(FieldChange {
+                field: field.to_string(),
+                old,
+                new,
+                protected,
+            });
+        }
+    };
+    let text = |value: Option<&str>| value.unwrap_or("").to_string();
+    compare("Title", text(old.get_title()), text(new.get_title()), false);
+    compare("Username", text(old.get_username()), text(new.get_username()), false);
+    compare("Password", text(old.get_password()), text(new.get_password()), true);
+    compare("URL", text(old.get_url()), text(new.get_url()), false);
+    compare("Notes", text(old.get_notes()), text(new.get_notes()), false);
+    compare("Tags", old.get_tags().join(", "), new.get_tags().join(", "), false);
+    compare("Expires", expiry(old), expiry(new), false);
+
+    let old_fields = custom_fields(old);
+    let new_fields = custom_fields(new);
+    let mut names = old_fields
+        .iter()
+        .chain(&new_fields)
+        .map(|field| field.name.clone())
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    for name in names {
+        let field = |fields: &[CustomField]| fields.iter().find(|field| field.name == name).cloned();
+        let (old_field, new_field) = (field(&old_fields), field(&new_fields));
+        let protected = [&old_field, &new_field].into_iter().flatten().any(|field| field.protected);
+        let value = |field: Option<CustomField>| field.map(|field| field.value.expose().to_string()).unwrap_or_default();
+        compare(&name, value(old_field), value(new_field), protected);
+    }
+    compare("Attachments", attachments(old), attachments(new), false);
+    changes
+}
+
+/// Makes version `index` the current content of `entry`, keeping the older state in the history.
+pub fn restore(entry: &mut Entry, index: usize) -> bool {
+    let Some(version) = versions(entry).into_iter().nth(index) else {
+        return false;
+    };
+    let parent = entry.get_parent();
+    let mut times = entry.get_times().clone();
+    times.set_expires(version.get_times().get_expires());
+    times.set_expiry_time(version.get_times().get_expiry_time());
+    let history = entry.history.take();
+    *entry = version;
+    entry.set_parent(parent);
+    *entry.get_times_mut() = times;
+    entry.history = history;
+    entry.update_history();
+    true
+}
+
+/// Drops version `index` from the history of `entry`.
+pub fn remove_version(entry: &mut Entry, index: usize) -> bool {
+    let mut kept = versions(entry);
+    if index >= kept.len() {
+        return false;
+    }
+    kept.remove(index);
+    entry.history = Some(history_of(kept));
+    true
+}
+
+/// A history holding `versions`, newest first.
+pub fn history_of(versions: Vec<Entry>) -> History {
+    let mut history = History::default();
+    // `add_entry` puts each version in front of the ones added before it.
+    for version in versions.into_iter().rev() {
+        history.add_entry(version);
+    }
+    history
+}
+
+fn expiry(entry: &Entry) -> String {
+    entry
+        .get_times()
+        .get_expiry_time()
+        .filter(|_| entry.get_times().get_expires())
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| "Never".to_string())
+}
+
+fn attachments(entry: &Entry) -> String {
+    let mut names = entry
+        .attachments
+        .iter()
+        .map(|(name, attachment)| format!("{name} ({} bytes)", attachment.data.get().len()))
+        .collect::<Vec<_>>();
+    names.sort();
+    names.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, remove_version, restore, versions};
+    use crate::keepass::KpDb;
+    use keepass_ng::db::{Entry, with_node, with_node_mut};
+
+    #[test]
+    fn restores_and_removes_versions() {
+        let mut kpdb = KpDb::new();
+        let root = kpdb.get_root().unwrap().borrow().get_uuid();
+        let node = kpdb.create_new_entry(root).unwrap();
+        with_node_mut::<Entry, _, _>(&node, |entry| {
+            entry.set_title(Some("Registry"));
+            entry.set_password(Some("first"));
+            entry.update_history();
+            entry.set_password(Some("second"));
+            entry.update_history();
+        });
+
+        let entry = with_node::<Entry, _, _>(&node, |entry| entry.clone()).unwrap();
+        let history = versions(&entry);
+        let oldest = history.iter().position(|version| version.get_password() == Some("first")).unwrap();
+        let changes = diff(&history[oldest], &entry);
+        assert_eq!(changes.len(), 1);
+        assert_eq!((changes[0].field.as_str(), changes[0].protected), ("Password", true));
+        assert_eq!((changes[0].old.as_str(), changes[0].new.as_str()), ("first", "second"));
+
+        with_node_mut::<Entry, _, _>(&node, |entry| {
+            assert!(restore(entry, oldest));
+            assert_eq!(entry.get_password(), Some("first"));
+            assert_eq!(entry.get_title(), Some("Registry"));
+            let count = versions(entry).len();
+            assert!(count > history.len());
+            assert!(remove_version(entry, count - 1));
+            assert_eq!(versions(entry).len(), count - 1);
+            assert!(!remove_version(entry, count));
+        });
+    }
+}
@@ -0,0 +1,155 @@
+use crate::history;
+use keepass_ng::db::{Entry, Node};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+use wxdragon::{
+    BoxSizer, Button, ButtonEvents, CheckBox, ListColumnFormat, ListCtrl, ListCtrlStyle, MessageDialog, MessageDialogStyle, Notebook,
+    Orientation, Panel, SizerFlag, StaticText, WxWidget,
+};
+
+/// Builds the "History" page of an entry: past versions newest first and a field diff against `current`.
+/// Deleting removes the version from `versions` before `on_delete` is called; both callbacks get the version index.
+pub fn build_history_page(
+    notebook: &Notebook,
+    current: &Entry,
+    versions: Rc<RefCell<Vec<Entry>>>,
+    on_restore: Rc<dyn Fn(usize)>,
+    on_delete: Rc<dyn Fn(usize)>,
+) -> Panel {
+    let page = Panel::builder(notebook).build();
+    let page_sizer = BoxSizer::builder(Orientation::Horizontal).build();
+
+    let versions_sizer = BoxSizer::builder(Orientation::Vertical).build();
+    versions_sizer.add(
+        &StaticText::builder(&page).with_label("Versions").build(),
+        0,
+        SizerFlag::All | SizerFlag::Expand,
+        4,
+    );
+    let versions_list = ListCtrl::builder(&page)
+        .with_style(ListCtrlStyle::Report | ListCtrlStyle::SingleSel | ListCtrlStyle::VRules | ListCtrlStyle::HRules)
+        .build();
+    versions_list.insert_column(0, "Modified", ListColumnFormat::Left, 160);
+    versions_list.insert_column(1, "Title", ListColumnFormat::Left, -1);
+    fill_versions(&versions_list, &versions.borrow());
+    versions_sizer.add(&versions_list, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    let buttons = BoxSizer::builder(Orientation::Horizontal).build();
+    let restore = Button::builder(&page).with_label("Restore").build();
+    let delete = Button::builder(&page).with_label("Delete").build();
+    restore.enable(false);
+    delete.enable(false);
+    buttons.add(&restore, 0, SizerFlag::All, 4);
+    buttons.add(&delete, 0, SizerFlag::All, 4);
+    versions_sizer.add_sizer(&buttons, 0, SizerFlag::All, 0);
+
+    let diff_sizer = BoxSizer::builder(Orientation::Vertical).build();
+    let show_protected = CheckBox::builder(&page).with_label("Show passwords").build();
+    diff_sizer.add(&show_protected, 0, SizerFlag::All, 4);
+    let diff_list = ListCtrl::builder(&page)
+        .with_style(ListCtrlStyle::Report | ListCtrlStyle::SingleSel | ListCtrlStyle::VRules | ListCtrlStyle::HRules)
+        .build();
+    diff_list.insert_column(0, "Field", ListColumnFormat::Left, 110);
+    diff_list.insert_column(1, "This version", ListColumnFormat::Left, 180);
+    diff_list.insert_column(2, "Current", ListColumnFormat::Left, -1);
+    diff_sizer.add(&diff_list, 1, SizerFlag::All | SizerFlag::Expand, 4);
+
+    page_sizer.add_sizer(&versions_sizer, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    page_sizer.add_sizer(&diff_sizer, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    page.set_sizer(page_sizer, true);
+
+    let current = Rc::new(current.clone());
+    let selected = Rc::new(Cell::new(None::<usize>));
+    let show_diff = {
+        let versions = Rc::clone(&versions);
+        let current = Rc::clone(&current);
+        let selected = Rc::clone(&selected);
+        Rc::new(move || {
+            diff_list.delete_all_items();
+            let versions = versions.borrow();
+            let Some(version) = selected.get().and_then(|index| versions.get(index)) else {
+                return;
+            };
+            fill_diff(&diff_list, version, &current, show_protected.get_value());
+        })
+    };
+
+    let selected_for_select = Rc::clone(&selected);
+    let show_diff_for_select = Rc::clone(&show_diff);
+    versions_list.on_item_selected(move |event| {
+        let index = usize::try_from(event.get_item_index()).ok();
+        selected_for_select.set(index);
+        restore.enable(index.is_some());
+        delete.enable(index.is_some());
+        show_diff_for_select();
+    });
+    let show_diff_for_toggle = Rc::clone(&show_diff);
+    show_protected.on_toggled(move |_| show_diff_for_toggle());
+
+    let selected_for_restore = Rc::clone(&selected);
+    restore.on_click(move |_| {
+        if let Some(index) = selected_for_restore.get() {
+            on_restore(index);
+        }
+    });
+    let selected_for_delete = Rc::clone(&selected);
+    delete.on_click(move |_| {
+        let Some(index) = selected_for_delete.get() else {
+            return;
+        };
+        let confirm = MessageDialog::builder(&versions_list, "Delete this version from the history?", "Delete version")
+            .with_style(MessageDialogStyle::YesNo | MessageDialogStyle::IconWarning)
+            .build();
+        let answer = confirm.show_modal();
+        confirm.destroy();
+        if answer != wxdragon::ID_YES || index >= versions.borrow().len() {
+            return;
+        }
+        versions.borrow_mut().remove(index);
+        fill_versions(&versions_list, &versions.borrow());
+        selected_for_delete.set(None);
+        restore.enable(false);
+        delete.enable(false);
+        show_diff();
+        on_delete(index);
+    });
+    page
+}
+
+fn fill_versions(list: &ListCtrl, versions: &[Entry]) {
+    list.delete_all_items();
+    for (index, version) in versions.iter().enumerate() {
+        let row = index as i64;
+        let modified = version
+            .get_times()
+            .get_last_modification()
+            .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        if list.insert_item(row, &modified, None) >= 0 {
+            list.set_item_text_by_column(row, 1, version.get_title().unwrap_or("(no title)"));
+        }
+    }
+}
+
+fn fill_diff(list: &ListCtrl, version: &Entry, current: &Entry, show_protected: bool) {
+    let changes = history::diff(version, current);
+    if changes.is_empty() {
+        list.insert_item(0, "(same as current)", None);
+        return;
+    }
+    let shown = |protected: bool, value: &str| {
+        if protected && !show_protected && !value.is_empty() {
+            "••••••••".to_string()
+        } else {
+            value.replace('\n', " ⏎ ")
+        }
+    };
+    for (index, change) in changes.iter().enumerate() {
+        let row = index as i64;
+        if list.insert_item(row, &change.field, None) >= 0 {
+            list.set_item_text_by_column(row, 1, &shown(change.protected, &change.old));
+            list.set_item_text_by_column(row, 2, &shown(change.protected, &change.new));
+        }
+    }
+}
@@ -0,0 +1,773 @@
+use crate::custom_fields::{CustomField, STANDARD_FIELDS, set_custom_fields};
+use crate::error::Result;
+use crate::keepass::KpDb;
+use crate::secret::SecretString;
+use crate::totp::OTP_FIELD;
+use keepass_ng::{
+    Uuid,
+    db::{Entry, Group, Node, group_get_children, node_is_group, with_node_mut},
+};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use std::{io::Read, path::Path};
+
+/// Export formats `parse` understands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Any CSV file; the columns are assigned with a `CsvMapping`.
+    Csv,
+    Bitwarden,
+    OnePassword1Pux,
+    OnePasswordCsv,
+    LastPass,
+    KeePassXc,
+}
+
+impl Format {
+    pub const ALL: [Format; 6] = [
+        Format::Csv,
+        Format::Bitwarden,
+        Format::OnePassword1Pux,
+        Format::OnePasswordCsv,
+        Format::LastPass,
+        Format::KeePassXc,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Format::Csv => "Generic CSV",
+            Format::Bitwarden => "Bitwarden JSON",
+            Format::OnePassword1Pux => "1Password 1PUX",
+            Format::OnePasswordCsv => "1Password CSV",
+            Format::LastPass => "LastPass CSV",
+            Format::KeePassXc => "KeePassXC CSV",
+        }
+    }
+
+    /// The most likely format of the file at `path` with contents `data`.
+    pub fn guess(path: &Path, data: &[u8]) -> Format {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if extension == "1pux" || data.starts_with(b"PK\x03\x04") {
+            return Format::OnePassword1Pux;
+        }
+        if extension == "json" {
+            return Format::Bitwarden;
+        }
+        let header = read_csv(data)
+            .ok()
+            .and_then(|rows| rows.into_iter().next())
+            .unwrap_or_default()
+            .iter()
+            .map(|column| column.trim().to_lowercase())
+            .collect::<Vec<_>>();
+        let has = |names: &[&str]| names.iter().all(|name| header.iter().any(|column| column == name));
+        if has(&["group", "title", "username", "password", "totp"]) {
+            Format::KeePassXc
+        } else if has(&["url", "username", "password", "extra", "name", "grouping"]) {
+            Format::LastPass
+        } else if has(&["title", "url", "username", "password", "otpauth"]) {
+            Format::OnePasswordCsv
+        } else {
+            Format::Csv
+        }
+    }
+}
+
+/// One entry read from an export, before it is added to the database.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportedEntry {
+    /// Folder names from the outermost down; empty for entries at the top of the export.
+    pub path: Vec<String>,
+    pub title: String,
+    pub username: String,
+    pub password: SecretString,
+    pub url: String,
+    pub notes: String,
+    pub tags: Vec<String>,
+    pub fields: Vec<CustomField>,
+    /// An `otpauth://` URI or a bare Base32 seed.
+    pub totp: Option<SecretString>,
+}
+
+impl ImportedEntry {
+    /// Adds a custom field, skipping empty values and renaming clashes with standard or earlier fields.
+    pub fn add_field(&mut self, name: &str, value: &str, protected: bool) {
+        if value.is_empty() {
+            return;
+        }
+        let base = match name.trim() {
+            "" => "Field",
+            name => name,
+        };
+        let taken = |name: &str| {
+            name == OTP_FIELD
+                || STANDARD_FIELDS.iter().any(|standard| standard.eq_ignore_ascii_case(name))
+                || self.fields.iter().any(|field| field.name == name)
+        };
+        let mut name = base.to_string();
+        let mut counter = 2;
+        while taken(&name) {
+            name = format!("{base} ({counter})");
+            counter += 1;
+        }
+        self.fields.push(CustomField {
+            name,
+            value: SecretString::from(value),
+            protected,
+        });
+    }
+
+    /// Whether the entry holds nothing worth importing, like the empty rows at the end of many CSV files.
+    fn is_blank(&self) -> bool {
+        self.title.is_empty()
+            && self.username.is_empty()
+            && self.password.is_empty()
+            && self.url.is_empty()
+            && self.notes.is_empty()
+            && self.fields.is_empty()
+            && self.totp.is_none()
+    }
+
+    fn set_totp(&mut self, value: &str) {
+        let value = value.trim();
+        if value.is_empty() {
+            return;
+        }
+        if self.totp.is_none() {
+            self.totp = Some(SecretString::from(value));
+        } else {
+            self.add_field("TOTP", value, true);
+        }
+    }
+}
+
+/// What a CSV column is imported as.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    Ignore,
+    Title,
+    UserName,
+    Password,
+    Url,
+    Notes,
+    /// A folder path; nested folders are separated by `/`.
+    Group,
+    Totp,
+    /// Tags separated by `,` or `;`.
+    Tags,
+    /// A custom field named after the column header.
+    Field,
+    /// Like `Field`, but stored as a protected value.
+    ProtectedField,
+}
+
+impl Target {
+    pub const ALL: [Target; 11] = [
+        Target::Ignore,
+        Target::Title,
+        Target::UserName,
+        Target::Password,
+        Target::Url,
+        Target::Notes,
+        Target::Group,
+        Target::Totp,
+        Target::Tags,
+        Target::Field,
+        Target::ProtectedField,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Target::Ignore => "(ignore)",
+            Target::Title => "Title",
+            Target::UserName => "Username",
+            Target::Password => "Password",
+            Target::Url => "URL",
+            Target::Notes => "Notes",
+            Target::Group => "Group",
+            Target::Totp => "TOTP",
+            Target::Tags => "Tags",
+            Target::Field => "Custom field",
+            Target::ProtectedField => "Protected custom field",
+        }
+    }
+}
+
+/// How the columns of a CSV file map onto entries.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CsvMapping {
+    /// One target per column; missing columns are ignored.
+    pub columns: Vec<Target>,
+    /// The first row names the columns instead of holding an entry.
+    pub has_header: bool,
+    pub group_separator: char,
+    /// Drops the first folder of every path, for exports that include the database root.
+    pub strip_root_group: bool,
+}
+
+impl CsvMapping {
+    /// Guesses the meaning of every column from its header.
+    pub fn guess(header: &[String]) -> Self {
+        Self {
+            columns: header.iter().map(|name| guess_target(name)).collect(),
+            has_header: true,
+            group_separator: '/',
+            strip_root_group: false,
+        }
+    }
+}
+
+fn guess_target(header: &str) -> Target {
+    match header.trim().to_lowercase().as_str() {
+        "title" | "name" => Target::Title,
+        "username" | "user name" | "user" | "login" | "login_username" => Target::UserName,
+        "password" | "pass" | "login_password" => Target::Password,
+        "url" | "uri" | "website" | "web site" | "login_uri" => Target::Url,
+        "notes" | "note" | "extra" | "comments" => Target::Notes,
+        "group" | "folder" | "grouping" | "path" => Target::Group,
+        "totp" | "otp" | "otpauth" | "login_totp" | "one-time password" => Target::Totp,
+        "tags" => Target::Tags,
+        // Bookkeeping columns of the common exports.
+        "fav" | "favorite" | "archived" | "icon" | "last modified" | "created" | "type" | "reprompt" => Target::Ignore,
+        _ => Target::Field,
+    }
+}
+
+/// Splits CSV `data` into rows of columns.
+pub fn read_csv(data: &[u8]) -> Result<Vec<Vec<String>>> {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    let mut reader = csv::ReaderBuilder::new().has_headers(false).flexible(true).from_reader(data);
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        rows.push(record.iter().map(str::to_string).collect());
+    }
+    Ok(rows)
+}
+
+/// Turns CSV `rows` into entries according to `mapping`, skipping rows without any content.
+pub fn parse_csv(rows: &[Vec<String>], mapping: &CsvMapping) -> Vec<ImportedEntry> {
+    let (header, rows) = match rows.split_first() {
+        Some((header, rows)) if mapping.has_header => (header.as_slice(), rows),
+        _ => (&[][..], rows),
+    };
+    let mut entries = Vec::new();
+    for row in rows {
+        let mut entry = ImportedEntry::default();
+        for (index, value) in row.iter().enumerate() {
+            let target = mapping.columns.get(index).copied().unwrap_or(Target::Ignore);
+            match target {
+                Target::Ignore => {}
+                Target::Title => entry.title = value.clone(),
+                Target::UserName => entry.username = value.clone(),
+                Target::Password => entry.password = SecretString::from(value.as_str()),
+                Target::Url => entry.url = value.clone(),
+                Target::Notes => entry.notes = value.clone(),
+                Target::Group => entry.path = split_path(value, mapping.group_separator),
+                Target::Totp => entry.set_totp(value),
+                Target::Tags => entry.tags = split_tags(value),
+                Target::Field | Target::ProtectedField => {
+                    let name = header.get(index).cloned().unwrap_or_else(|| format!("Column {}", index + 1));
+                    entry.add_field(&name, value, target == Target::ProtectedField);
+                }
+            }
+        }
+        if mapping.strip_root_group && !entry.path.is_empty() {
+            entry.path.remove(0);
+        }
+        if !entry.is_blank() {
+            entries.push(entry);
+        }
+    }
+    entries
+}
+
+fn split_path(value: &str, separator: char) -> Vec<String> {
+    value
+        .split(separator)
+        .map(str::trim)
+        .filter(|folder| !folder.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn split_tags(value: &str) -> Vec<String> {
+    value
+        .split([',', ';'])
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Reads an export in one of the native formats; `Format::Csv` needs `parse_csv` with a mapping instead.
+pub fn parse(format: Format, data: &[u8]) -> Result<Vec<ImportedEntry>> {
+    match format {
+        Format::Csv => Err("Generic CSV files need a column mapping".into()),
+        Format::Bitwarden => parse_bitwarden(data),
+        Format::OnePassword1Pux => parse_1pux(data),
+        Format::OnePasswordCsv => parse_native_csv(data, format, &["title", "username", "password", "url"]),
+        Format::LastPass => parse_native_csv(data, format, &["url", "username", "password", "name", "grouping"]),
+        Format::KeePassXc => parse_native_csv(data, format, &["group", "title", "username", "password", "url"]),
+    }
+}
+
+fn parse_native_csv(data: &[u8], format: Format, required: &[&str]) -> Result<Vec<ImportedEntry>> {
+    let rows = read_csv(data)?;
+    let header = rows.first().ok_or("The file is empty")?;
+    let columns = header.iter().map(|column| column.trim().to_lowercase()).collect::<Vec<_>>();
+    if let Some(missing) = required.iter().find(|name| !columns.iter().any(|column| column == *name)) {
+        return Err(format!("This is not a {} export: the {missing} column is missing", format.label()).into());
+    }
+    let mut mapping = CsvMapping::guess(header);
+    match format {
+        // LastPass nests folders with backslashes.
+        Format::LastPass => mapping.group_separator = '\\',
+        // KeePassXC paths start with the root group of the exported database.
+        Format::KeePassXc => mapping.strip_root_group = true,
+        _ => {}
+    }
+    let mut entries = parse_csv(&rows, &mapping);
+    if format == Format::LastPass {
+        // Secure notes carry this placeholder instead of a URL.
+        for entry in &mut entries {
+            if entry.url == "http://sn" {
+                entry.url.clear();
+            }
+        }
+    }
+    Ok(entries)
+}
+
+#[derive(Deserialize)]
+struct BitwardenExport {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<BitwardenFolder>,
+    #[serde(default)]
+    items: Vec<BitwardenItem>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenFolder {
+    id: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BitwardenItem {
+    name: Option<String>,
+    notes: Option<String>,
+    folder_id: Option<String>,
+    login: Option<BitwardenLogin>,
+    #[serde(default)]
+    fields: Vec<BitwardenField>,
+    card: Option<serde_json::Map<String, JsonValue>>,
+    identity: Option<serde_json::Map<String, JsonValue>>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenLogin {
+    username: Option<String>,
+    password: Option<SecretString>,
+    totp: Option<SecretString>,
+    #[serde(default)]
+    uris: Vec<BitwardenUri>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenUri {
+    uri: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenField {
+    name: Option<String>,
+    value: Option<String>,
+    /// 0 text, 1 hidden, 2 boolean, 3 linked.
+    #[serde(rename = "type")]
+    kind: u8,
+}
+
+/// Card and identity properties that are stored as protected fields.
+const SENSITIVE_PROPERTIES: [&str; 5] = ["number", "code", "ssn", "passportNumber", "licenseNumber"];
+
+fn parse_bitwarden(data: &[u8]) -> Result<Vec<ImportedEntry>> {
+    let export: BitwardenExport = serde_json::from_slice(data)?;
+    if export.encrypted {
+        return Err("Encrypted Bitwarden exports cannot be imported; export the vault as unencrypted JSON".into());
+    }
+    let mut entries = Vec::new();
+    for item in export.items {
+        let mut entry = ImportedEntry {
+            title: item.name.unwrap_or_default(),
+            notes: item.notes.unwrap_or_default(),
+            ..Default::default()
+        };
+        if let Some(folder) = item.folder_id.and_then(|id| export.folders.iter().find(|folder| folder.id == id)) {
+            // Bitwarden shows folders named "a/b" as "b" nested in "a".
+            entry.path = split_path(&folder.name, '/');
+        }
+        if let Some(login) = item.login {
+            entry.username = login.username.unwrap_or_default();
+            entry.password = login.password.unwrap_or_default();
+            if let Some(totp) = login.totp {
+                entry.set_totp(totp.expose());
+            }
+            let mut uris = login.uris.into_iter().filter_map(|uri| uri.uri).filter(|uri| !uri.is_empty());
+            entry.url = uris.next().unwrap_or_default();
+            // Further URLs use the names KeePassXC and Keepass2Android understand.
+            for (index, uri) in uris.enumerate() {
+                entry.add_field(&format!("KP2A_URL_{}", index + 1), &uri, false);
+            }
+        }
+        for field in item.fields {
+            if field.kind == 3 {
+                continue;
+            }
+            let name = field.name.unwrap_or_default();
+            entry.add_field(&name, &field.value.unwrap_or_default(), field.kind == 1);
+        }
+        for properties in [item.card, item.identity].into_iter().flatten() {
+            for (name, value) in properties {
+                if let JsonValue::String(value) = value {
+                    entry.add_field(&name, &value, SENSITIVE_PROPERTIES.contains(&name.as_str()));
+                }
+            }
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Reads the `export.data` document of a 1Password 1PUX archive.
+fn parse_1pux(data: &[u8]) -> Result<Vec<ImportedEntry>> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(data))?;
+    let mut document = Vec::new();
+    archive
+        .by_name("export.data")
+        .map_err(|_| "This is not a 1Password 1PUX export: export.data is missing")?
+        .read_to_end(&mut document)?;
+    parse_1pux_data(&document)
+}
+
+fn parse_1pux_data(document: &[u8]) -> Result<Vec<ImportedEntry>> {
+    let export: JsonValue = serde_json::from_slice(document)?;
+    let mut entries = Vec::new();
+    let vaults = json_array(&export, "accounts")
+        .iter()
+        .flat_map(|account| json_array(account, "vaults"));
+    for vault in vaults {
+        let vault_name = vault["attrs"]["name"].as_str().unwrap_or("1Password");
+        for item in json_array(vault, "items") {
+            if item["state"].as_str() == Some("trashed") {
+                continue;
+            }
+            entries.push(parse_1pux_item(vault_name, item));
+        }
+    }
+    Ok(entries)
+}
+
+fn parse_1pux_item(vault_name: &str, item: &JsonValue) -> ImportedEntry {
+    let overview = &item["overview"];
+    let details = &item["details"];
+    let mut entry = ImportedEntry {
+        path: vec![vault_name.to_string()],
+        title: json_str(overview, "title"),
+        url: json_str(overview, "url"),
+        notes: json_str(details, "notesPlain"),
+        tags: json_array(overview, "tags")
+            .iter()
+            .filter_map(JsonValue::as_str)
+            .map(str::to_string)
+            .collect(),
+        ..Default::default()
+    };
+    // Password items keep their secret outside the login fields.
+    entry.password = SecretString::from(json_str(details, "password"));
+    for field in json_array(details, "loginFields") {
+        let value = json_str(field, "value");
+        match field["designation"].as_str() {
+            Some("username") => entry.username = value,
+            Some("password") => entry.password = SecretString::from(value),
+            _ => entry.add_field(&json_str(field, "name"), &value, field["fieldType"].as_str() == Some("P")),
+        }
+    }
+    for url in json_array(overview, "urls").iter().skip(1) {
+        let count = entry.fields.iter().filter(|field| field.name.starts_with("KP2A_URL_")).count();
+        entry.add_field(&format!("KP2A_URL_{}", count + 1), &json_str(url, "url"), false);
+    }
+    for section in json_array(details, "sections") {
+        for field in json_array(section, "fields") {
+            let name = match json_str(field, "title") {
+                title if title.is_empty() => json_str(field, "id"),
+                title => title,
+            };
+            let Some((kind, value)) = field["value"].as_object().and_then(|value| value.iter().next()) else {
+                continue;
+            };
+            let text = match value {
+                JsonValue::String(text) => text.clone(),
+                JsonValue::Number(number) => number.to_string(),
+                JsonValue::Bool(flag) => flag.to_string(),
+                // Email addresses, addresses and SSH keys are small objects.
+                JsonValue::Object(parts) => parts
+                    .get("email_address")
+                    .or_else(|| parts.get("privateKey"))
+                    .and_then(JsonValue::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| {
+                        parts
+                            .values()
+                            .filter_map(JsonValue::as_str)
+                            .filter(|part| !part.is_empty())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    }),
+                _ => String::new(),
+            };
+            match kind.as_str() {
+                "totp" => entry.set_totp(&text),
+                "concealed" | "creditCardNumber" | "sshKey" => entry.add_field(&name, &text, true),
+                _ => entry.add_field(&name, &text, false),
+            }
+        }
+    }
+    entry
+}
+
+fn json_array<'a>(value: &'a JsonValue, key: &str) -> &'a [JsonValue] {
+    value[key].as_array().map_or(&[], Vec::as_slice)
+}
+
+fn json_str(value: &JsonValue, key: &str) -> String {
+    value[key].as_str().unwrap_or_default().to_string()
+}
+
+/// The `otp` field value for `totp`: URIs are kept, bare Base32 seeds become a TOTP URI with the usual settings.
+pub fn otp_uri(totp: &str, title: &str, username: &str) -> String {
+    let totp = totp.trim();
+    if totp.contains("://") {
+        return totp.to_string();
+    }
+    let secret = totp.replace([' ', '-'], "").to_uppercase();
+    let encode = |text: &str| url::form_urlencoded::byte_serialize(text.as_bytes()).collect::<String>();
+    let issuer = if title.is_empty() { "mypass" } else { title };
+    format!(
+        "otpauth://totp/{}:{}?secret={secret}&period=30&digits=6&issuer={}",
+        encode(issuer),
+        encode(username),
+        encode(issuer)
+    )
+}
+
+/// Adds `entries` under group `parent`, creating a group for every folder, and returns how many were added.
+pub fn import(kpdb: &mut KpDb, parent: Uuid, entries: &[ImportedEntry]) -> Result<usize> {
+    let db = kpdb.db.as_ref().ok_or("No database")?;
+    for imported in entries {
+        let mut group = parent;
+        for folder in &imported.path {
+            let group_node = kpdb.get_node_by_id(group).ok_or("No such group")?;
+            let children = group_get_children(&group_node).unwrap_or_default();
+            let existing = children
+                .iter()
+                .filter(|child| node_is_group(child))
+                .find(|child| child.borrow().get_title() == Some(folder.as_str()))
+                .map(|child| child.borrow().get_uuid());
+            group = match existing {
+                Some(existing) => existing,
+                None => {
+                    let created = db.create_new_group(group, children.len())?;
+                    with_node_mut::<Group, _, _>(&created, |created| created.set_title(Some(folder.as_str())));
+                    created.borrow().get_uuid()
+                }
+            };
+        }
+
+        let group_node = kpdb.get_node_by_id(group).ok_or("No such group")?;
+        let index = group_get_children(&group_node).unwrap_or_default().len();
+        let created = db.create_new_entry(group, index)?;
+        with_node_mut::<Entry, _, _>(&created, |entry| fill_entry(entry, imported));
+    }
+    if !entries.is_empty() {
+        kpdb.mark_data_changed();
+    }
+    Ok(entries.len())
+}
+
+fn fill_entry(entry: &mut Entry, imported: &ImportedEntry) {
+    entry.set_title(Some(&imported.title));
+    entry.set_username(Some(&imported.username));
+    entry.set_password(Some(imported.password.expose()));
+    entry.set_url(Some(&imported.url));
+    entry.set_notes(Some(&imported.notes));
+    entry.get_tags_mut().extend(imported.tags.iter().cloned());
+    let mut fields = imported.fields.clone();
+    if let Some(totp) = &imported.totp {
+        fields.push(CustomField {
+            name: OTP_FIELD.to_string(),
+            value: SecretString::new(otp_uri(totp.expose(), &imported.title, &imported.username)),
+            protected: true,
+        });
+    }
+    set_custom_fields(entry, &fields);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CsvMapping, Format, OTP_FIELD, Target, import, otp_uri, parse, parse_1pux_data, parse_csv, read_csv};
+    use crate::keepass::KpDb;
+    use keepass_ng::{
+        Uuid,
+        db::{Entry, Node, with_node},
+    };
+    use std::path::Path;
+
+    fn child_group(kpdb: &KpDb, parent: Uuid, title: &str) -> Uuid {
+        let parent = kpdb.get_node_by_id(parent).unwrap();
+        let groups = kpdb.get_groups(&parent);
+        let group = groups.iter().find(|group| group.borrow().get_title() == Some(title)).unwrap();
+        group.borrow().get_uuid()
+    }
+
+    fn entry_titles(kpdb: &KpDb, group: Uuid) -> Vec<String> {
+        let group = kpdb.get_node_by_id(group).unwrap();
+        kpdb.get_entries(&group)
+            .iter()
+            .filter_map(|entry| with_node::<Entry, _, _>(entry, |entry| entry.get_title().unwrap_or("").to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn maps_generic_csv_columns() {
+        let data = b"\xEF\xBB\xBFName,Login,Password,Website,Folder,PIN\n\"Mail, work\",jane,s3cret,https://mail.example.com,Work/Mail,1234\n,,,,,\n";
+        let rows = read_csv(data).unwrap();
+        let mut mapping = CsvMapping::guess(&rows[0]);
+        assert_eq!(
+            mapping.columns,
+            [
+                Target::Title,
+                Target::UserName,
+                Target::Password,
+                Target::Url,
+                Target::Group,
+                Target::Field
+            ]
+        );
+        mapping.columns[5] = Target::ProtectedField;
+        let entries = parse_csv(&rows, &mapping);
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.title, "Mail, work");
+        assert_eq!(entry.username, "jane");
+        assert_eq!(entry.password.expose(), "s3cret");
+        assert_eq!(entry.path, ["Work", "Mail"]);
+        assert_eq!(entry.fields[0].name, "PIN");
+        assert!(entry.fields[0].protected);
+    }
+
+    #[test]
+    fn reads_native_csv_exports() {
+        let keepassxc = b"\"Group\",\"Title\",\"Username\",\"Password\",\"URL\",\"Notes\",\"TOTP\",\"Icon\",\"Last Modified\",\"Created\"\n\"Root/Banking\",\"Bank\",\"jane\",\"pw\",\"https://bank.example.com\",\"\",\"otpauth://totp/Bank:jane?secret=JBSWY3DPEHPK3PXP\",\"0\",\"2024-01-01T00:00:00Z\",\"2024-01-01T00:00:00Z\"\n";
+        assert_eq!(Format::guess(Path::new("export.csv"), keepassxc), Format::KeePassXc);
+        let entries = parse(Format::KeePassXc, keepassxc).unwrap();
+        assert_eq!(entries[0].path, ["Banking"]);
+        assert!(entries[0].fields.is_empty());
+        assert!(entries[0].totp.as_ref().unwrap().expose().starts_with("otpauth://"));
+
+        let lastpass = b"url,username,password,totp,extra,name,grouping,fav\nhttp://sn,,,,Door code 1234,Office,Work\\Notes,0\n";
+        assert_eq!(Format::guess(Path::new("lastpass.csv"), lastpass), Format::LastPass);
+        let entries = parse(Format::LastPass, lastpass).unwrap();
+        assert_eq!(entries[0].path, ["Work", "Notes"]);
+        assert_eq!(entries[0].url, "");
+        assert_eq!(entries[0].notes, "Door code 1234");
+
+        assert!(parse(Format::LastPass, keepassxc).is_err());
+    }
+
+    #[test]
+    fn reads_bitwarden_json() {
+        let data = br#"{
+            "encrypted": false,
+            "folders": [{ "id": "f1", "name": "Work/Servers" }],
+            "items": [{
+                "type": 1, "name": "db01", "notes": "Primary", "folderId": "f1",
+                "login": {
+                    "username": "admin", "password": "pw", "totp": "JBSW Y3DP EHPK 3PXP",
+                    "uris": [{ "uri": "https://db01.example.com" }, { "uri": "https://db01.internal" }]
+                },
+                "fields": [{ "name": "Port", "value": "5432", "type": 0 }, { "name": "Root key", "value": "k", "type": 1 }]
+            }, {
+                "type": 3, "name": "Visa", "folderId": null,
+                "card": { "cardholderName": "Jane", "number": "4111111111111111", "code": "123", "expMonth": null }
+            }]
+        }"#;
+        let entries = parse(Format::Bitwarden, data).unwrap();
+        assert_eq!(entries[0].path, ["Work", "Servers"]);
+        assert_eq!(entries[0].url, "https://db01.example.com");
+        let names = entries[0].fields.iter().map(|field| field.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["KP2A_URL_1", "Port", "Root key"]);
+        assert!(entries[0].fields[2].protected);
+        assert!(entries[1].path.is_empty());
+        assert_eq!(entries[1].fields.len(), 3);
+        assert!(entries[1].fields.iter().find(|field| field.name == "number").unwrap().protected);
+
+        assert!(parse(Format::Bitwarden, br#"{ "encrypted": true, "items": [] }"#).is_err());
+    }
+
+    #[test]
+    fn reads_1password_export_data() {
+        let document = br#"{ "accounts": [{ "vaults": [{ "attrs": { "name": "Private" }, "items": [{
+            "state": "active",
+            "overview": { "title": "Forum", "url": "https://forum.example.com", "tags": ["social"] },
+            "details": {
+                "loginFields": [
+                    { "value": "jane", "name": "username", "fieldType": "T", "designation": "username" },
+                    { "value": "pw", "name": "password", "fieldType": "P", "designation": "password" }
+                ],
+                "notesPlain": "",
+                "sections": [{ "title": "", "fields": [
+                    { "title": "one-time password", "id": "TOTP_1", "value": { "totp": "otpauth://totp/Forum?secret=JBSWY3DPEHPK3PXP" } },
+                    { "title": "recovery code", "id": "r", "value": { "concealed": "abc-def" } }
+                ] }]
+            }
+        }, { "state": "trashed", "overview": { "title": "Old" }, "details": {} }] }] }] }"#;
+        let entries = parse_1pux_data(document).unwrap();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.path, ["Private"]);
+        assert_eq!((entry.username.as_str(), entry.password.expose()), ("jane", "pw"));
+        assert_eq!(entry.tags, ["social"]);
+        assert!(entry.totp.is_some());
+        assert_eq!(entry.fields[0].name, "recovery code");
+        assert!(entry.fields[0].protected);
+    }
+
+    #[test]
+    fn imports_into_groups_with_totp() {
+        let mut kpdb = KpDb::new();
+        let root = kpdb.get_root().unwrap().borrow().get_uuid();
+        let rows = read_csv(b"title,username,group,totp\nMail,jane,Work/Mail,jbsw y3dp\nChat,jane,Work,\n").unwrap();
+        let entries = parse_csv(&rows, &CsvMapping::guess(&rows[0]));
+        assert_eq!(import(&mut kpdb, root, &entries).unwrap(), 2);
+        assert!(kpdb.is_data_changed());
+
+        let work = child_group(&kpdb, root, "Work");
+        let mail = child_group(&kpdb, work, "Mail");
+        assert_eq!(entry_titles(&kpdb, work), ["Chat"]);
+        assert_eq!(entry_titles(&kpdb, mail), ["Mail"]);
+        let entry = kpdb.get_entries(&kpdb.get_node_by_id(mail).unwrap())[0].clone();
+        let otp = with_node::<Entry, _, _>(&entry, |entry| entry.get(OTP_FIELD).map(str::to_string)).flatten();
+        assert_eq!(otp.unwrap(), otp_uri("jbsw y3dp", "Mail", "jane"));
+        assert_eq!(
+            otp_uri("jbsw y3dp", "Mail", "jane@example.com"),
+            "otpauth://totp/Mail:jane%40example.com?secret=JBSWY3DP&period=30&digits=6&issuer=Mail"
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use crate::import::{CsvMapping, Format, Target};
+use keepass_ng::Uuid;
+use wxdragon::{
+    BoxSizer, Button, ButtonEvents, CheckBox, Choice, Dialog, FlexGridSizer, Orientation, SizerFlag, StaticText, TextCtrl, WxWidget,
+};
+
+/// Asks for the format of `file_name` and the group to import into; `groups` are the candidates with their paths.
+pub fn show(parent: &dyn WxWidget, file_name: &str, guessed: Format, groups: &[(Uuid, String)], target: Uuid) -> Option<(Format, Uuid)> {
+    let dialog = Dialog::builder(parent, "Import").with_size(520, 220).build();
+    let grid = FlexGridSizer::builder(0, 2).with_vgap(8).with_hgap(8).build();
+    grid.add_growable_col(1, 1);
+
+    let format = Choice::builder(&dialog)
+        .with_choices(Format::ALL.iter().map(|format| format.label().to_string()).collect())
+        .build();
+    let guessed_index = Format::ALL.iter().position(|format| *format == guessed).unwrap_or(0);
+    format.set_selection(guessed_index as u32);
+    let group = Choice::builder(&dialog)
+        .with_choices(groups.iter().map(|(_, path)| path.clone()).collect())
+        .build();
+    group.set_selection(groups.iter().position(|(uuid, _)| *uuid == target).unwrap_or(0) as u32);
+
+    grid.add(&StaticText::builder(&dialog).with_label("File").build(), 0, SizerFlag::All, 4);
+    grid.add(
+        &StaticText::builder(&dialog).with_label(file_name).build(),
+        1,
+        SizerFlag::All | SizerFlag::Expand,
+        4,
+    );
+    grid.add(&StaticText::builder(&dialog).with_label("Format").build(), 0, SizerFlag::All, 4);
+    grid.add(&format, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    grid.add(
+        &StaticText::builder(&dialog).with_label("Import into").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    grid.add(&group, 1, SizerFlag::All | SizerFlag::Expand, 4);
+
+    let root = BoxSizer::builder(Orientation::Vertical).build();
+    root.add_sizer(&grid, 1, SizerFlag::All | SizerFlag::Expand, 12);
+    let actions = BoxSizer::builder(Orientation::Horizontal).build();
+    let spacer = StaticText::builder(&dialog).with_label("").build();
+    let cancel = Button::builder(&dialog).with_id(wxdragon::ID_CANCEL).with_label("Cancel").build();
+    let ok = Button::builder(&dialog).with_label("Next").build();
+    actions.add(&spacer, 1, SizerFlag::Expand, 0);
+    actions.add(&cancel, 0, SizerFlag::All, 4);
+    actions.add(&ok, 0, SizerFlag::All, 4);
+    root.add_sizer(&actions, 0, SizerFlag::All | SizerFlag::Expand, 8);
+    dialog.set_sizer(root, true);
+    dialog.set_escape_id(wxdragon::ID_CANCEL);
+
+    let dialog_for_cancel = dialog;
+    cancel.on_click(move |_| dialog_for_cancel.end_modal(wxdragon::ID_CANCEL));
+    let dialog_for_ok = dialog;
+    ok.on_click(move |_| dialog_for_ok.end_modal(wxdragon::ID_OK));
+
+    dialog.center();
+    let result = dialog.show_modal();
+    let choice = format
+        .get_selection()
+        .and_then(|selection| Format::ALL.get(selection as usize).copied())
+        .zip(
+            group
+                .get_selection()
+                .and_then(|selection| groups.get(selection as usize))
+                .map(|(uuid, _)| *uuid),
+        );
+    dialog.destroy();
+    choice.filter(|_| result == wxdragon::ID_OK)
+}
+
+/// Lets the user assign every column of a generic CSV file; `rows` must contain at least one row.
+pub fn map_columns(parent: &dyn WxWidget, rows: &[Vec<String>]) -> Option<CsvMapping> {
+    let header = rows.first().cloned().unwrap_or_default();
+    let sample = rows.get(1).cloned().unwrap_or_default();
+    let guess = CsvMapping::guess(&header);
+    let height = (220 + 36 * header.len()).min(640) as i32;
+    let dialog = Dialog::builder(parent, "Map CSV columns").with_size(640, height).build();
+
+    let has_header = CheckBox::builder(&dialog)
+        .with_label("The first row holds column names")
+        .with_value(guess.has_header)
+        .build();
+    let separator = TextCtrl::builder(&dialog).with_value("/").build();
+    let grid = FlexGridSizer::builder(0, 3).with_vgap(4).with_hgap(8).build();
+    grid.add_growable_col(1, 1);
+    grid.add(&StaticText::builder(&dialog).with_label("Column").build(), 0, SizerFlag::All, 4);
+    grid.add(&StaticText::builder(&dialog).with_label("Example").build(), 1, SizerFlag::All, 4);
+    grid.add(&StaticText::builder(&dialog).with_label("Import as").build(), 0, SizerFlag::All, 4);
+    let target_labels = Target::ALL.iter().map(|target| target.label().to_string()).collect::<Vec<_>>();
+    let mut targets = Vec::new();
+    for (index, name) in header.iter().enumerate() {
+        let example = sample.get(index).map(String::as_str).unwrap_or("");
+        // Keep long notes and secrets from stretching the dialog.
+        let example = match example.char_indices().nth(40) {
+            Some((end, _)) => format!("{}...", &example[..end]),
+            None => example.to_string(),
+        };
+        let target = Choice::builder(&dialog).with_choices(target_labels.clone()).build();
+        let guessed = guess.columns.get(index).copied().unwrap_or(Target::Ignore);
+        target.set_selection(Target::ALL.iter().position(|target| *target == guessed).unwrap_or(0) as u32);
+        grid.add(&StaticText::builder(&dialog).with_label(name).build(), 0, SizerFlag::All, 4);
+        grid.add(
+            &StaticText::builder(&dialog).with_label(&example).build(),
+            1,
+            SizerFlag::All | SizerFlag::Expand,
+            4,
+        );
+        grid.add(&target, 0, SizerFlag::All, 4);
+        targets.push(target);
+    }
+
+    let root = BoxSizer::builder(Orientation::Vertical).build();
+    root.add(&has_header, 0, SizerFlag::All, 12);
+    let separator_row = BoxSizer::builder(Orientation::Horizontal).build();
+    separator_row.add(
+        &StaticText::builder(&dialog).with_label("Folder separator").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    separator_row.add(&separator, 0, SizerFlag::All, 4);
+    root.add_sizer(&separator_row, 0, SizerFlag::Left | SizerFlag::Right, 8);
+    root.add_sizer(&grid, 1, SizerFlag::All | SizerFlag::Expand, 12);
+    let actions = BoxSizer::builder(Orientation::Horizontal).build();
+    let spacer = StaticText::builder(&dialog).with_label("").build();
+    let cancel = Button::builder(&dialog).with_id(wxdragon::ID_CANCEL).with_label("Cancel").build();
+    let ok = Button::builder(&dialog).with_label("Import").build();
+    actions.add(&spacer, 1, SizerFlag::Expand, 0);
+    actions.add(&cancel, 0, SizerFlag::All, 4);
+    actions.add(&ok, 0, SizerFlag::All, 4);
+    root.add_sizer(&actions, 0, SizerFlag::All | SizerFlag::Expand, 8);
+    dialog.set_sizer(root, true);
+    dialog.set_escape_id(wxdragon::ID_CANCEL);
+
+    let dialog_for_cancel = dialog;
+    cancel.on_click(move |_| dialog_for_cancel.end_modal(wxdragon::ID_CANCEL));
+    let dialog_for_ok = dialog;
+    ok.on_click(move |_| dialog_for_ok.end_modal(wxdragon::ID_OK));
+
+    dialog.center();
+    let result = dialog.show_modal();
+    let mapping = CsvMapping {
+        columns: targets
+            .iter()
+            .map(|target| {
+                target
+                    .get_selection()
+                    .and_then(|selection| Target::ALL.get(selection as usize).copied())
+                    .unwrap_or(Target::Ignore)
+            })
+            .collect(),
+        has_header: has_header.get_value(),
+        group_separator: separator.get_value().trim().chars().next().unwrap_or('/'),
+        strip_root_group: false,
+    };
+    dialog.destroy();
+    (result == wxdragon::ID_OK).then_some(mapping)
+}
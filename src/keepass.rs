@@ -1,21 +1,79 @@
-use crate::error::Result;
+use crate::challenge_response::ChallengeResponseProvider;
+use crate::error::{Error, Result};
+use crate::secret::SecretString;
 use chrono::Local;
 use keepass_ng::{
     DatabaseConfig, DatabaseKey, DatabaseVersion, Uuid,
-    db::{self, Database, Group, NodePtr, group_get_children, node_is_group, search_node_by_uuid},
+    db::{
+        self, Database, Entry, Group, NodeIterator, NodePtr, group_add_child, group_get_children, group_remove_node_by_uuid, node_is_group,
+        search_node_by_uuid, with_node, with_node_mut,
+    },
 };
+use sha2::{Digest, Sha256};
 use std::{
+    fmt,
     fs::{self, File},
-    path::PathBuf,
+    io::Write,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::SystemTime,
 };
 
-#[derive(Debug)]
+/// An open database and the master key it is saved with. Deliberately not `Debug`, so it cannot end up in a log line.
 pub struct KpDb {
     pub db: Option<Database>,
     pub db_path: Option<String>,
-    pub password: Option<String>,
+    pub password: Option<SecretString>,
     pub key_file: Option<String>,
+    /// A hardware or software key answering the challenge that is part of the master key.
+    pub challenge_response: Option<Rc<dyn ChallengeResponseProvider>>,
     data_changed: bool,
+    /// The file at `db_path` as we last read or wrote it.
+    disk_state: Option<DiskState>,
+    /// The master key that file is encrypted with, which differs from ours while a new one is not saved yet.
+    disk_key: Option<DiskKey>,
+}
+
+/// The parts of a master key, kept to read the file again.
+#[derive(Clone)]
+struct DiskKey {
+    password: Option<SecretString>,
+    key_file: Option<String>,
+    challenge_response: Option<Rc<dyn ChallengeResponseProvider>>,
+}
+
+/// Identifies one version of a database file, so writes by other programs can be told apart from ours.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct DiskState {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: [u8; 32],
+}
+
+impl DiskState {
+    fn new(path: &Path, data: &[u8]) -> Self {
+        Self {
+            modified: modified_time(path),
+            len: data.len() as u64,
+            hash: Sha256::digest(data).into(),
+        }
+    }
+}
+
+/// A database with unsaved changes, encrypted with its master key so the key can be dropped while it is locked.
+#[derive(Clone)]
+pub struct SealedDatabase {
+    data: Vec<u8>,
+    version: DatabaseVersion,
+    disk_state: Option<DiskState>,
+}
+
+impl fmt::Debug for SealedDatabase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SealedDatabase")
+            .field("len", &self.data.len())
+            .finish_non_exhaustive()
+    }
 }
 
 impl Default for KpDb {
@@ -25,7 +83,10 @@ impl Default for KpDb {
             db_path: None,
             password: None,
             key_file: None,
+            challenge_response: None,
             data_changed: false,
+            disk_state: None,
+            disk_key: None,
         }
     }
 }
@@ -35,18 +96,76 @@ impl KpDb {
         Self::default()
     }
 
-    pub fn open(db_path: &str, password: Option<&str>, key_file: Option<&str>) -> Result<Self> {
+    pub fn open(
+        db_path: &str,
+        password: Option<&str>,
+        key_file: Option<&str>,
+        challenge_response: Option<Rc<dyn ChallengeResponseProvider>>,
+    ) -> Result<Self> {
         let mut kpdb = Self::new();
         kpdb.db_path = Some(db_path.to_string());
-        kpdb.password = password.map(|s| s.to_string());
+        kpdb.password = password.map(SecretString::from);
         kpdb.key_file = key_file.map(|s| s.to_string());
+        kpdb.challenge_response = challenge_response;
 
         let _db_path = std::path::Path::new(db_path);
 
         let db_key = kpdb.build_db_key()?;
 
-        let db = Database::open(&mut File::open(_db_path)?, db_key)?;
+        let data = fs::read(_db_path)?;
+        let db = Database::open(&mut data.as_slice(), db_key)?;
+        kpdb.db = Some(db);
+        kpdb.disk_state = Some(DiskState::new(_db_path, &data));
+        kpdb.disk_key = Some(kpdb.current_key());
+        Ok(kpdb)
+    }
+
+    /// Decrypts a database sealed by `seal` with the credentials entered to unlock it; its changes stay unsaved.
+    pub fn unseal(
+        sealed: &SealedDatabase,
+        db_path: &str,
+        password: Option<&str>,
+        key_file: Option<&str>,
+        challenge_response: Option<Rc<dyn ChallengeResponseProvider>>,
+    ) -> Result<Self> {
+        let mut kpdb = Self::new();
+        kpdb.db_path = Some(db_path.to_string());
+        kpdb.password = password.map(SecretString::from);
+        kpdb.key_file = key_file.map(|s| s.to_string());
+        kpdb.challenge_response = challenge_response;
+
+        let mut db = Database::open(&mut sealed.data.as_slice(), kpdb.build_db_key()?)?;
+        db.config.version = sealed.version;
         kpdb.db = Some(db);
+        kpdb.data_changed = true;
+        kpdb.disk_state = sealed.disk_state.clone();
+        kpdb.disk_key = Some(kpdb.current_key());
+        Ok(kpdb)
+    }
+
+    pub fn create(db_path: &str, password: Option<&str>, key_file: Option<&str>, config: DatabaseConfig) -> Result<Self> {
+        if password.is_none_or(str::is_empty) && key_file.is_none() {
+            return Err("A password or a key file is required".into());
+        }
+        let mut kpdb = Self::new();
+        kpdb.db = Some(Database::new(config));
+        kpdb.db_path = Some(db_path.to_string());
+        kpdb.password = password.map(SecretString::from);
+        kpdb.key_file = key_file.map(|s| s.to_string());
+
+        let name = std::path::Path::new(db_path)
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("Root")
+            .to_string();
+        if let Some(root) = kpdb.get_root() {
+            with_node_mut::<Group, _, _>(&root, |group| group.set_title(Some(&name)));
+        }
+        if let Some(db) = kpdb.db.as_mut() {
+            db.meta.database_name = Some(name);
+        }
+        kpdb.mark_data_changed();
+        kpdb.save(None)?;
         Ok(kpdb)
     }
 
@@ -64,16 +183,115 @@ impl KpDb {
         let db_path = std::path::Path::new(db_path);
         let mut temporary_path = PathBuf::from(db_path);
         temporary_path.set_extension("kdbx.tmp");
-        let mut file = File::create(&temporary_path)?;
         db.config.version = DatabaseVersion::KDB4(1);
-        db.save(&mut file, db_key).map_err(|error| error.to_string())?;
+        let mut data = Vec::new();
+        db.save(&mut data, db_key).map_err(|error| error.to_string())?;
+        let mut file = File::create(&temporary_path)?;
+        file.write_all(&data)?;
         file.sync_all()?;
         drop(file);
         fs::rename(temporary_path, db_path)?;
+        self.disk_state = Some(DiskState::new(db_path, &data));
+        self.disk_key = Some(self.current_key());
         self.data_changed = false;
         Ok(true)
     }
 
+    /// Whether another program wrote `db_path` since we opened or saved it.
+    /// The content hash is only computed when the modification time or the size differ.
+    pub fn changed_on_disk(&self) -> bool {
+        let (Some(path), Some(state)) = (self.db_path.as_deref(), self.disk_state.as_ref()) else {
+            return false;
+        };
+        let path = Path::new(path);
+        // A missing file has nothing left to clobber.
+        let Ok(metadata) = fs::metadata(path) else {
+            return false;
+        };
+        if metadata.modified().ok() == state.modified && metadata.len() == state.len {
+            return false;
+        }
+        fs::read(path).is_ok_and(|data| Sha256::digest(&data)[..] != state.hash[..])
+    }
+
+    /// Modification time of the file at `db_path` right now.
+    pub fn disk_modified(&self) -> Option<SystemTime> {
+        self.db_path.as_deref().and_then(|path| modified_time(Path::new(path)))
+    }
+
+    /// Treats the file `other` was read from as the version this database is based on, after merging it.
+    pub fn adopt_disk_state(&mut self, other: &KpDb) {
+        self.disk_state = other.disk_state.clone();
+        self.disk_key = other.disk_key.clone();
+    }
+
+    /// Accepts the current file at `db_path` as ours, so the next `save` overwrites it without asking.
+    pub fn ignore_disk_changes(&mut self) {
+        if let Some(path) = self.db_path.as_deref()
+            && let Ok(data) = fs::read(path)
+        {
+            self.disk_state = Some(DiskState::new(Path::new(path), &data));
+        }
+    }
+
+    /// Opens the file at `db_path` again with the master key it was last read or written with.
+    pub fn reopen(&self) -> Result<Self> {
+        let path = self.db_path.as_deref().ok_or("Database path is not set")?;
+        let key = self.disk_key.clone().unwrap_or_else(|| self.current_key());
+        Self::open(
+            path,
+            key.password.as_ref().map(SecretString::expose),
+            key.key_file.as_deref(),
+            key.challenge_response,
+        )
+    }
+
+    fn current_key(&self) -> DiskKey {
+        DiskKey {
+            password: self.password.clone(),
+            key_file: self.key_file.clone(),
+            challenge_response: self.challenge_response.clone(),
+        }
+    }
+
+    /// An independent copy of the in-memory database, including unsaved changes.
+    pub fn deep_copy(&self) -> Result<Self> {
+        let db = self.db.as_ref().ok_or("No database")?;
+        // One key for both directions, so the key file is read and the challenge-response provider asked only once.
+        let db_key = self.build_db_key()?;
+        let mut buffer = Vec::new();
+        db.save(&mut buffer, db_key.clone()).map_err(|error| error.to_string())?;
+        let copy = Database::open(&mut buffer.as_slice(), db_key)?;
+        Ok(Self {
+            db: Some(copy),
+            db_path: self.db_path.clone(),
+            password: self.password.clone(),
+            key_file: self.key_file.clone(),
+            challenge_response: self.challenge_response.clone(),
+            data_changed: self.data_changed,
+            disk_state: self.disk_state.clone(),
+            disk_key: self.disk_key.clone(),
+        })
+    }
+
+    /// Encrypts the in-memory database, unsaved changes included, without writing it to `db_path`.
+    pub fn seal(&mut self) -> Result<SealedDatabase> {
+        let db_key = self.build_db_key()?;
+        let db = self.db.as_mut().ok_or("No database")?;
+        // Only KDBX 4 can be written; the version is put back so unlocking still offers the upgrade on save.
+        let version = db.config.version;
+        db.config.version = DatabaseVersion::KDB4(1);
+        let mut data = Vec::new();
+        let saved = db.save(&mut data, db_key).map_err(|error| error.to_string());
+        db.config.version = version;
+        saved?;
+        Ok(SealedDatabase {
+            data,
+            version,
+            disk_state: self.disk_state.clone(),
+        })
+    }
+
     pub fn is_data_changed(&self) -> bool {
         self.data_changed
     }
@@ -82,18 +300,58 @@ impl KpDb {
         self.data_changed = true;
     }
 
-    fn build_db_key(&self) -> Result<DatabaseKey> {
-        let mut key_file = self.key_file.as_ref().and_then(|f| File::open(f).ok());
-        let key_file = key_file.as_mut().map(|kf| kf as &mut dyn std::io::Read);
+    /// Checks the supplied credentials against the ones the database was opened with, by the key they derive, so
+    /// a copy of the key file elsewhere matches and a different file at the same path does not. A challenge-response
+    /// key can only prove itself by decrypting the file, which therefore must be saved with the current key.
+    pub fn verify_master_key(
+        &self,
+        password: Option<&str>,
+        key_file: Option<&str>,
+        challenge_response: Option<Rc<dyn ChallengeResponseProvider>>,
+    ) -> bool {
+        let stored = composite_key(self.password.as_ref().map(SecretString::expose), self.key_file.as_deref());
+        let matches = match (composite_key(password, key_file), stored) {
+            (Ok(supplied), Ok(stored)) => supplied == stored,
+            _ => false,
+        };
+        if !matches || challenge_response.is_some() != self.challenge_response.is_some() {
+            return false;
+        }
+        match challenge_response {
+            Some(provider) => self
+                .db_path
+                .as_deref()
+                .is_some_and(|path| Self::open(path, password, key_file, Some(provider)).is_ok()),
+            None => true,
+        }
+    }
 
-        let mut db_key = DatabaseKey::new();
-        if let Some(ref password) = self.password
-            && !password.is_empty()
-        {
-            db_key = db_key.with_password(password);
+    /// Replaces the master key; the new key is used from the next `save` on.
+    pub fn set_master_key(&mut self, password: Option<&str>, key_file: Option<&str>) -> Result<()> {
+        let password = password.filter(|password| !password.is_empty());
+        if password.is_none() && key_file.is_none() {
+            return Err("A password or a key file is required".into());
         }
         if let Some(key_file) = key_file {
-            db_key = db_key.with_keyfile(key_file)?;
+            File::open(key_file)?;
+        }
+        self.password = password.map(SecretString::from);
+        self.key_file = key_file.map(|s| s.to_string());
+        self.mark_data_changed();
+        Ok(())
+    }
+
+    /// Undoes `set_master_key` after the new key could not be saved, including the unsaved change it recorded.
+    pub fn restore_master_key(&mut self, password: Option<SecretString>, key_file: Option<String>, data_changed: bool) {
+        self.password = password;
+        self.key_file = key_file;
+        self.data_changed = data_changed;
+    }
+
+    fn build_db_key(&self) -> Result<DatabaseKey> {
+        let mut db_key = composite_key(self.password.as_ref().map(SecretString::expose), self.key_file.as_deref())?;
+        if let Some(provider) = self.challenge_response.as_ref() {
+            db_key = db_key.with_challenge_response_key(provider.key()?);
         }
 
         Ok(db_key)
@@ -155,6 +413,235 @@ impl KpDb {
         self.get_root().and_then(|root| search_node_by_uuid(&root, id))
     }
 
+    /// Titles of the groups between the root (exclusive) and `node` (exclusive).
+    pub fn get_group_path(&self, node: &db::NodePtr) -> Vec<String> {
+        let root_uuid = self.get_root().map(|root| root.borrow().get_uuid());
+        let mut path = Vec::new();
+        let mut parent = node.borrow().get_parent();
+        while let Some(uuid) = parent.filter(|uuid| Some(*uuid) != root_uuid) {
+            let Some(group) = self.get_node_by_id(uuid) else {
+                break;
+            };
+            path.push(group.borrow().get_title().unwrap_or("").to_string());
+            parent = group.borrow().get_parent();
+        }
+        path.reverse();
+        path
+    }
+
+    /// Moves `uuid` to the end of the group `new_parent` and records the location change.
+    pub fn move_node(&mut self, uuid: Uuid, new_parent: Uuid) -> Result<()> {
+        self.place_node(uuid, new_parent, None)
+    }
+
+    /// Moves `uuid` into the group `new_parent` at `index` among its children, or last, and records the location change.
+    pub fn place_node(&mut self, uuid: Uuid, new_parent: Uuid, index: Option<usize>) -> Result<()> {
+        let node = self.get_node_by_id(uuid).ok_or("No such node")?;
+        let old_parent = node.borrow().get_parent().ok_or("The database root cannot be moved")?;
+        let target = self.get_node_by_id(new_parent).ok_or("No such group")?;
+        if !node_is_group(&target) {
+            return Err("Nodes can only be moved into groups".into());
+        }
+        if self.is_inside(new_parent, uuid) {
+            return Err("A group cannot be moved into itself".into());
+        }
+        let old_parent = self.get_node_by_id(old_parent).ok_or("No such group")?;
+        let node = group_remove_node_by_uuid(&old_parent, uuid)?;
+        let children = group_get_children(&target).map(|children| children.len()).unwrap_or(0);
+        group_add_child(&target, node.clone(), index.unwrap_or(children).min(children))?;
+        node.borrow_mut()
+            .get_times_mut()
+            .set_location_changed(Some(Local::now().naive_local()));
+        self.mark_data_changed();
+        Ok(())
+    }
+
+    /// Moves `uuid` for a drop onto `target`: into `target` when it is a group and `before` is not set or it is the
+    /// root, otherwise just before it. Returns the new parent.
+    pub fn drop_node(&mut self, uuid: Uuid, target: Uuid, before: bool) -> Result<Uuid> {
+        if uuid == target {
+            return Err("A node cannot be dropped onto itself".into());
+        }
+        let target_node = self.get_node_by_id(target).ok_or("No such node")?;
+        if node_is_group(&target_node) && (!before || target_node.borrow().get_parent().is_none()) {
+            self.move_node(uuid, target)?;
+            return Ok(target);
+        }
+        let parent = target_node.borrow().get_parent().ok_or("No such group")?;
+        let parent_node = self.get_node_by_id(parent).ok_or("No such group")?;
+        // `place_node` counts positions after `uuid` has left its old place.
+        let index = group_get_children(&parent_node)
+            .unwrap_or_default()
+            .iter()
+            .map(|child| child.borrow().get_uuid())
+            .filter(|child| *child != uuid)
+            .position(|child| child == target);
+        self.place_node(uuid, parent, index)?;
+        Ok(parent)
+    }
+
+    /// Whether `uuid` is `ancestor` or lies somewhere below it.
+    pub fn is_inside(&self, uuid: Uuid, ancestor: Uuid) -> bool {
+        let mut current = Some(uuid);
+        while let Some(node_uuid) = current {
+            if node_uuid == ancestor {
+                return true;
+            }
+            current = self.get_node_by_id(node_uuid).and_then(|node| node.borrow().get_parent());
+        }
+        false
+    }
+
+    pub fn recycle_bin(&self) -> Option<NodePtr> {
+        self.db.as_ref().and_then(|db| db.get_recycle_bin())
+    }
+
+    /// Whether `uuid` was deleted into the recycle bin; the bin itself does not count.
+    pub fn is_recycled(&self, uuid: Uuid) -> bool {
+        self.recycle_bin()
+            .map(|bin| bin.borrow().get_uuid())
+            .is_some_and(|bin| bin != uuid && self.is_inside(uuid, bin))
+    }
+
+    /// Every entry of the database except those in the recycle bin, group by group.
+    pub fn live_entries(&self) -> Vec<NodePtr> {
+        fn collect(kpdb: &KpDb, group: &NodePtr, recycle_bin: Option<Uuid>, entries: &mut Vec<NodePtr>) {
+            entries.extend(kpdb.get_entries(group));
+            for child in kpdb.get_groups(group) {
+                if Some(child.borrow().get_uuid()) != recycle_bin {
+                    collect(kpdb, &child, recycle_bin, entries);
+                }
+            }
+        }
+        let mut entries = Vec::new();
+        if let Some(root) = self.get_root() {
+            let recycle_bin = self.recycle_bin().map(|bin| bin.borrow().get_uuid());
+            collect(self, &root, recycle_bin, &mut entries);
+        }
+        entries
+    }
+
+    /// Moves a recycled node back to the group it was deleted from, or to the root when that group is gone.
+    pub fn restore_node(&mut self, uuid: Uuid) -> Result<Uuid> {
+        if !self.is_recycled(uuid) {
+            return Err("The node is not in the recycle bin".into());
+        }
+        let node = self.get_node_by_id(uuid).ok_or("No such node")?;
+        let root = self.get_root().ok_or("No database")?.borrow().get_uuid();
+        let target = node
+            .borrow()
+            .get_previous_parent_group()
+            .filter(|parent| self.get_node_by_id(*parent).is_some() && !self.is_recycled(*parent))
+            .unwrap_or(root);
+        self.move_node(uuid, target)?;
+        Ok(target)
+    }
+
+    /// Deletes everything in the recycle bin for good and returns how many nodes were removed.
+    pub fn empty_recycle_bin(&mut self) -> Result<usize> {
+        let Some(bin) = self.recycle_bin() else {
+            return Ok(0);
+        };
+        let children = group_get_children(&bin)
+            .unwrap_or_default()
+            .iter()
+            .map(|node| node.borrow().get_uuid())
+            .collect::<Vec<_>>();
+        let db = self.db.as_mut().ok_or("No database")?;
+        for uuid in &children {
+            // Nodes that are already in the bin are removed for good and recorded as deleted objects.
+            db.remove_node_by_uuid(*uuid)?;
+        }
+        if !children.is_empty() {
+            self.mark_data_changed();
+        }
+        Ok(children.len())
+    }
+
+    /// Whether deleting moves nodes to the bin, and the group used as the bin when one is set.
+    pub fn recycle_bin_settings(&self) -> (bool, Option<Uuid>) {
+        self.db.as_ref().map_or((false, None), |db| {
+            (db.meta.recyclebin_enabled.unwrap_or(true), db.meta.recyclebin_uuid)
+        })
+    }
+
+    /// Turns the recycle bin on or off; `group` picks an existing group as the bin, `None` lets one be created on the next delete.
+    pub fn set_recycle_bin(&mut self, enabled: bool, group: Option<Uuid>) -> Result<()> {
+        if let Some(group) = group
+            && !self.get_node_by_id(group).is_some_and(|node| node_is_group(&node))
+        {
+            return Err("The recycle bin must be a group".into());
+        }
+        let db = self.db.as_mut().ok_or("No database")?;
+        db.meta.recyclebin_enabled = Some(enabled);
+        db.meta.recyclebin_uuid = group;
+        db.meta.recyclebin_changed = Some(Local::now().naive_local());
+        self.mark_data_changed();
+        Ok(())
+    }
+
+    /// Every group except the root, with its path below the root joined by " / ".
+    pub fn group_paths(&self) -> Vec<(Uuid, String)> {
+        let Some(root) = self.get_root() else {
+            return Vec::new();
+        };
+        NodeIterator::new(&root)
+            .filter(|node| node_is_group(node) && node.borrow().get_parent().is_some())
+            .map(|node| {
+                let mut path = self.get_group_path(&node);
+                path.push(node.borrow().get_title().unwrap_or("").to_string());
+                (node.borrow().get_uuid(), path.join(" / "))
+            })
+            .collect()
+    }
+
+    /// Stores `data` in the binary pool as attachment `name` of `entry`, replacing an attachment of the same name.
+    pub fn attach(&mut self, entry: Uuid, name: &str, data: Vec<u8>) -> Result<()> {
+        self.update_entry(entry, |entry| {
+            entry.attachments.insert(name.to_string(), db::Attachment::new(data));
+            Ok(())
+        })
+    }
+
+    pub fn attachment_data(&self, entry: Uuid, name: &str) -> Option<Vec<u8>> {
+        let node = self.get_node_by_id(entry)?;
+        with_node::<Entry, _, _>(&node, |entry| {
+            entry.attachments.get(name).map(|attachment| attachment.data.get().to_vec())
+        })
+        .flatten()
+    }
+
+    pub fn rename_attachment(&mut self, entry: Uuid, name: &str, new_name: &str) -> Result<()> {
+        self.update_entry(entry, |entry| {
+            if entry.attachments.contains_key(new_name) {
+                return Err(format!("An attachment named {new_name} already exists").into());
+            }
+            let attachment = entry.attachments.remove(name).ok_or("No such attachment")?;
+            entry.attachments.insert(new_name.to_string(), attachment);
+            Ok(())
+        })
+    }
+
+    pub fn remove_attachment(&mut self, entry: Uuid, name: &str) -> Result<()> {
+        self.update_entry(entry, |entry| {
+            entry.attachments.remove(name).ok_or("No such attachment")?;
+            Ok(())
+        })
+    }
+
+    /// Applies `change` to an entry, keeps the result in its history and marks the database changed.
+    fn update_entry(&mut self, uuid: Uuid, change: impl FnOnce(&mut Entry) -> Result<()>) -> Result<()> {
+        let node = self.get_node_by_id(uuid).ok_or("No such entry")?;
+        with_node_mut::<Entry, _, _>(&node, |entry| {
+            change(entry)?;
+            entry.update_history();
+            Ok::<(), Error>(())
+        })
+        .ok_or("Not an entry")??;
+        self.mark_data_changed();
+        Ok(())
+    }
+
     pub fn add_custom_icon(&mut self, data: Vec<u8>, source_url: String) -> Result<Uuid> {
         let db = self.db.as_mut().ok_or("No database")?;
         if let Some((uuid, _)) = db.meta.custom_icons().find(|(_, icon)| icon.name() == Some(&source_url)) {
@@ -192,10 +679,25 @@ impl KpDb {
     }
 }
 
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// The part of the master key made of the password and the contents of the key file.
+fn composite_key(password: Option<&str>, key_file: Option<&str>) -> Result<DatabaseKey> {
+    let mut db_key = DatabaseKey::new();
+    if let Some(password) = password.filter(|password| !password.is_empty()) {
+        db_key = db_key.with_password(password);
+    }
+    if let Some(key_file) = key_file {
+        db_key = db_key.with_keyfile(&mut File::open(key_file)?)?;
+    }
+    Ok(db_key)
+}
+
 #[test]
 fn test_demo_db() {
-    use crate::error::Error;
-    use keepass_ng::db::{Entry, Node, NodeIterator};
+    use keepass_ng::db::{Node, NodeIterator};
     let block = || {
         dotenvy::dotenv().ok();
 
@@ -206,7 +708,7 @@ fn test_demo_db() {
         let password = std::env::var("PASSWORD").ok();
         let key_file = std::env::var("KEY_FILE").ok();
 
-        let kpdb = KpDb::open(&db_path, password.as_deref(), key_file.as_deref())?;
+        let kpdb = KpDb::open(&db_path, password.as_deref(), key_file.as_deref(), None)?;
         // Iterate over all `Group`s and `Entry`s
         for node in NodeIterator::new(&kpdb.get_root().unwrap()) {
             if node_is_group(&node) {
@@ -222,3 +724,204 @@ fn test_demo_db() {
     };
     assert!(block().is_ok());
 }
+
+#[test]
+fn test_create_and_reopen_db() {
+    let path = std::env::temp_dir().join(format!("mypass-create-{}.kdbx", Uuid::new_v4()));
+    let path = path.to_str().unwrap();
+    let created = KpDb::create(path, Some("demopass"), None, DatabaseConfig::default()).unwrap();
+    assert!(!created.is_data_changed());
+    let reopened = KpDb::open(path, Some("demopass"), None, None).unwrap();
+    assert_eq!(reopened.db.as_ref().unwrap().config.version, DatabaseVersion::KDB4(1));
+    assert!(KpDb::open(path, Some("wrong"), None, None).is_err());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_change_master_key() {
+    let path = std::env::temp_dir().join(format!("mypass-rekey-{}.kdbx", Uuid::new_v4()));
+    let path = path.to_str().unwrap();
+    let mut kpdb = KpDb::create(path, Some("old"), None, DatabaseConfig::default()).unwrap();
+    assert!(kpdb.verify_master_key(Some("old"), None, None));
+    assert!(!kpdb.verify_master_key(Some("new"), None, None));
+    assert!(kpdb.set_master_key(None, None).is_err());
+    kpdb.set_master_key(Some("new"), None).unwrap();
+    kpdb.restore_master_key(Some(SecretString::from("old")), None, false);
+    assert!(!kpdb.is_data_changed());
+    assert!(kpdb.verify_master_key(Some("old"), None, None));
+    kpdb.set_master_key(Some("new"), None).unwrap();
+    // Until it is saved, the file still has to be read with the old key.
+    assert!(kpdb.reopen().is_ok());
+    assert!(kpdb.save(None).unwrap());
+    assert!(kpdb.reopen().is_ok());
+    assert!(KpDb::open(path, Some("old"), None, None).is_err());
+    assert!(KpDb::open(path, Some("new"), None, None).is_ok());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_seal_unsaved_changes() {
+    let path = std::env::temp_dir().join(format!("mypass-seal-{}.kdbx", Uuid::new_v4()));
+    let path = path.to_str().unwrap();
+    let mut kpdb = KpDb::create(path, Some("demopass"), None, DatabaseConfig::default()).unwrap();
+    let root = kpdb.get_root().unwrap().borrow().get_uuid();
+    let group = kpdb.create_new_group(root).unwrap().borrow().get_uuid();
+    let sealed = kpdb.seal().unwrap();
+    drop(kpdb);
+    assert!(KpDb::unseal(&sealed, path, Some("wrong"), None, None).is_err());
+    let mut unsealed = KpDb::unseal(&sealed, path, Some("demopass"), None, None).unwrap();
+    assert!(unsealed.is_data_changed());
+    assert!(unsealed.get_node_by_id(group).is_some());
+    // The file on disk was not touched, so saving does not report a conflict.
+    assert!(unsealed.save(None).unwrap());
+    assert!(KpDb::open(path, Some("demopass"), None, None).is_ok());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_verify_master_key_by_key_file_contents() {
+    let dir = std::env::temp_dir().join(format!("mypass-keyfile-{}", Uuid::new_v4()));
+    fs::create_dir(&dir).unwrap();
+    let key_file = dir.join("team.key");
+    let copy = dir.join("copy.key");
+    fs::write(&key_file, [7u8; 64]).unwrap();
+    fs::copy(&key_file, &copy).unwrap();
+    let (key_file, copy) = (key_file.to_str().unwrap(), copy.to_str().unwrap());
+    let path = dir.join("db.kdbx");
+    let kpdb = KpDb::create(path.to_str().unwrap(), Some("pass"), Some(key_file), DatabaseConfig::default()).unwrap();
+    assert!(kpdb.verify_master_key(Some("pass"), Some(key_file), None));
+    assert!(kpdb.verify_master_key(Some("pass"), Some(copy), None));
+    assert!(!kpdb.verify_master_key(Some("pass"), None, None));
+    fs::write(copy, [8u8; 64]).unwrap();
+    assert!(!kpdb.verify_master_key(Some("pass"), Some(copy), None));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_challenge_response_key() {
+    use crate::challenge_response::SoftwareHmacSha1;
+    let path = std::env::temp_dir().join(format!("mypass-challenge-{}.kdbx", Uuid::new_v4()));
+    let path = path.to_str().unwrap();
+    let mut kpdb = KpDb::create(path, Some("demopass"), None, DatabaseConfig::default()).unwrap();
+    kpdb.challenge_response = Some(Rc::new(SoftwareHmacSha1::new(b"yubikey secret")));
+    kpdb.mark_data_changed();
+    assert!(kpdb.save(None).unwrap());
+
+    let provider = || -> Option<Rc<dyn ChallengeResponseProvider>> { Some(Rc::new(SoftwareHmacSha1::new(b"yubikey secret"))) };
+    let reopened = KpDb::open(path, Some("demopass"), None, provider()).unwrap();
+    assert!(reopened.reopen().is_ok());
+    assert!(reopened.verify_master_key(Some("demopass"), None, provider()));
+    assert!(!reopened.verify_master_key(Some("demopass"), None, None));
+    assert!(KpDb::open(path, Some("demopass"), None, None).is_err());
+    let other_secret: Option<Rc<dyn ChallengeResponseProvider>> = Some(Rc::new(SoftwareHmacSha1::new(b"another secret")));
+    assert!(!reopened.verify_master_key(Some("demopass"), None, other_secret.clone()));
+    assert!(KpDb::open(path, Some("demopass"), None, other_secret).is_err());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_detect_changes_on_disk() {
+    let path = std::env::temp_dir().join(format!("mypass-disk-{}.kdbx", Uuid::new_v4()));
+    let path = path.to_str().unwrap();
+    let mut ours = KpDb::create(path, Some("demopass"), None, DatabaseConfig::default()).unwrap();
+    assert!(!ours.changed_on_disk());
+
+    let mut theirs = KpDb::open(path, Some("demopass"), None, None).unwrap();
+    let root = theirs.get_root().unwrap().borrow().get_uuid();
+    theirs.create_new_entry(root).unwrap();
+    assert!(theirs.save(None).unwrap());
+    assert!(ours.changed_on_disk());
+    assert!(!theirs.changed_on_disk());
+
+    let on_disk = ours.reopen().unwrap();
+    ours.adopt_disk_state(&on_disk);
+    assert!(!ours.changed_on_disk());
+
+    theirs.mark_data_changed();
+    assert!(theirs.save(None).unwrap());
+    ours.ignore_disk_changes();
+    assert!(!ours.changed_on_disk());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_attachments() {
+    let path = std::env::temp_dir().join(format!("mypass-attach-{}.kdbx", Uuid::new_v4()));
+    let path = path.to_str().unwrap();
+    let mut kpdb = KpDb::create(path, Some("demopass"), None, DatabaseConfig::default()).unwrap();
+    let root = kpdb.get_root().unwrap().borrow().get_uuid();
+    let entry = kpdb.create_new_entry(root).unwrap().borrow().get_uuid();
+    kpdb.attach(entry, "id_ed25519", b"private key".to_vec()).unwrap();
+    kpdb.attach(entry, "server.pem", b"certificate".to_vec()).unwrap();
+    assert!(kpdb.rename_attachment(entry, "server.pem", "id_ed25519").is_err());
+    kpdb.rename_attachment(entry, "server.pem", "server.crt").unwrap();
+    kpdb.remove_attachment(entry, "id_ed25519").unwrap();
+    assert!(kpdb.remove_attachment(entry, "id_ed25519").is_err());
+    assert!(kpdb.save(None).unwrap());
+
+    let reopened = KpDb::open(path, Some("demopass"), None, None).unwrap();
+    assert_eq!(reopened.attachment_data(entry, "server.crt").as_deref(), Some(&b"certificate"[..]));
+    assert!(reopened.attachment_data(entry, "id_ed25519").is_none());
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_drop_node() {
+    let mut kpdb = KpDb::new();
+    let root = kpdb.get_root().unwrap().borrow().get_uuid();
+    let group = kpdb.create_new_group(root).unwrap().borrow().get_uuid();
+    let first = kpdb.create_new_entry(root).unwrap().borrow().get_uuid();
+    let second = kpdb.create_new_entry(root).unwrap().borrow().get_uuid();
+    let children = |kpdb: &KpDb, parent: Uuid| {
+        group_get_children(&kpdb.get_node_by_id(parent).unwrap())
+            .unwrap_or_default()
+            .iter()
+            .map(|child| child.borrow().get_uuid())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(kpdb.drop_node(second, first, false).unwrap(), root);
+    assert_eq!(children(&kpdb, root), [group, second, first]);
+    assert_eq!(kpdb.drop_node(group, first, false).unwrap(), root);
+    assert_eq!(children(&kpdb, root), [second, group, first]);
+
+    let sibling = kpdb.create_new_group(root).unwrap().borrow().get_uuid();
+    assert_eq!(kpdb.drop_node(sibling, group, true).unwrap(), root);
+    assert_eq!(children(&kpdb, root), [second, sibling, group, first]);
+    assert_eq!(kpdb.drop_node(sibling, root, true).unwrap(), root);
+    assert_eq!(children(&kpdb, root), [second, group, first, sibling]);
+
+    assert_eq!(kpdb.drop_node(first, group, false).unwrap(), group);
+    assert_eq!(children(&kpdb, group), [first]);
+    let node = kpdb.get_node_by_id(first).unwrap();
+    assert!(node.borrow().get_times().get_location_changed().is_some());
+    assert!(kpdb.is_data_changed());
+
+    assert!(kpdb.drop_node(group, first, false).is_err());
+    assert!(kpdb.drop_node(first, first, false).is_err());
+}
+
+#[test]
+fn test_recycle_bin() {
+    let mut kpdb = KpDb::new();
+    let root = kpdb.get_root().unwrap().borrow().get_uuid();
+    let group = kpdb.create_new_group(root).unwrap().borrow().get_uuid();
+    let entry = kpdb.create_new_entry(group).unwrap().borrow().get_uuid();
+    assert!(kpdb.move_node(group, entry).is_err());
+    assert!(kpdb.move_node(group, group).is_err());
+
+    kpdb.delete_node(entry).unwrap();
+    assert!(kpdb.is_recycled(entry));
+    assert_eq!(kpdb.restore_node(entry).unwrap(), group);
+    assert!(!kpdb.is_recycled(entry));
+    assert_eq!(kpdb.get_node_by_id(entry).unwrap().borrow().get_parent(), Some(group));
+
+    kpdb.delete_node(entry).unwrap();
+    assert_eq!(kpdb.empty_recycle_bin().unwrap(), 1);
+    assert!(kpdb.get_node_by_id(entry).is_none());
+
+    let bin = kpdb.recycle_bin().unwrap().borrow().get_uuid();
+    kpdb.set_recycle_bin(false, Some(bin)).unwrap();
+    assert_eq!(kpdb.recycle_bin_settings(), (false, Some(bin)));
+    assert!(kpdb.set_recycle_bin(true, Some(entry)).is_err());
+}
@@ -0,0 +1,85 @@
+use crate::keepass::SealedDatabase;
+use keepass_ng::Uuid;
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+
+thread_local! {
+    static LAST_ACTIVITY: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// What is needed to reopen a locked database where the user left it.
+#[derive(Clone, Debug)]
+pub struct LockedDatabase {
+    pub path: String,
+    pub selected_node: Option<Uuid>,
+    /// Changes that were not saved when it was locked, restored on unlock instead of the file.
+    pub unsaved: Option<SealedDatabase>,
+}
+
+/// Records user activity; the idle lock measures from the last call.
+pub fn note_activity() {
+    LAST_ACTIVITY.set(Some(Instant::now()));
+}
+
+/// Whether no activity was recorded during the `timeout` before `now`.
+pub fn is_idle(timeout: Duration, now: Instant) -> bool {
+    LAST_ACTIVITY
+        .get()
+        .is_some_and(|last_activity| now.saturating_duration_since(last_activity) >= timeout)
+}
+
+/// Whether the operating system session is locked; false where that cannot be told.
+pub fn session_locked() -> bool {
+    os_session_locked()
+}
+
+#[cfg(windows)]
+fn os_session_locked() -> bool {
+    use std::ffi::c_void;
+    const DESKTOP_SWITCHDESKTOP: u32 = 0x0100;
+    #[link(name = "user32")]
+    unsafe extern "system" {
+        fn OpenInputDesktop(flags: u32, inherit: i32, desired_access: u32) -> *mut c_void;
+        fn CloseDesktop(desktop: *mut c_void) -> i32;
+    }
+    // While the session is locked, the input desktop is the secure one, which user programs cannot open.
+    // SAFETY: OpenInputDesktop takes no pointers, and the handle it returns is closed exactly once.
+    let desktop = unsafe { OpenInputDesktop(0, 0, DESKTOP_SWITCHDESKTOP) };
+    if desktop.is_null() {
+        return true;
+    }
+    // SAFETY: `desktop` is the valid handle just opened above.
+    unsafe { CloseDesktop(desktop) };
+    false
+}
+
+#[cfg(target_os = "linux")]
+fn os_session_locked() -> bool {
+    // logind tracks the screen locker of the session this process belongs to.
+    std::process::Command::new("loginctl")
+        .args(["show-session", "self", "--property=LockedHint", "--value"])
+        .output()
+        .is_ok_and(|output| output.status.success() && output.stdout.trim_ascii() == b"yes")
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn os_session_locked() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_idle, note_activity};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn idle_is_measured_from_the_last_activity() {
+        assert!(!is_idle(Duration::ZERO, Instant::now()));
+        note_activity();
+        let timeout = Duration::from_secs(60);
+        assert!(!is_idle(timeout, Instant::now()));
+        assert!(is_idle(timeout, Instant::now() + timeout));
+    }
+}
@@ -1,24 +1,67 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState, hotkey::HotKey};
 use keepass_ng::{
     Uuid,
-    db::{Entry, Icon, NodePtr, group_get_children, node_is_group},
+    db::{Entry, Icon, NodePtr, group_get_children, node_is_group, with_node},
 };
 use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::path::Path;
 use std::rc::Rc;
 use wxdragon::prelude::*;
 
+pub mod attachments;
+pub mod autotype;
+pub mod autotype_dlg;
+#[cfg(target_os = "linux")]
+pub mod autotype_x11;
+pub mod breach;
+pub mod challenge_response;
+pub mod cli;
+pub mod clipboard;
+pub mod custom_field_dlg;
+pub mod custom_fields;
+pub mod disk_change_dlg;
+pub mod duplicate;
+pub mod duplicate_dlg;
 pub mod entry_view;
 pub mod error;
+pub mod expiry;
+pub mod export;
+pub mod export_dlg;
 pub mod favicon;
 pub mod group_view;
+pub mod health;
+pub mod health_view;
+pub mod history;
+pub mod history_view;
 pub mod icon_cache;
 pub mod icon_picker;
+pub mod import;
+pub mod import_dlg;
 pub mod keepass;
+pub mod lock;
+pub mod master_key_dlg;
+pub mod new_database_dlg;
+pub mod password_generator;
+pub mod password_generator_dlg;
+pub mod placeholders;
+pub mod recycle_bin_dlg;
+pub mod search;
+pub mod search_view;
+pub mod secret;
 pub mod settings;
 pub mod settings_dlg;
+pub mod sync;
+pub mod sync_dlg;
+pub mod totp;
+pub mod totp_dlg;
 
-use keepass::KpDb;
+use disk_change_dlg::DiskChangeAction;
+use keepass::{KpDb, SealedDatabase};
+use lock::LockedDatabase;
+use secret::SecretString;
 use settings::{MAX_RECENT_FILES, Settings};
 
 const TREE_PANE_NAME: &str = "architecture-tree";
@@ -26,21 +69,52 @@ const MENU_OPEN: i32 = 2001;
 const MENU_SAVE: i32 = 2002;
 const MENU_EXIT: i32 = 2003;
 const MENU_CLOSE: i32 = 2004;
+const MENU_NEW: i32 = 2005;
+const MENU_IMPORT: i32 = 2006;
+const MENU_EXPORT: i32 = 2007;
+const MENU_CHANGE_MASTER_KEY: i32 = 2050;
+const MENU_LOCK: i32 = 2051;
+const MENU_SYNCHRONIZE: i32 = 2052;
+const MENU_EMPTY_RECYCLE_BIN: i32 = 2053;
+const MENU_RECYCLE_BIN_SETTINGS: i32 = 2054;
+const MENU_HEALTH_REPORT: i32 = 2055;
+const MENU_BREACH_LIST: i32 = 2056;
+const MENU_COPY_USERNAME: i32 = 2060;
+const MENU_COPY_PASSWORD: i32 = 2061;
+const MENU_COPY_URL: i32 = 2062;
+const MENU_COPY_TOTP: i32 = 2063;
 const MENU_SETTINGS: i32 = 2100;
 const MENU_TOGGLE_TREE: i32 = 2101;
 const MENU_TOGGLE_SHOW: i32 = 2102;
+const MENU_AUTOTYPE: i32 = 2103;
 const MENU_ABOUT: i32 = 2201;
 const MENU_TREE_NEW_GROUP: i32 = 2301;
 const MENU_TREE_NEW_ENTRY: i32 = 2302;
 const MENU_TREE_EDIT: i32 = 2303;
 const MENU_TREE_DELETE: i32 = 2304;
+const MENU_TREE_RESTORE: i32 = 2305;
+const MENU_TREE_DUPLICATE: i32 = 2306;
 const MENU_RECENT_FILE_FIRST: i32 = 2410;
 const MENU_RECENT_FILE_LAST: i32 = MENU_RECENT_FILE_FIRST + MAX_RECENT_FILES as i32 - 1;
+const MAX_TRAY_ENTRIES: usize = 5;
+const MENU_TRAY_USERNAME_FIRST: i32 = 2510;
+const MENU_TRAY_USERNAME_LAST: i32 = MENU_TRAY_USERNAME_FIRST + MAX_TRAY_ENTRIES as i32 - 1;
+const MENU_TRAY_PASSWORD_FIRST: i32 = 2520;
+const MENU_TRAY_PASSWORD_LAST: i32 = MENU_TRAY_PASSWORD_FIRST + MAX_TRAY_ENTRIES as i32 - 1;
+const TRAY_USERNAME_MENU_POSITION: usize = 2;
+const TRAY_PASSWORD_MENU_POSITION: usize = 3;
+const MAX_TRAY_EXPIRING: usize = 10;
+const MENU_TRAY_EXPIRING_FIRST: i32 = 2530;
+const MENU_TRAY_EXPIRING_LAST: i32 = MENU_TRAY_EXPIRING_FIRST + MAX_TRAY_EXPIRING as i32 - 1;
+const TRAY_EXPIRING_MENU_POSITION: usize = 4;
+const EXPIRY_CHECK_INTERVAL_MS: i32 = 60_000;
 
 #[allow(dead_code)]
 struct TrayState {
     taskbar: TaskBarIcon,
     popup_menu: Menu,
+    recent_entries: Vec<(Uuid, String)>,
+    expiring: Vec<(Uuid, String)>,
 }
 
 thread_local! {
@@ -59,10 +133,11 @@ fn update_recent_menu(menu_bar: &MenuBar, recent_files: Option<&[String]>) {
     let Some(file_menu) = menu_bar.get_menu(0) else {
         return;
     };
-    let Some(recent_item) = file_menu.find_item_by_position(6) else {
-        return;
-    };
-    let Some(recent_menu) = recent_item.get_sub_menu() else {
+    // "Recent files" is the only submenu of the File menu.
+    let Some(recent_menu) = (0..)
+        .map_while(|position| file_menu.find_item_by_position(position))
+        .find_map(|item| item.get_sub_menu())
+    else {
         return;
     };
     for id in MENU_RECENT_FILE_FIRST..=MENU_RECENT_FILE_LAST {
@@ -92,6 +167,282 @@ fn update_recent_menu(menu_bar: &MenuBar, recent_files: Option<&[String]>) {
     }
 }
 
+fn update_tray_entries(popup_menu: &Menu, recent_entries: &[(Uuid, String)]) {
+    for (position, first_id) in [
+        (TRAY_USERNAME_MENU_POSITION, MENU_TRAY_USERNAME_FIRST),
+        (TRAY_PASSWORD_MENU_POSITION, MENU_TRAY_PASSWORD_FIRST),
+    ] {
+        let Some(submenu) = popup_menu.find_item_by_position(position).and_then(|item| item.get_sub_menu()) else {
+            continue;
+        };
+        for id in first_id..first_id + MAX_TRAY_ENTRIES as i32 {
+            if submenu.find_item(id).is_some() {
+                submenu.delete(id);
+            }
+        }
+        if recent_entries.is_empty() {
+            if let Some(item) = submenu.append(first_id, "No recent entries", "", ItemKind::Normal) {
+                item.enable(false);
+            }
+            continue;
+        }
+        for (index, (_, title)) in recent_entries.iter().enumerate() {
+            submenu.append(first_id + index as i32, title, "", ItemKind::Normal);
+        }
+    }
+}
+
+fn update_tray_expiring(popup_menu: &Menu, expiring: &[(Uuid, String)]) {
+    let Some(submenu) = popup_menu
+        .find_item_by_position(TRAY_EXPIRING_MENU_POSITION)
+        .and_then(|item| item.get_sub_menu())
+    else {
+        return;
+    };
+    for id in MENU_TRAY_EXPIRING_FIRST..=MENU_TRAY_EXPIRING_LAST {
+        if submenu.find_item(id).is_some() {
+            submenu.delete(id);
+        }
+    }
+    if expiring.is_empty() {
+        if let Some(item) = submenu.append(MENU_TRAY_EXPIRING_FIRST, "Nothing expires soon", "", ItemKind::Normal) {
+            item.enable(false);
+        }
+        return;
+    }
+    for (index, (_, label)) in expiring.iter().take(MAX_TRAY_EXPIRING).enumerate() {
+        submenu.append(MENU_TRAY_EXPIRING_FIRST + index as i32, label, "", ItemKind::Normal);
+    }
+}
+
+/// Shows what expired or expires soon in the tray menu and tooltip.
+/// Returns the items that are new to the list or expired since the last check, so the caller can announce them.
+fn update_tray_expiry(items: &[expiry::Expiring], now: chrono::NaiveDateTime) -> Vec<expiry::Expiring> {
+    TRAY_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let Some(tray) = state.as_mut() else {
+            return Vec::new();
+        };
+        let labels = items.iter().map(|item| (item.uuid, item.describe(now))).collect::<Vec<_>>();
+        if labels == tray.expiring {
+            return Vec::new();
+        }
+        let added = items
+            .iter()
+            .zip(&labels)
+            .filter(|(_, label)| !tray.expiring.contains(label))
+            .map(|(item, _)| item.clone())
+            .collect();
+        update_tray_expiring(&tray.popup_menu, &labels);
+        let tooltip = if items.is_empty() {
+            "mypass".to_string()
+        } else {
+            format!("mypass - {}", expiry::summary(items, now))
+        };
+        set_tray_icon(&tray.taskbar, application_icon().as_ref(), &tooltip);
+        tray.expiring = labels;
+        added
+    })
+}
+
+/// Looks for expired and soon expiring entries and groups and announces the ones that are new.
+fn check_expiring(kpdb: &Rc<RefCell<Option<KpDb>>>, status_bar: &StatusBar) {
+    // A dialog may be holding the database mutably; the next check catches up.
+    let Ok(kpdb) = kpdb.try_borrow() else {
+        return;
+    };
+    let now = chrono::Local::now().naive_local();
+    let items = kpdb
+        .as_ref()
+        .map(|db| expiry::expiring(db, now, health::EXPIRING_SOON_DAYS))
+        .unwrap_or_default();
+    drop(kpdb);
+    let added = update_tray_expiry(&items, now);
+    if added.is_empty() {
+        return;
+    }
+    let message = match &added[..] {
+        [item] => item.describe(now),
+        _ => expiry::summary(&added, now),
+    };
+    log::info!("Expiry: {message}");
+    status_bar.set_status_text(&format!("Expiry: {message}"), 0);
+    // wxWidgets only has tray balloons on Windows; elsewhere the tooltip and the menu carry the news.
+    #[cfg(target_os = "windows")]
+    TRAY_STATE.with(|state| {
+        if let Some(tray) = state.borrow().as_ref() {
+            tray.taskbar.show_balloon("Credentials expiring", &message, 10_000, 0);
+        }
+    });
+}
+
+fn expiring_entry_uuid(index: usize) -> Option<Uuid> {
+    TRAY_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .and_then(|tray| tray.expiring.get(index).map(|(uuid, _)| *uuid))
+    })
+}
+
+/// Installs the tray icon, or a blank one when the application icon is missing, with `tooltip`.
+fn set_tray_icon(taskbar: &TaskBarIcon, icon: Option<&Bitmap>, tooltip: &str) {
+    if let Some(icon) = icon {
+        if !taskbar.set_icon(icon, tooltip) {
+            log::warn!("Failed to install the mypass tray icon");
+        }
+    } else if let Some(fallback) = Bitmap::new(16, 16)
+        && !taskbar.set_icon(&fallback, tooltip)
+    {
+        log::warn!("Failed to install the fallback mypass tray icon");
+    }
+}
+
+/// Selects node `uuid` in the tree and shows it; false when it is not in the open database.
+fn reveal_node(
+    frame: Frame,
+    tree: &TreeCtrl,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+    content: &Panel,
+    current_view: &Rc<RefCell<Option<Panel>>>,
+    status_bar: &StatusBar,
+    uuid: Uuid,
+) -> bool {
+    let Some(node) = kpdb.borrow().as_ref().and_then(|db| db.get_node_by_id(uuid)) else {
+        return false;
+    };
+    if let Some(root_item) = tree.get_root_item()
+        && let Some(item) = find_tree_item(tree, &root_item, uuid)
+    {
+        tree.select_item(&item);
+    }
+    show_node_view(content, frame, current_view, &node, tree, kpdb, status_bar);
+    true
+}
+
+/// Puts an entry at the top of the tray's recently used entries.
+pub(crate) fn remember_recent_entry(node: &NodePtr) {
+    let uuid = node.borrow().get_uuid();
+    let title = node_title(node);
+    TRAY_STATE.with(|state| {
+        if let Some(tray) = state.borrow_mut().as_mut() {
+            tray.recent_entries.retain(|(recent_uuid, _)| *recent_uuid != uuid);
+            tray.recent_entries.insert(0, (uuid, title));
+            tray.recent_entries.truncate(MAX_TRAY_ENTRIES);
+            update_tray_entries(&tray.popup_menu, &tray.recent_entries);
+        }
+    });
+}
+
+fn forget_recent_entries() {
+    TRAY_STATE.with(|state| {
+        if let Some(tray) = state.borrow_mut().as_mut() {
+            tray.recent_entries.clear();
+            update_tray_entries(&tray.popup_menu, &tray.recent_entries);
+        }
+    });
+}
+
+fn recent_entry_uuid(index: usize) -> Option<Uuid> {
+    TRAY_STATE.with(|state| {
+        state
+            .borrow()
+            .as_ref()
+            .and_then(|tray| tray.recent_entries.get(index).map(|(uuid, _)| *uuid))
+    })
+}
+
+/// Registers the global Auto-Type hotkey; it stays registered for as long as the returned manager lives.
+fn register_autotype_hotkey(text: Option<&str>) -> Option<(GlobalHotKeyManager, HotKey)> {
+    let text = text?;
+    let hotkey = text
+        .parse::<HotKey>()
+        .inspect_err(|error| log::warn!("Invalid Auto-Type hotkey {text}: {error}"))
+        .ok()?;
+    let manager = GlobalHotKeyManager::new()
+        .inspect_err(|error| log::warn!("Global hotkeys are not available: {error}"))
+        .ok()?;
+    manager
+        .register(hotkey)
+        .inspect_err(|error| log::warn!("Could not register the Auto-Type hotkey {text}: {error}"))
+        .ok()?;
+    Some((manager, hotkey))
+}
+
+/// Types the entry that matches the active window, asking which one when several do; `false` when the user cancelled.
+#[cfg(target_os = "linux")]
+fn perform_global_autotype(frame: Frame, kpdb: &Rc<RefCell<Option<KpDb>>>) -> Result<bool, String> {
+    let window = autotype_x11::active_window().map_err(|error| error.to_string())?;
+    let candidates = {
+        let kpdb = kpdb.borrow();
+        let db = kpdb.as_ref().ok_or("Open or unlock a database first")?;
+        autotype::candidates(db, &window.title)
+    };
+    let index = match candidates.len() {
+        0 => return Err(format!("No entry matches the window \"{}\"", window.title)),
+        1 => 0,
+        _ => match autotype_dlg::choose(&frame, &window.title, &candidates) {
+            Some(index) => index,
+            None => return Ok(false),
+        },
+    };
+    let candidate = &candidates[index];
+    let (node, actions) = {
+        let kpdb = kpdb.borrow();
+        let db = kpdb.as_ref().ok_or("The database was closed")?;
+        let node = db.get_node_by_id(candidate.uuid).ok_or("The entry is no longer in the database")?;
+        let entry = with_node::<Entry, _, _>(&node, Entry::clone).ok_or("Only entries can be typed")?;
+        let actions = autotype::compile(db.get_root().as_ref(), &entry, &candidate.sequence).map_err(|error| error.to_string())?;
+        (node, actions)
+    };
+    remember_recent_entry(&node);
+    std::thread::spawn(move || {
+        let typed = autotype_x11::XTestOutput::open().and_then(|mut output| {
+            output.activate(&window);
+            // Give the window manager a moment to hand the focus back before typing.
+            std::thread::sleep(std::time::Duration::from_millis(250));
+            autotype::perform(&actions, &mut output)
+        });
+        if let Err(error) = typed {
+            log::warn!("Auto-Type failed: {error}");
+        }
+    });
+    Ok(true)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn perform_global_autotype(_frame: Frame, _kpdb: &Rc<RefCell<Option<KpDb>>>) -> Result<bool, String> {
+    Err("Auto-Type is only available on Linux".to_string())
+}
+
+/// Starts Auto-Type from the hotkey or the tray menu and reports how it went.
+fn start_global_autotype(frame: Frame, kpdb: &Rc<RefCell<Option<KpDb>>>, status_bar: &StatusBar) {
+    match perform_global_autotype(frame, kpdb) {
+        Ok(true) => status_bar.set_status_text("Auto-Type started", 0),
+        Ok(false) => {}
+        Err(error) => {
+            log::warn!("Auto-Type: {error}");
+            status_bar.set_status_text(&format!("Auto-Type: {error}"), 0);
+        }
+    }
+}
+
+/// Copies the KDBX field `field` of entry `uuid` with its placeholders resolved.
+fn copy_entry_field(frame: Frame, kpdb: &Rc<RefCell<Option<KpDb>>>, uuid: Uuid, field: &str) -> Result<(), String> {
+    let node = kpdb
+        .borrow()
+        .as_ref()
+        .and_then(|db| db.get_node_by_id(uuid))
+        .ok_or("The entry is no longer in the database")?;
+    let value = with_node::<Entry, _, _>(&node, |entry| placeholders::resolved_field(kpdb.borrow().as_ref(), entry, field))
+        .ok_or("Select an entry first")?;
+    if value.is_empty() {
+        return Err("The field is empty".to_string());
+    }
+    entry_view::copy_to_clipboard(&frame, &node, &value);
+    Ok(())
+}
+
 fn node_icon_index(tree: &TreeCtrl, node: &NodePtr, kpdb: Option<&KpDb>) -> Option<i32> {
     let image_list = tree.get_image_list()?;
     let bitmap = match node.borrow().get_icon() {
@@ -104,6 +455,20 @@ fn node_icon_index(tree: &TreeCtrl, node: &NodePtr, kpdb: Option<&KpDb>) -> Opti
     Some(image_list.add_bitmap(&bitmap))
 }
 
+/// Prefix of the text a dragged node carries, so that text dropped from other programs is ignored.
+const NODE_DRAG_PREFIX: &str = "mypass-node:";
+
+/// Dropping a node this many pixels from the top edge of a tree item places it before the item instead of inside.
+const DROP_BEFORE_MARGIN: i32 = 5;
+
+/// Drags node `uuid` from `source`; dropping it on a tree item moves it there.
+pub(crate) fn drag_node<W: WxWidget>(source: &W, uuid: Uuid) {
+    let data = TextDataObject::new(&format!("{NODE_DRAG_PREFIX}{uuid}"));
+    let drop_source = DropSource::new(source);
+    drop_source.set_data(&data);
+    drop_source.do_drag_drop(true);
+}
+
 fn set_node_icon(tree: &TreeCtrl, item: &TreeItemId, node: &NodePtr, kpdb: Option<&KpDb>) {
     let image_index = node_icon_index(tree, node, kpdb).unwrap_or(-1);
     for icon_type in [
@@ -116,19 +481,22 @@ fn set_node_icon(tree: &TreeCtrl, item: &TreeItemId, node: &NodePtr, kpdb: Optio
     }
 }
 
-fn append_nodes(tree: &TreeCtrl, parent: &TreeItemId, node: &NodePtr, kpdb: Option<&KpDb>) {
+fn append_nodes(tree: &TreeCtrl, parent: &TreeItemId, node: &NodePtr, kpdb: Option<&KpDb>, filter: Option<&HashSet<Uuid>>) {
     let Some(children) = group_get_children(node) else {
         return;
     };
 
     for child in children {
         let uuid = child.borrow().get_uuid();
+        if filter.is_some_and(|filter| !filter.contains(&uuid)) {
+            continue;
+        }
         let image_index = node_icon_index(tree, &child, kpdb);
         let Some(item) = tree.append_item_with_data(parent, &node_title(&child), uuid, image_index, image_index) else {
             continue;
         };
         if node_is_group(&child) {
-            append_nodes(tree, &item, &child, kpdb);
+            append_nodes(tree, &item, &child, kpdb, filter);
         }
     }
 }
@@ -138,12 +506,17 @@ fn append_node(tree: &TreeCtrl, parent: &TreeItemId, node: &NodePtr, kpdb: Optio
     let image_index = node_icon_index(tree, node, kpdb);
     let item = tree.append_item_with_data(parent, &node_title(node), uuid, image_index, image_index)?;
     if node_is_group(node) {
-        append_nodes(tree, &item, node, kpdb);
+        append_nodes(tree, &item, node, kpdb, None);
     }
     Some(item)
 }
 
 fn populate_tree(tree: &TreeCtrl, kpdb: Option<&KpDb>) -> Option<TreeItemId> {
+    populate_filtered_tree(tree, kpdb, None)
+}
+
+/// Fills the tree, keeping only the nodes in `filter` when one is given.
+fn populate_filtered_tree(tree: &TreeCtrl, kpdb: Option<&KpDb>, filter: Option<&HashSet<Uuid>>) -> Option<TreeItemId> {
     tree.set_image_list(ImageList::new(20, 20, true, 0));
     let Some(root) = kpdb.and_then(KpDb::get_root) else {
         tree.add_root("No keepass database loaded", None, None);
@@ -152,7 +525,7 @@ fn populate_tree(tree: &TreeCtrl, kpdb: Option<&KpDb>) -> Option<TreeItemId> {
 
     let image_index = node_icon_index(tree, &root, kpdb);
     let root_item = tree.add_root_with_data(&node_title(&root), root.borrow().get_uuid(), image_index, image_index)?;
-    append_nodes(tree, &root_item, &root, kpdb);
+    append_nodes(tree, &root_item, &root, kpdb, filter);
     tree.expand(&root_item);
     Some(root_item)
 }
@@ -247,6 +620,7 @@ fn show_node_view(
     new_content_sizer.add(&new_view, 1, SizerFlag::All | SizerFlag::Expand, 0);
     content.set_sizer(new_content_sizer, true);
     content.layout();
+    watch_activity(&new_view);
     *current_view.borrow_mut() = Some(new_view);
 }
 
@@ -303,14 +677,155 @@ fn refresh_tree(
     show_node_view(content, frame, current_view, &selected_node, tree, kpdb, status_bar);
 }
 
-fn save_if_data_changed(frame: Frame, kpdb: &Rc<RefCell<Option<KpDb>>>) -> Result<(), String> {
-    let mut kpdb = kpdb.borrow_mut();
-    let Some(db) = kpdb.as_mut() else {
+#[allow(clippy::too_many_arguments)]
+fn show_search_results(
+    frame: Frame,
+    query: &str,
+    options: search::SearchOptions,
+    tree: &TreeCtrl,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+    content: &Panel,
+    current_view: &Rc<RefCell<Option<Panel>>>,
+    status_bar: &StatusBar,
+) -> Result<(), String> {
+    let Some(root) = kpdb.borrow().as_ref().and_then(KpDb::get_root) else {
         return Ok(());
     };
-    if !db.is_data_changed() {
-        return Ok(());
+    let results = search::search_entries(&root, query, options).map_err(|error| error.to_string())?;
+    let mut filter = HashSet::new();
+    if let Some(db) = kpdb.borrow().as_ref() {
+        for node in &results {
+            filter.insert(node.borrow().get_uuid());
+            let mut parent = node.borrow().get_parent();
+            while let Some(uuid) = parent {
+                if !filter.insert(uuid) {
+                    break;
+                }
+                parent = db.get_node_by_id(uuid).and_then(|group| group.borrow().get_parent());
+            }
+        }
+    }
+    tree.delete_all_items();
+    if populate_filtered_tree(tree, kpdb.borrow().as_ref(), Some(&filter)).is_some() {
+        tree.expand_all();
+    }
+    if let Some(old_view) = current_view.borrow_mut().take() {
+        old_view.destroy();
+    }
+    let new_view = Panel::builder(content).build();
+    search_view::build_search_view(&new_view, frame, query, &results, tree, kpdb, status_bar);
+    let new_content_sizer = BoxSizer::builder(Orientation::Vertical).build();
+    new_content_sizer.add(&new_view, 1, SizerFlag::All | SizerFlag::Expand, 0);
+    content.set_sizer(new_content_sizer, true);
+    content.layout();
+    watch_activity(&new_view);
+    *current_view.borrow_mut() = Some(new_view);
+    status_bar.set_status_text(&format!("{} entries found", results.len()), 0);
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn show_health_report(
+    frame: Frame,
+    settings: &Settings,
+    tree: &TreeCtrl,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+    content: &Panel,
+    current_view: &Rc<RefCell<Option<Panel>>>,
+    status_bar: &StatusBar,
+) -> Result<usize, String> {
+    // A list that moved or was deleted must not stop the rest of the report.
+    let (breach_list, notice) = match settings.breach_list_path.as_deref().map(breach::BreachList::open) {
+        Some(Ok(list)) => (Some(list), None),
+        Some(Err(error)) => (
+            None,
+            Some(format!(
+                "Breached passwords were not checked, the list could not be opened: {error}. Choose it again in Database > Check breached passwords."
+            )),
+        ),
+        None => (None, None),
+    };
+    let options = health::HealthOptions {
+        max_age_days: settings.password_max_age_days(),
+        expiring_within_days: health::EXPIRING_SOON_DAYS,
+        breach_list: breach_list.as_ref(),
+    };
+    let report = kpdb
+        .borrow()
+        .as_ref()
+        .map(|db| health::check(db, &options, chrono::Local::now().naive_local()))
+        .ok_or("No database loaded")?
+        .map_err(|error| error.to_string())?;
+    if let Some(old_view) = current_view.borrow_mut().take() {
+        old_view.destroy();
+    }
+    let new_view = Panel::builder(content).build();
+    health_view::build_health_view(&new_view, frame, &report, notice.as_deref(), tree, kpdb, status_bar);
+    let new_content_sizer = BoxSizer::builder(Orientation::Vertical).build();
+    new_content_sizer.add(&new_view, 1, SizerFlag::All | SizerFlag::Expand, 0);
+    content.set_sizer(new_content_sizer, true);
+    content.layout();
+    watch_activity(&new_view);
+    *current_view.borrow_mut() = Some(new_view);
+    Ok(report.findings.len())
+}
+
+/// Asks for the local breached password list, a sorted hash file or one of its range files, and remembers it.
+/// Cancelling offers to stop checking against the list chosen before.
+fn choose_breach_list(frame: Frame, settings: &mut Settings) -> Result<bool, String> {
+    let file_dialog = FileDialog::builder(&frame)
+        .with_message("Choose the sorted SHA-1 hash file, or any range file of the downloaded list")
+        .with_default_file(settings.breach_list_path.as_deref().unwrap_or(""))
+        .with_style(FileDialogStyle::Open | FileDialogStyle::FileMustExist)
+        .with_wildcard("Hash lists (*.txt)|*.txt|All files (*.*)|*.*")
+        .build();
+    let path = (file_dialog.show_modal() == wxdragon::ID_OK)
+        .then(|| file_dialog.get_path())
+        .flatten();
+    let Some(path) = path else {
+        if let Some(current) = settings.breach_list_path.clone() {
+            let message = format!("Stop checking passwords against\n{current}?");
+            let clear = MessageDialog::builder(&frame, &message, "Breached passwords")
+                .with_style(MessageDialogStyle::YesNo | MessageDialogStyle::IconWarning)
+                .build();
+            if clear.show_modal() == wxdragon::ID_YES {
+                settings.breach_list_path = None;
+                settings.save();
+            }
+            clear.destroy();
+        }
+        return Ok(false);
+    };
+    let list = breach::BreachList::open(&path).map_err(|error| error.to_string())?;
+    let path = match list {
+        breach::BreachList::Sorted(path) | breach::BreachList::Ranges(path) => path,
+    };
+    settings.breach_list_path = Some(path.to_string_lossy().into_owned());
+    settings.save();
+    Ok(true)
+}
+
+/// Saves unsaved changes, first asking what to do when another program changed the file.
+/// Returns true when the database was reloaded or merged from disk, so the views are out of date.
+fn save_if_data_changed(frame: Frame, kpdb: &Rc<RefCell<Option<KpDb>>>) -> Result<bool, String> {
+    let changed_on_disk = match kpdb.borrow().as_ref() {
+        Some(db) if db.is_data_changed() => db.changed_on_disk().then(|| db.db_path.clone().unwrap_or_default()),
+        _ => return Ok(false),
+    };
+    let mut updated_from_disk = false;
+    if let Some(path) = changed_on_disk {
+        let action = disk_change_dlg::ask(&frame, &path, true, true)
+            .ok_or("The database file was changed by another program, so nothing was saved")?;
+        apply_disk_change(kpdb, action)?;
+        if action == DiskChangeAction::Reload {
+            return Ok(true);
+        }
+        updated_from_disk = action == DiskChangeAction::Merge;
     }
+    let mut kpdb = kpdb.borrow_mut();
+    let Some(db) = kpdb.as_mut() else {
+        return Ok(updated_from_disk);
+    };
     let should_upgrade = |version: keepass_ng::DatabaseVersion| {
         let msg = format!("This database is {version:?}, but saving requires KDBX4.1. Upgrade it to KDBX4.1?\nChoose No to abandon it.",);
         let dlg = MessageDialog::builder(&frame, &msg, "Upgrade database format?")
@@ -324,34 +839,88 @@ fn save_if_data_changed(frame: Frame, kpdb: &Rc<RefCell<Option<KpDb>>>) -> Resul
     if saved && let (Some(path), Some(database)) = (db.db_path.as_deref(), db.db.as_ref()) {
         frame.set_title(&format!("mypass - {path} ({})", database.config.version));
     }
-    Ok(())
+    Ok(updated_from_disk)
 }
 
-fn close_current_file(
-    frame: Frame,
-    kpdb: &Rc<RefCell<Option<KpDb>>>,
-    tree: &TreeCtrl,
-    content: &Panel,
-    current_view: &Rc<RefCell<Option<Panel>>>,
-    status_bar: &StatusBar,
-) -> Result<(), String> {
-    if kpdb.borrow().is_some() {
-        save_if_data_changed(frame, kpdb)?;
-    }
-    kpdb.borrow_mut().take();
-    tree.delete_all_items();
-    if let Some(view) = current_view.borrow_mut().take() {
-        view.destroy();
+/// Applies the user's answer about a database file that another program changed.
+fn apply_disk_change(kpdb: &Rc<RefCell<Option<KpDb>>>, action: DiskChangeAction) -> Result<(), String> {
+    let mut kpdb = kpdb.borrow_mut();
+    let db = kpdb.as_mut().ok_or("No database loaded")?;
+    match action {
+        DiskChangeAction::Reload => *db = db.reopen().map_err(|error| error.to_string())?,
+        DiskChangeAction::Merge => {
+            let on_disk = db.reopen().map_err(|error| error.to_string())?;
+            sync::merge(db, &on_disk).map_err(|error| error.to_string())?;
+            db.adopt_disk_state(&on_disk);
+        }
+        DiskChangeAction::Overwrite => db.ignore_disk_changes(),
     }
-    content.set_sizer(BoxSizer::builder(Orientation::Vertical).build(), true);
-    content.layout();
-    status_bar.set_status_text("Ready", 0);
-    status_bar.set_status_text("No database loaded", 1);
-    frame.set_title("mypass");
     Ok(())
 }
 
-fn open_database_from_picker(
+fn change_master_key(frame: Frame, kpdb: &Rc<RefCell<Option<KpDb>>>) -> Result<bool, String> {
+    let (current_key_file, current_yubikey_slot) = {
+        let kpdb = kpdb.borrow();
+        let db = kpdb.as_ref().ok_or("No database loaded")?;
+        let slot = db
+            .db_path
+            .as_deref()
+            .and_then(|path| Settings::shared().borrow().yubikey_slot(path));
+        (db.key_file.clone(), slot)
+    };
+    let Some(change) = master_key_dlg::show(&frame, current_key_file.as_deref(), current_yubikey_slot) else {
+        return Ok(false);
+    };
+    let current_provider = challenge_response::yubikey_provider(change.current_yubikey_slot).map_err(|error| error.to_string())?;
+    let previous = {
+        let mut kpdb = kpdb.borrow_mut();
+        let db = kpdb.as_mut().ok_or("No database loaded")?;
+        if !db.verify_master_key(
+            change.current_password.as_ref().map(SecretString::expose),
+            change.current_key_file.as_deref(),
+            current_provider,
+        ) {
+            return Err("The current password, key file or YubiKey is wrong".to_string());
+        }
+        let previous = (db.password.clone(), db.key_file.clone(), db.is_data_changed());
+        db.set_master_key(
+            change.new_password.as_ref().map(SecretString::expose),
+            change.new_key_file.as_deref(),
+        )
+        .map_err(|error| error.to_string())?;
+        previous
+    };
+    let outcome = save_if_data_changed(frame, kpdb);
+    // A reload or merge replaced what the previous unsaved-changes flag described.
+    let updated_from_disk = matches!(outcome, Ok(true));
+    let saved = outcome.and_then(|_| {
+        // Declining the format upgrade leaves the database unsaved, and the new key only in memory.
+        let is_saved = kpdb.borrow().as_ref().is_some_and(|db| {
+            !db.is_data_changed()
+                && db.verify_master_key(
+                    change.new_password.as_ref().map(SecretString::expose),
+                    change.new_key_file.as_deref(),
+                    db.challenge_response.clone(),
+                )
+        });
+        if is_saved {
+            Ok(())
+        } else {
+            Err("The database was not saved, so the master key is unchanged".to_string())
+        }
+    });
+    if let Err(error) = saved {
+        if let Some(db) = kpdb.borrow_mut().as_mut() {
+            let data_changed = if updated_from_disk { db.is_data_changed() } else { previous.2 };
+            db.restore_master_key(previous.0, previous.1, data_changed);
+        }
+        return Err(error);
+    }
+    Ok(true)
+}
+
+/// Merges another copy of the database, and the file on disk when another client changed it, after the user reviewed the changes.
+fn synchronize_database(
     frame: Frame,
     kpdb: &Rc<RefCell<Option<KpDb>>>,
     tree: &TreeCtrl,
@@ -359,40 +928,540 @@ fn open_database_from_picker(
     current_view: &Rc<RefCell<Option<Panel>>>,
     status_bar: &StatusBar,
 ) -> Result<bool, String> {
-    let database_dialog = FileDialog::builder(&frame)
-        .with_message("Choose a KeePass database")
+    let (mut merged, changed_on_disk) = {
+        let kpdb = kpdb.borrow();
+        let db = kpdb.as_ref().ok_or("No database loaded")?;
+        if db.db_path.is_none() {
+            return Err("Save the database before synchronizing it".to_string());
+        }
+        (db.deep_copy().map_err(|error| error.to_string())?, db.changed_on_disk())
+    };
+
+    let file_dialog = FileDialog::builder(&frame)
+        .with_message("Choose the database to synchronize with")
         .with_style(FileDialogStyle::Open | FileDialogStyle::FileMustExist)
         .with_wildcard("KeePass database (*.kdbx)|*.kdbx|All files (*.*)|*.*")
         .build();
-    if database_dialog.show_modal() != wxdragon::ID_OK {
+    if file_dialog.show_modal() != wxdragon::ID_OK {
         return Ok(false);
     }
-    let Some(database_path) = database_dialog.get_path() else {
+    let Some(other_path) = file_dialog.get_path() else {
         return Ok(false);
     };
-    open_database_path(frame, kpdb, tree, content, current_view, status_bar, database_path)
+
+    // Copies of one database usually share the master key, so only ask when it does not fit.
+    let other = match KpDb::open(
+        &other_path,
+        merged.password.as_ref().map(SecretString::expose),
+        merged.key_file.as_deref(),
+        merged.challenge_response.clone(),
+    ) {
+        Ok(other) => other,
+        Err(_) => {
+            let Some((password, key_file, yubikey_slot)) = ask_credentials(frame, "Synchronize with database", &other_path) else {
+                return Ok(false);
+            };
+            let provider = challenge_response::yubikey_provider(yubikey_slot).map_err(|error| error.to_string())?;
+            KpDb::open(
+                &other_path,
+                password.as_ref().map(SecretString::expose),
+                key_file.as_deref(),
+                provider,
+            )
+            .map_err(|error| error.to_string())?
+        }
+    };
+
+    let mut summary = sync::MergeSummary::default();
+    if changed_on_disk {
+        let msg = "The database file was changed by another client since it was opened.\nMerge those changes as well?\nChoose No to overwrite them.";
+        let dlg = MessageDialog::builder(&frame, msg, "Database changed on disk")
+            .with_style(MessageDialogStyle::YesNo | MessageDialogStyle::Cancel | MessageDialogStyle::IconWarning)
+            .build();
+        let answer = dlg.show_modal();
+        dlg.destroy();
+        if answer == wxdragon::ID_CANCEL {
+            return Ok(false);
+        }
+        if answer == wxdragon::ID_YES {
+            let on_disk = merged.reopen().map_err(|error| error.to_string())?;
+            summary.extend(sync::merge(&mut merged, &on_disk).map_err(|error| error.to_string())?);
+            merged.adopt_disk_state(&on_disk);
+        } else {
+            merged.ignore_disk_changes();
+        }
+    }
+    summary.extend(sync::merge(&mut merged, &other).map_err(|error| error.to_string())?);
+    if summary.is_empty() {
+        MessageDialog::builder(&frame, "Both databases already contain the same entries.", "Synchronize with file")
+            .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconInformation)
+            .build()
+            .show_modal();
+        return Ok(false);
+    }
+
+    let source_name = Path::new(&other_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or(other_path);
+    if !sync_dlg::confirm(&frame, &source_name, &summary) {
+        return Ok(false);
+    }
+    let selected_uuid = tree
+        .get_selection()
+        .and_then(|item| tree.get_custom_data(&item))
+        .and_then(|data| data.downcast_ref::<Uuid>().copied());
+    kpdb.borrow_mut().replace(merged);
+    save_if_data_changed(frame, kpdb)?;
+    refresh_tree(frame, tree, kpdb, content, current_view, status_bar, selected_uuid);
+    Ok(true)
 }
 
-fn open_database_path(
+/// Imports an export of another password manager into the open database and returns how many entries were added.
+fn import_file(
     frame: Frame,
     kpdb: &Rc<RefCell<Option<KpDb>>>,
     tree: &TreeCtrl,
     content: &Panel,
     current_view: &Rc<RefCell<Option<Panel>>>,
     status_bar: &StatusBar,
-    database_path: String,
-) -> Result<bool, String> {
-    let dialog = Dialog::builder(&frame, "Open KeePass database")
-        .with_style(DialogStyle::DefaultDialogStyle | DialogStyle::ResizeBorder | DialogStyle::MaximizeBox)
-        .with_size(800, 220)
+) -> Result<Option<usize>, String> {
+    let (groups, root) = {
+        let kpdb = kpdb.borrow();
+        let db = kpdb.as_ref().ok_or("Open or create a database to import into")?;
+        let root = db.get_root().ok_or("No database loaded")?;
+        let root_title = root.borrow().get_title().unwrap_or("Root").to_string();
+        let root = root.borrow().get_uuid();
+        let mut groups = vec![(root, root_title)];
+        groups.extend(db.group_paths().into_iter().filter(|(uuid, _)| !db.is_recycled(*uuid)));
+        (groups, root)
+    };
+
+    let file_dialog = FileDialog::builder(&frame)
+        .with_message("Choose the file to import")
+        .with_style(FileDialogStyle::Open | FileDialogStyle::FileMustExist)
+        .with_wildcard("Exports (*.csv;*.json;*.1pux)|*.csv;*.json;*.1pux|All files (*.*)|*.*")
         .build();
-    dialog.set_min_size(Size::new(620, 244));
-    let dialog_sizer = BoxSizer::builder(Orientation::Vertical).build();
+    if file_dialog.show_modal() != wxdragon::ID_OK {
+        return Ok(None);
+    }
+    let Some(path) = file_dialog.get_path() else {
+        return Ok(None);
+    };
+    let data = std::fs::read(&path).map_err(|error| error.to_string())?;
+    let file_name = Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.clone());
+
+    let selected = tree
+        .get_selection()
+        .and_then(|item| tree.get_custom_data(&item))
+        .and_then(|data| data.downcast_ref::<Uuid>().copied())
+        .filter(|uuid| groups.iter().any(|(group, _)| group == uuid))
+        .unwrap_or(root);
+    let guessed = import::Format::guess(Path::new(&path), &data);
+    let Some((format, parent)) = import_dlg::show(&frame, &file_name, guessed, &groups, selected) else {
+        return Ok(None);
+    };
+    let entries = if format == import::Format::Csv {
+        let rows = import::read_csv(&data).map_err(|error| error.to_string())?;
+        if rows.is_empty() {
+            return Err(format!("{file_name} is empty"));
+        }
+        let Some(mapping) = import_dlg::map_columns(&frame, &rows) else {
+            return Ok(None);
+        };
+        import::parse_csv(&rows, &mapping)
+    } else {
+        import::parse(format, &data).map_err(|error| error.to_string())?
+    };
+    if entries.is_empty() {
+        return Err(format!("{file_name} does not contain any entries"));
+    }
+
+    let count = {
+        let mut kpdb = kpdb.borrow_mut();
+        let db = kpdb.as_mut().ok_or("No database loaded")?;
+        import::import(db, parent, &entries).map_err(|error| error.to_string())?
+    };
+    // The entries are in the database even when saving fails, so the tree must show them either way.
+    refresh_tree(frame, tree, kpdb, content, current_view, status_bar, Some(parent));
+    save_if_data_changed(frame, kpdb)?;
+    Ok(Some(count))
+}
+
+/// Writes the database or a group subtree to an unencrypted file once the master key was entered again.
+fn export_database(frame: Frame, kpdb: &Rc<RefCell<Option<KpDb>>>, tree: &TreeCtrl) -> Result<Option<(usize, String)>, String> {
+    let (groups, database_path, database_name) = {
+        let kpdb = kpdb.borrow();
+        let db = kpdb.as_ref().ok_or("No database loaded")?;
+        let root = db.get_root().ok_or("No database loaded")?;
+        let root_title = root.borrow().get_title().unwrap_or("Root").to_string();
+        let database_path = db.db_path.clone().unwrap_or_default();
+        let database_name = Path::new(&database_path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| root_title.clone());
+        let mut groups = vec![(root.borrow().get_uuid(), format!("{root_title} (whole database)"))];
+        groups.extend(db.group_paths().into_iter().filter(|(uuid, _)| !db.is_recycled(*uuid)));
+        (groups, database_path, database_name)
+    };
+    let selected = tree
+        .get_selection()
+        .and_then(|item| tree.get_custom_data(&item))
+        .and_then(|data| data.downcast_ref::<Uuid>().copied())
+        .unwrap_or(groups[0].0);
+    let Some(options) = export_dlg::show(&frame, &groups, selected) else {
+        return Ok(None);
+    };
+
+    let Some((password, key_file, yubikey_slot)) = ask_credentials(frame, "Confirm export with the master key", &database_path) else {
+        return Ok(None);
+    };
+    let provider = challenge_response::yubikey_provider(yubikey_slot).map_err(|error| error.to_string())?;
+    let exported = {
+        let kpdb = kpdb.borrow();
+        let db = kpdb.as_ref().ok_or("No database loaded")?;
+        if !db.verify_master_key(password.as_ref().map(SecretString::expose), key_file.as_deref(), provider) {
+            return Err("The password, key file or YubiKey is wrong".to_string());
+        }
+        export::collect(db, options.group, options.format).map_err(|error| error.to_string())?
+    };
+    let contents = export::export(&exported, options.format, &options.columns, &database_name)
+        .map(zeroize::Zeroizing::new)
+        .map_err(|error| error.to_string())?;
+
+    let extension = options.format.extension();
+    let file_dialog = FileDialog::builder(&frame)
+        .with_message("Export to")
+        .with_default_file(&format!("{database_name}.{extension}"))
+        .with_style(FileDialogStyle::Save | FileDialogStyle::OverwritePrompt)
+        .with_wildcard(&format!(
+            "{} (*.{extension})|*.{extension}|All files (*.*)|*.*",
+            options.format.label()
+        ))
+        .build();
+    if file_dialog.show_modal() != wxdragon::ID_OK {
+        return Ok(None);
+    }
+    let Some(path) = file_dialog.get_path() else {
+        return Ok(None);
+    };
+    export::write_file(Path::new(&path), &contents).map_err(|error| error.to_string())?;
+    Ok(Some((exported.entry_count(), path)))
+}
+
+/// Asks how to copy node `uuid` and returns the copy, or `None` when the user cancelled.
+fn duplicate_node(frame: Frame, kpdb: &Rc<RefCell<Option<KpDb>>>, uuid: Uuid) -> Result<Option<Uuid>, String> {
+    let is_group = kpdb
+        .borrow()
+        .as_ref()
+        .and_then(|db| db.get_node_by_id(uuid))
+        .map(|node| node_is_group(&node))
+        .ok_or("No such node")?;
+    let Some(options) = duplicate_dlg::show(&frame, is_group) else {
+        return Ok(None);
+    };
+    let mut kpdb = kpdb.borrow_mut();
+    let db = kpdb.as_mut().ok_or("No database loaded")?;
+    duplicate::duplicate(db, uuid, options).map(Some).map_err(|error| error.to_string())
+}
+
+/// Asks for confirmation and empties the recycle bin; `None` when there was nothing to do or the user declined.
+fn empty_recycle_bin(frame: Frame, kpdb: &Rc<RefCell<Option<KpDb>>>) -> Result<Option<usize>, String> {
+    let count = {
+        let kpdb = kpdb.borrow();
+        let db = kpdb.as_ref().ok_or("No database loaded")?;
+        db.recycle_bin()
+            .and_then(|bin| group_get_children(&bin))
+            .map(|children| children.len())
+            .unwrap_or(0)
+    };
+    if count == 0 {
+        MessageDialog::builder(&frame, "The recycle bin is already empty.", "Empty recycle bin")
+            .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconInformation)
+            .build()
+            .show_modal();
+        return Ok(None);
+    }
+    let msg = format!("Delete the {count} items in the recycle bin for good?\nThis cannot be undone.");
+    let dialog = MessageDialog::builder(&frame, &msg, "Empty recycle bin")
+        .with_style(MessageDialogStyle::YesNo | MessageDialogStyle::IconWarning)
+        .build();
+    let confirmed = dialog.show_modal() == wxdragon::ID_YES;
+    dialog.destroy();
+    if !confirmed {
+        return Ok(None);
+    }
+    let mut kpdb = kpdb.borrow_mut();
+    let db = kpdb.as_mut().ok_or("No database loaded")?;
+    db.empty_recycle_bin().map(Some).map_err(|error| error.to_string())
+}
+
+fn configure_recycle_bin(frame: Frame, kpdb: &Rc<RefCell<Option<KpDb>>>) -> Result<bool, String> {
+    let (groups, (enabled, current)) = {
+        let kpdb = kpdb.borrow();
+        let db = kpdb.as_ref().ok_or("No database loaded")?;
+        (db.group_paths(), db.recycle_bin_settings())
+    };
+    let Some(settings) = recycle_bin_dlg::show(&frame, &groups, enabled, current) else {
+        return Ok(false);
+    };
+    let mut kpdb = kpdb.borrow_mut();
+    let db = kpdb.as_mut().ok_or("No database loaded")?;
+    db.set_recycle_bin(settings.enabled, settings.group)
+        .map_err(|error| error.to_string())?;
+    Ok(true)
+}
+
+fn close_current_file(
+    frame: Frame,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+    tree: &TreeCtrl,
+    content: &Panel,
+    current_view: &Rc<RefCell<Option<Panel>>>,
+    status_bar: &StatusBar,
+) -> Result<(), String> {
+    if kpdb.borrow().is_some() {
+        save_if_data_changed(frame, kpdb)?;
+    }
+    discard_current_file(frame, kpdb, tree, content, current_view, status_bar);
+    Ok(())
+}
+
+/// Drops the database and everything showing it, without saving.
+fn discard_current_file(
+    frame: Frame,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+    tree: &TreeCtrl,
+    content: &Panel,
+    current_view: &Rc<RefCell<Option<Panel>>>,
+    status_bar: &StatusBar,
+) {
+    kpdb.borrow_mut().take();
+    forget_recent_entries();
+    attachments::wipe_temporary_files();
+    tree.delete_all_items();
+    if let Some(view) = current_view.borrow_mut().take() {
+        view.destroy();
+    }
+    content.set_sizer(BoxSizer::builder(Orientation::Vertical).build(), true);
+    content.layout();
+    status_bar.set_status_text("Ready", 0);
+    status_bar.set_status_text("No database loaded", 1);
+    frame.set_title("mypass");
+}
+
+/// Counts key and mouse input anywhere in `window` as activity for the idle lock. wxWidgets delivers it only to the
+/// control under the pointer or with the focus, so each child gets the handlers too.
+fn watch_activity(window: &(impl WxWidget + WindowEvents)) {
+    let note_activity = |event: wxdragon::WindowEventData| {
+        lock::note_activity();
+        event.skip(true);
+    };
+    window.on_key_down(note_activity);
+    window.on_mouse_motion(note_activity);
+    window.on_mouse_left_down(note_activity);
+    window.on_mouse_right_down(note_activity);
+    window.on_mouse_wheel(note_activity);
+    for child in window.get_children() {
+        watch_activity(&child);
+    }
+}
+
+/// Whether a modal dialog is on screen; wxWidgets disables the main window while one runs.
+fn modal_dialog_open(frame: Frame) -> bool {
+    !frame.is_enabled()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn lock_database(
+    frame: Frame,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+    tree: &TreeCtrl,
+    content: &Panel,
+    current_view: &Rc<RefCell<Option<Panel>>>,
+    status_bar: &StatusBar,
+    locked: &Rc<RefCell<Option<LockedDatabase>>>,
+) -> Result<bool, String> {
+    let Some(path) = kpdb.borrow().as_ref().and_then(|db| db.db_path.clone()) else {
+        return Ok(false);
+    };
+    // Saving could ask about a changed file or a format upgrade while nobody is there to answer, leaving the
+    // database open; unsaved changes are kept encrypted in memory instead and come back on unlock.
+    let unsaved = match kpdb.borrow_mut().as_mut() {
+        Some(db) if db.is_data_changed() => Some(db.seal().map_err(|error| error.to_string())?),
+        _ => None,
+    };
+    let has_unsaved_changes = unsaved.is_some();
+    let selected_node = tree
+        .get_selection()
+        .and_then(|item| tree.get_custom_data(&item))
+        .and_then(|data| data.downcast_ref::<Uuid>().copied());
+    discard_current_file(frame, kpdb, tree, content, current_view, status_bar);
+    clipboard::clear_now();
+    *locked.borrow_mut() = Some(LockedDatabase {
+        path,
+        selected_node,
+        unsaved,
+    });
+    show_locked_view(frame, kpdb, tree, content, current_view, status_bar, locked);
+    status_bar.set_status_text(
+        if has_unsaved_changes {
+            "Database locked with unsaved changes"
+        } else {
+            "Database locked"
+        },
+        0,
+    );
+    Ok(true)
+}
+
+/// Asks before the unsaved changes of a locked database are thrown away; false keeps them.
+fn discard_locked_changes(frame: Frame, locked: &Rc<RefCell<Option<LockedDatabase>>>) -> bool {
+    let Some(path) = locked
+        .borrow()
+        .as_ref()
+        .filter(|locked| locked.unsaved.is_some())
+        .map(|locked| locked.path.clone())
+    else {
+        return true;
+    };
+    let msg = format!("{path} is locked with unsaved changes. Discard them?\nChoose No, then unlock it to save them.");
+    let dlg = MessageDialog::builder(&frame, &msg, "Discard unsaved changes?")
+        .with_style(MessageDialogStyle::YesNo | MessageDialogStyle::IconWarning)
+        .build();
+    let discard = dlg.show_modal() == wxdragon::ID_YES;
+    dlg.destroy();
+    if discard && let Some(locked) = locked.borrow_mut().as_mut() {
+        locked.unsaved = None;
+    }
+    discard
+}
+
+fn show_locked_view(
+    frame: Frame,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+    tree: &TreeCtrl,
+    content: &Panel,
+    current_view: &Rc<RefCell<Option<Panel>>>,
+    status_bar: &StatusBar,
+    locked: &Rc<RefCell<Option<LockedDatabase>>>,
+) {
+    let Some(path) = locked.borrow().as_ref().map(|locked| locked.path.clone()) else {
+        return;
+    };
+    if let Some(old_view) = current_view.borrow_mut().take() {
+        old_view.destroy();
+    }
+    let view = Panel::builder(content).build();
+    let message = StaticText::builder(&view).with_label(&format!("{path} is locked.")).build();
+    let unlock = Button::builder(&view).with_label("Unlock...").build();
+    let sizer = BoxSizer::builder(Orientation::Vertical).build();
+    sizer.add(&message, 0, SizerFlag::All | SizerFlag::Expand, 12);
+    sizer.add(&unlock, 0, SizerFlag::Left, 12);
+    view.set_sizer(sizer, true);
+
+    let kpdb_for_unlock = Rc::clone(kpdb);
+    let tree_for_unlock = *tree;
+    let content_for_unlock = *content;
+    let current_view_for_unlock = Rc::clone(current_view);
+    let status_bar_for_unlock = *status_bar;
+    let locked_for_unlock = Rc::clone(locked);
+    unlock.on_click(move |_| {
+        match unlock_database(
+            frame,
+            &kpdb_for_unlock,
+            &tree_for_unlock,
+            &content_for_unlock,
+            &current_view_for_unlock,
+            &status_bar_for_unlock,
+            &locked_for_unlock,
+        ) {
+            Ok(true) => status_bar_for_unlock.set_status_text("Database unlocked", 0),
+            Ok(false) => status_bar_for_unlock.set_status_text("Unlock cancelled", 0),
+            Err(error) => {
+                MessageDialog::builder(&frame, &error, "Unlock failed")
+                    .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                    .build()
+                    .show_modal();
+                status_bar_for_unlock.set_status_text("Could not unlock database", 0);
+            }
+        }
+    });
+
+    let content_sizer = BoxSizer::builder(Orientation::Vertical).build();
+    content_sizer.add(&view, 1, SizerFlag::All | SizerFlag::Expand, 0);
+    content.set_sizer(content_sizer, true);
+    content.layout();
+    watch_activity(&view);
+    *current_view.borrow_mut() = Some(view);
+    status_bar.set_status_text(&format!("Locked: {path}"), 1);
+}
+
+fn unlock_database(
+    frame: Frame,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+    tree: &TreeCtrl,
+    content: &Panel,
+    current_view: &Rc<RefCell<Option<Panel>>>,
+    status_bar: &StatusBar,
+    locked: &Rc<RefCell<Option<LockedDatabase>>>,
+) -> Result<bool, String> {
+    let Some(LockedDatabase {
+        path,
+        selected_node,
+        unsaved,
+    }) = locked.borrow().clone()
+    else {
+        return Ok(false);
+    };
+    if !open_database(frame, kpdb, tree, content, current_view, status_bar, path, unsaved.as_ref())? {
+        return Ok(false);
+    }
+    locked.borrow_mut().take();
+    if let Some(uuid) = selected_node {
+        reveal_node(frame, tree, kpdb, content, current_view, status_bar, uuid);
+    }
+    Ok(true)
+}
+
+fn open_database_from_picker(
+    frame: Frame,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+    tree: &TreeCtrl,
+    content: &Panel,
+    current_view: &Rc<RefCell<Option<Panel>>>,
+    status_bar: &StatusBar,
+) -> Result<bool, String> {
+    let database_dialog = FileDialog::builder(&frame)
+        .with_message("Choose a KeePass database")
+        .with_style(FileDialogStyle::Open | FileDialogStyle::FileMustExist)
+        .with_wildcard("KeePass database (*.kdbx)|*.kdbx|All files (*.*)|*.*")
+        .build();
+    if database_dialog.show_modal() != wxdragon::ID_OK {
+        return Ok(false);
+    }
+    let Some(database_path) = database_dialog.get_path() else {
+        return Ok(false);
+    };
+    open_database_path(frame, kpdb, tree, content, current_view, status_bar, database_path)
+}
+
+/// Asks for the password, key file and YubiKey slot of `database_path`; `None` when the user cancels.
+fn ask_credentials(frame: Frame, title: &str, database_path: &str) -> Option<(Option<SecretString>, Option<String>, Option<u8>)> {
+    let dialog = Dialog::builder(&frame, title)
+        .with_style(DialogStyle::DefaultDialogStyle | DialogStyle::ResizeBorder | DialogStyle::MaximizeBox)
+        .with_size(800, 260)
+        .build();
+    dialog.set_min_size(Size::new(620, 284));
+    let dialog_sizer = BoxSizer::builder(Orientation::Vertical).build();
     let fields = FlexGridSizer::builder(0, 3).with_vgap(8).with_hgap(8).build();
     fields.add_growable_col(1, 1);
 
     let database_path_control = TextCtrl::builder(&dialog)
-        .with_value(&database_path)
+        .with_value(database_path)
         .with_style(TextCtrlStyle::ReadOnly)
         .build();
     let password_panel = Panel::builder(&dialog).build();
@@ -415,11 +1484,11 @@ fn open_database_path(
     password_toggle.on_click(move |_| {
         let is_visible = !password_is_visible_for_toggle.get();
         if is_visible {
-            password_visible_for_toggle.set_value(&password_for_toggle.get_value());
+            password_visible_for_toggle.set_value(SecretString::new(password_for_toggle.get_value()).expose());
             password_for_toggle.show(false);
             password_visible_for_toggle.show(true);
         } else {
-            password_for_toggle.set_value(&password_visible_for_toggle.get_value());
+            password_for_toggle.set_value(SecretString::new(password_visible_for_toggle.get_value()).expose());
             password_visible_for_toggle.show(false);
             password_for_toggle.show(true);
         }
@@ -438,6 +1507,20 @@ fn open_database_path(
         .build();
     key_file_control.set_min_size(Size::new(300, 28));
     let key_file_button = Button::builder(&dialog).with_label("Pick key file...").build();
+    let yubikey_choice = Choice::builder(&dialog)
+        .with_choices(
+            std::iter::once("None".to_string())
+                .chain(challenge_response::YUBIKEY_SLOTS.iter().map(|slot| format!("YubiKey slot {slot}")))
+                .collect(),
+        )
+        .build();
+    let remembered_slot = Settings::shared().borrow().yubikey_slot(database_path);
+    let remembered_index = challenge_response::YUBIKEY_SLOTS
+        .iter()
+        .position(|slot| Some(*slot) == remembered_slot)
+        .map_or(0, |index| index + 1);
+    yubikey_choice.set_selection(remembered_index as u32);
+    yubikey_choice.set_tooltip("For databases protected with a YubiKey in HMAC-SHA1 challenge-response mode, as in KeePassXC");
     fields.add(
         &StaticText::builder(&dialog).with_label("Database file").build(),
         0,
@@ -452,6 +1535,14 @@ fn open_database_path(
     fields.add(&StaticText::builder(&dialog).with_label("Key file").build(), 0, SizerFlag::All, 4);
     fields.add(&key_file_control, 1, SizerFlag::All | SizerFlag::Expand, 4);
     fields.add(&key_file_button, 0, SizerFlag::All, 4);
+    fields.add(
+        &StaticText::builder(&dialog).with_label("Challenge-response").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    fields.add(&yubikey_choice, 0, SizerFlag::All, 4);
+    fields.add(&StaticText::builder(&dialog).with_label("").build(), 0, SizerFlag::All, 4);
     dialog_sizer.add_sizer(&fields, 1, SizerFlag::All | SizerFlag::Expand, 12);
 
     let key_file_for_picker = key_file_control;
@@ -484,21 +1575,112 @@ fn open_database_path(
     let dialog_for_ok = dialog;
     ok.on_click(move |_| dialog_for_ok.end_modal(wxdragon::ID_OK));
 
-    if dialog.show_modal() != wxdragon::ID_OK {
-        return Ok(false);
-    }
-
+    let result = dialog.show_modal();
     let key_file = key_file_control.get_value();
     let key_file = (!key_file.trim().is_empty()).then_some(key_file);
-    let password = if password_is_visible.get() {
+    let yubikey_slot = yubikey_choice
+        .get_selection()
+        .and_then(|selection| (selection as usize).checked_sub(1))
+        .and_then(|index| challenge_response::YUBIKEY_SLOTS.get(index).copied());
+    let password = SecretString::non_empty(if password_is_visible.get() {
         password_visible_control.get_value()
     } else {
         password_control.get_value()
+    });
+    password_control.set_value("");
+    password_visible_control.set_value("");
+    dialog.destroy();
+    (result == wxdragon::ID_OK).then_some((password, key_file, yubikey_slot))
+}
+
+fn open_database_path(
+    frame: Frame,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+    tree: &TreeCtrl,
+    content: &Panel,
+    current_view: &Rc<RefCell<Option<Panel>>>,
+    status_bar: &StatusBar,
+    database_path: String,
+) -> Result<bool, String> {
+    open_database(frame, kpdb, tree, content, current_view, status_bar, database_path, None)
+}
+
+/// Asks for the master key of `database_path` and shows the database, read from `unsaved` when it was locked with
+/// unsaved changes and from the file otherwise.
+#[allow(clippy::too_many_arguments)]
+fn open_database(
+    frame: Frame,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+    tree: &TreeCtrl,
+    content: &Panel,
+    current_view: &Rc<RefCell<Option<Panel>>>,
+    status_bar: &StatusBar,
+    database_path: String,
+    unsaved: Option<&SealedDatabase>,
+) -> Result<bool, String> {
+    let Some((password, key_file, yubikey_slot)) = ask_credentials(frame, "Open KeePass database", &database_path) else {
+        return Ok(false);
+    };
+    let provider = challenge_response::yubikey_provider(yubikey_slot).map_err(|error| error.to_string())?;
+    let provider_name = provider.as_ref().map(|provider| provider.describe());
+    let password = password.as_ref().map(SecretString::expose);
+    let new_db = match unsaved {
+        Some(sealed) => KpDb::unseal(sealed, &database_path, password, key_file.as_deref(), provider),
+        None => KpDb::open(&database_path, password, key_file.as_deref(), provider),
+    }
+    .map_err(|error| match &provider_name {
+        Some(name) => format!("{error}\nThe master key was tried with {name}."),
+        None => error.to_string(),
+    })?;
+    {
+        let settings = Settings::shared();
+        let mut settings = settings.borrow_mut();
+        settings.remember_yubikey_slot(&database_path, yubikey_slot);
+        settings.save();
+    }
+    save_if_data_changed(frame, kpdb)?;
+    show_loaded_database(frame, kpdb, tree, content, current_view, status_bar, new_db);
+    status_bar.set_status_text("Database opened", 0);
+    Ok(true)
+}
+
+fn create_new_database(
+    frame: Frame,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+    tree: &TreeCtrl,
+    content: &Panel,
+    current_view: &Rc<RefCell<Option<Panel>>>,
+    status_bar: &StatusBar,
+) -> Result<bool, String> {
+    let Some(options) = new_database_dlg::show(&frame) else {
+        return Ok(false);
     };
-    let password = (!password.is_empty()).then_some(password);
-    let new_db = KpDb::open(&database_path, password.as_deref(), key_file.as_deref()).map_err(|error| error.to_string())?;
     save_if_data_changed(frame, kpdb)?;
+    let new_db = KpDb::create(
+        &options.path,
+        options.password.as_ref().map(SecretString::expose),
+        options.key_file.as_deref(),
+        options.config,
+    )
+    .map_err(|error| error.to_string())?;
+    show_loaded_database(frame, kpdb, tree, content, current_view, status_bar, new_db);
+    status_bar.set_status_text("Database created", 0);
+    Ok(true)
+}
+
+fn show_loaded_database(
+    frame: Frame,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+    tree: &TreeCtrl,
+    content: &Panel,
+    current_view: &Rc<RefCell<Option<Panel>>>,
+    status_bar: &StatusBar,
+    new_db: KpDb,
+) {
+    let database_path = new_db.db_path.clone().unwrap_or_default();
     kpdb.borrow_mut().replace(new_db);
+    forget_recent_entries();
+    lock::note_activity();
     tree.delete_all_items();
     if let Some(view) = current_view.borrow_mut().take() {
         view.destroy();
@@ -520,8 +1702,7 @@ fn open_database_path(
         .unwrap_or_else(|| "unknown".to_string());
     frame.set_title(&format!("mypass - {database_path} ({version})"));
     status_bar.set_status_text(&database_path, 1);
-    status_bar.set_status_text("Database opened", 0);
-    Ok(true)
+    check_expiring(kpdb, status_bar);
 }
 
 fn application_icon() -> Option<Bitmap> {
@@ -533,15 +1714,18 @@ fn application_icon() -> Option<Bitmap> {
 #[tokio::main]
 async fn main() {
     dotenvy::dotenv().ok();
+    if cli::is_cli_invocation(&std::env::args_os().skip(1).collect::<Vec<_>>()) {
+        std::process::exit(cli::run());
+    }
     SystemOptions::set_option_by_int("msw.no-manifest-check", 1);
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("trace")).init();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
     if let Err(e) = wxdragon::main(on_wxdragon_init) {
         log::error!("Failed to run wxDragon application: {e}");
     }
 }
 
 fn on_wxdragon_init(app: App) {
-    let settings = Rc::new(RefCell::new(Settings::load()));
+    let settings = Settings::shared();
     let application_icon = application_icon();
     let frame = Frame::builder().with_title("mypass").with_size(Size::new(960, 640)).build();
     if let Some(icon) = application_icon.as_ref() {
@@ -568,7 +1752,9 @@ fn on_wxdragon_init(app: App) {
     let kpdb = std::env::var("DB_PATH").ok().and_then(|path| {
         let password = std::env::var("PASSWORD").ok();
         let key_file = std::env::var("KEY_FILE").ok();
-        KpDb::open(&path, password.as_deref(), key_file.as_deref()).ok()
+        let yubikey_slot = std::env::var("YUBIKEY_SLOT").ok().and_then(|slot| slot.parse().ok());
+        let provider = challenge_response::yubikey_provider(yubikey_slot).ok().flatten();
+        KpDb::open(&path, password.as_deref(), key_file.as_deref(), provider).ok()
     });
     let kpdb = Rc::new(RefCell::new(kpdb));
     if let Some(db) = kpdb.borrow().as_ref()
@@ -584,16 +1770,74 @@ fn on_wxdragon_init(app: App) {
 
     let recent_menu = Menu::builder().build();
     let file_menu = Menu::builder()
+        .append_item(MENU_NEW, "New...", "Create a new KeePass database")
         .append_item(MENU_OPEN, "Open...", "Open a KeePass database")
         .append_item(MENU_SAVE, "Save", "Save the current database")
         .append_item(MENU_CLOSE, "Close", "Close the current database")
         .append_separator()
+        .append_item(MENU_IMPORT, "Import...", "Import entries exported from another password manager")
+        .append_item(MENU_EXPORT, "Export...", "Export the database or a group to an unencrypted file")
+        .append_separator()
         .append_item(MENU_SETTINGS, "Settings", "Open application settings")
         .build();
     file_menu.append_separator();
     file_menu.append_submenu(recent_menu, "Recent files", "Open a recently used KeePass database");
     file_menu.append_separator();
     file_menu.append(MENU_EXIT, "Exit", "Exit mypass", ItemKind::Normal);
+    let database_menu = Menu::builder()
+        .append_item(
+            MENU_CHANGE_MASTER_KEY,
+            "Change master key...",
+            "Change the password or key file of the database",
+        )
+        .append_item(
+            MENU_SYNCHRONIZE,
+            "Synchronize with file...",
+            "Merge another copy of the database into this one",
+        )
+        .append_separator()
+        .append_item(
+            MENU_EMPTY_RECYCLE_BIN,
+            "Empty recycle bin",
+            "Delete everything in the recycle bin for good",
+        )
+        .append_item(
+            MENU_RECYCLE_BIN_SETTINGS,
+            "Recycle bin settings...",
+            "Turn the recycle bin on or off and choose its group",
+        )
+        .append_separator()
+        .append_item(
+            MENU_HEALTH_REPORT,
+            "Database health",
+            "List weak, reused, old and expired passwords",
+        )
+        .append_item(
+            MENU_BREACH_LIST,
+            "Check breached passwords...",
+            "Choose a local copy of the Have I Been Pwned hash list for the health report",
+        )
+        .append_separator()
+        .append_item(MENU_LOCK, "Lock\tCtrl+L", "Lock the database until the master key is entered again")
+        .build();
+    let entry_menu = Menu::builder()
+        .append_item(
+            MENU_COPY_USERNAME,
+            "Copy username\tCtrl+B",
+            "Copy the username of the selected entry",
+        )
+        .append_item(
+            MENU_COPY_PASSWORD,
+            "Copy password\tCtrl+Shift+C",
+            "Copy the password of the selected entry",
+        )
+        .append_item(MENU_COPY_URL, "Copy URL\tCtrl+U", "Copy the URL of the selected entry")
+        .append_item(
+            MENU_COPY_TOTP,
+            "Copy TOTP\tCtrl+T",
+            "Copy the current one-time code of the selected entry",
+        )
+        .build();
     let view_menu = Menu::builder()
         .append_check_item(MENU_TOGGLE_TREE, "Architecture tree", "Show or hide the architecture tree")
         .build();
@@ -602,6 +1846,8 @@ fn on_wxdragon_init(app: App) {
         .build();
     let menu_bar = MenuBar::builder()
         .append(file_menu, "File")
+        .append(database_menu, "Database")
+        .append(entry_menu, "Entry")
         .append(view_menu, "View")
         .append(help_menu, "Help")
         .build();
@@ -622,12 +1868,24 @@ fn on_wxdragon_init(app: App) {
     content_sizer.add(&initial_view, 1, SizerFlag::All | SizerFlag::Expand, 0);
     content.set_sizer(content_sizer, true);
     let current_view = Rc::new(RefCell::new(Some(initial_view)));
+    let locked = Rc::new(RefCell::new(None::<LockedDatabase>));
 
     let tree_pane = Panel::builder(&frame).build();
     let tree = TreeCtrl::builder(&tree_pane)
         .with_style(TreeCtrlStyle::HasButtons | TreeCtrlStyle::LinesAtRoot | TreeCtrlStyle::Single)
         .build();
+    let search_text = TextCtrl::builder(&tree_pane).build();
+    search_text.set_tooltip("Search titles, usernames, URLs, notes, tags and attributes");
+    let search_case = CheckBox::builder(&tree_pane).with_label("Aa").build();
+    search_case.set_tooltip("Match case");
+    let search_regex = CheckBox::builder(&tree_pane).with_label(".*").build();
+    search_regex.set_tooltip("Regular expression");
+    let search_sizer = BoxSizer::builder(Orientation::Horizontal).build();
+    search_sizer.add(&search_text, 1, SizerFlag::All | SizerFlag::Expand, 0);
+    search_sizer.add(&search_case, 0, SizerFlag::Left | SizerFlag::AlignCenterVertical, 4);
+    search_sizer.add(&search_regex, 0, SizerFlag::Left | SizerFlag::AlignCenterVertical, 4);
     let tree_sizer = BoxSizer::builder(Orientation::Vertical).build();
+    tree_sizer.add_sizer(&search_sizer, 0, SizerFlag::All | SizerFlag::Expand, 4);
     tree_sizer.add(&tree, 1, SizerFlag::All | SizerFlag::Expand, 4);
     tree_pane.set_sizer(tree_sizer, true);
     let root_item = populate_tree(&tree, kpdb.borrow().as_ref());
@@ -661,6 +1919,7 @@ fn on_wxdragon_init(app: App) {
     let content_for_selection = content;
     let status_bar_for_selection = status_bar;
     tree.on_selection_changed(move |event| {
+        lock::note_activity();
         let Some(item) = event.get_item().or_else(|| tree.get_selection()) else {
             return;
         };
@@ -686,13 +1945,57 @@ fn on_wxdragon_init(app: App) {
         status_bar.set_status_text("Node selected", 0);
     });
 
-    let context_node = Rc::new(Cell::new(None::<Uuid>));
-    let enter_edit_requested = Rc::new(Cell::new(false));
-    let enter_edit_requested_for_key = Rc::clone(&enter_edit_requested);
-    let context_node_for_key = Rc::clone(&context_node);
-    let tree_for_key = tree;
-    let kpdb_for_key = Rc::clone(&kpdb);
-    let content_for_key = content;
+    let kpdb_for_search = Rc::clone(&kpdb);
+    let current_view_for_search = Rc::clone(&current_view);
+    let run_search = Rc::new(move || {
+        lock::note_activity();
+        let query = search_text.get_value();
+        if query.trim().is_empty() {
+            let selected_uuid = tree
+                .get_selection()
+                .and_then(|item| tree.get_custom_data(&item))
+                .and_then(|data| data.downcast_ref::<Uuid>().copied());
+            refresh_tree(
+                frame,
+                &tree,
+                &kpdb_for_search,
+                &content,
+                &current_view_for_search,
+                &status_bar,
+                selected_uuid,
+            );
+            return;
+        }
+        let options = search::SearchOptions {
+            case_sensitive: search_case.get_value(),
+            regex: search_regex.get_value(),
+        };
+        if let Err(error) = show_search_results(
+            frame,
+            &query,
+            options,
+            &tree,
+            &kpdb_for_search,
+            &content,
+            &current_view_for_search,
+            &status_bar,
+        ) {
+            status_bar.set_status_text(&format!("Invalid search: {error}"), 0);
+        }
+    });
+    let run_search_for_text = Rc::clone(&run_search);
+    search_text.on_text_updated(move |_| run_search_for_text());
+    let run_search_for_case = Rc::clone(&run_search);
+    search_case.on_toggled(move |_| run_search_for_case());
+    search_regex.on_toggled(move |_| run_search());
+
+    let context_node = Rc::new(Cell::new(None::<Uuid>));
+    let enter_edit_requested = Rc::new(Cell::new(false));
+    let enter_edit_requested_for_key = Rc::clone(&enter_edit_requested);
+    let context_node_for_key = Rc::clone(&context_node);
+    let tree_for_key = tree;
+    let kpdb_for_key = Rc::clone(&kpdb);
+    let content_for_key = content;
     let current_view_for_key = Rc::clone(&current_view);
     let status_bar_for_key = status_bar;
     tree.on_key_down(move |event| {
@@ -771,28 +2074,114 @@ fn on_wxdragon_init(app: App) {
         context_node_for_tree.set(Some(*uuid));
         tree_for_context.select_item(&item);
 
-        let is_group = kpdb_for_context
+        let (is_group, is_root, is_recycled, is_recycle_bin) = kpdb_for_context
             .borrow()
             .as_ref()
-            .and_then(|db| db.get_node_by_id(*uuid))
-            .map(|node| node_is_group(&node))
-            .unwrap_or(false);
-        let mut menu = if is_group {
+            .and_then(|db| {
+                let node = db.get_node_by_id(*uuid)?;
+                let bin = db.recycle_bin().map(|bin| bin.borrow().get_uuid());
+                let is_root = node.borrow().get_parent().is_none();
+                Some((node_is_group(&node), is_root, db.is_recycled(*uuid), bin == Some(*uuid)))
+            })
+            .unwrap_or((false, false, false, false));
+        let mut menu = if is_recycled {
+            Menu::builder()
+                .append_item(MENU_TREE_RESTORE, "Restore", "Move this node back to where it was deleted from")
+                .append_item(MENU_TREE_EDIT, "Edit", "Edit this node")
+                .append_item(MENU_TREE_DELETE, "Delete", "Delete this node for good")
+                .build()
+        } else if is_recycle_bin {
+            Menu::builder()
+                .append_item(
+                    MENU_EMPTY_RECYCLE_BIN,
+                    "Empty recycle bin",
+                    "Delete everything in the recycle bin for good",
+                )
+                .build()
+        } else if is_root {
             Menu::builder()
                 .append_item(MENU_TREE_NEW_GROUP, "New Group", "Create a new group")
                 .append_item(MENU_TREE_NEW_ENTRY, "New Entry", "Create a new entry")
                 .append_separator()
                 .append_item(MENU_TREE_EDIT, "Edit", "Edit this node")
+                .build()
+        } else if is_group {
+            Menu::builder()
+                .append_item(MENU_TREE_NEW_GROUP, "New Group", "Create a new group")
+                .append_item(MENU_TREE_NEW_ENTRY, "New Entry", "Create a new entry")
+                .append_separator()
+                .append_item(MENU_TREE_EDIT, "Edit", "Edit this node")
+                .append_item(MENU_TREE_DUPLICATE, "Duplicate...", "Copy this group with everything in it")
                 .append_item(MENU_TREE_DELETE, "Delete", "Delete this node")
                 .build()
         } else {
             Menu::builder()
                 .append_item(MENU_TREE_EDIT, "Edit", "Edit this node")
+                .append_item(MENU_TREE_DUPLICATE, "Duplicate...", "Copy this entry")
                 .append_item(MENU_TREE_DELETE, "Delete", "Delete this node")
                 .build()
         };
         tree_for_context.popup_menu(&mut menu, None);
     });
+
+    let tree_for_drag = tree;
+    tree.on_begin_drag(move |event| {
+        let Some(item) = event.get_item() else {
+            return;
+        };
+        if let Some(data) = tree_for_drag.get_custom_data(&item)
+            && let Some(uuid) = data.downcast_ref::<Uuid>()
+        {
+            drag_node(&tree_for_drag, *uuid);
+        }
+    });
+    let tree_for_drop = tree;
+    let kpdb_for_drop = Rc::clone(&kpdb);
+    let content_for_drop = content;
+    let current_view_for_drop = Rc::clone(&current_view);
+    TextDropTarget::builder(&tree)
+        .with_on_drop_text(move |text, x, y| {
+            let Some(uuid) = text.strip_prefix(NODE_DRAG_PREFIX).and_then(|uuid| Uuid::parse_str(uuid).ok()) else {
+                return false;
+            };
+            let uuid_at = |y| {
+                tree_for_drop
+                    .hit_test(Point::new(x, y))
+                    .and_then(|item| tree_for_drop.get_custom_data(&item))
+                    .and_then(|data| data.downcast_ref::<Uuid>().copied())
+            };
+            let Some(target) = uuid_at(y) else {
+                return false;
+            };
+            // Near its top edge the item above, or nothing, is just a few pixels away.
+            let before = uuid_at(y - DROP_BEFORE_MARGIN) != Some(target);
+            let moved = kpdb_for_drop
+                .borrow_mut()
+                .as_mut()
+                .ok_or_else(|| "No database loaded".to_string())
+                .and_then(|db| db.drop_node(uuid, target, before).map_err(|error| error.to_string()));
+            if let Err(error) = moved {
+                status_bar.set_status_text(&format!("Move failed: {error}"), 0);
+                return false;
+            }
+            // The drag may have started in the content view, which the refresh replaces.
+            let kpdb = Rc::clone(&kpdb_for_drop);
+            let current_view = Rc::clone(&current_view_for_drop);
+            wxdragon::call_after(Box::new(move || {
+                refresh_tree(
+                    frame,
+                    &tree_for_drop,
+                    &kpdb,
+                    &content_for_drop,
+                    &current_view,
+                    &status_bar,
+                    Some(uuid),
+                );
+                status_bar.set_status_text("Node moved", 0);
+            }));
+            true
+        })
+        .build();
     if let Some(root_item) = root_item {
         tree.select_item(&root_item);
     }
@@ -810,129 +2199,107 @@ fn on_wxdragon_init(app: App) {
     let current_view_for_menu = Rc::clone(&current_view);
     let context_node_for_menu = Rc::clone(&context_node);
     let settings_for_menu = Rc::clone(&settings);
-    frame.on_menu(move |event| match event.get_id() {
-        MENU_OPEN => match open_database_from_picker(
-            frame,
-            &kpdb_for_menu,
-            &tree_for_menu,
-            &content_for_menu,
-            &current_view_for_menu,
-            &status_bar,
-        ) {
-            Ok(false) => status_bar.set_status_text("Open cancelled", 0),
-            Ok(true) => {
-                if let Some(path) = kpdb_for_menu.borrow().as_ref().and_then(|db| db.db_path.clone()) {
-                    let mut settings = settings_for_menu.borrow_mut();
-                    settings.add_recent_file(path);
-                    settings.save();
-                    if let Some(menu_bar) = frame.get_menu_bar() {
-                        update_recent_menu(&menu_bar, settings.recent_files.as_deref());
+    let locked_for_menu = Rc::clone(&locked);
+    frame.on_menu(move |event| {
+        // Accelerators arrive here without a key event reaching any watched window.
+        lock::note_activity();
+        match event.get_id() {
+            MENU_OPEN if !discard_locked_changes(frame, &locked_for_menu) => {}
+            MENU_OPEN => match open_database_from_picker(
+                frame,
+                &kpdb_for_menu,
+                &tree_for_menu,
+                &content_for_menu,
+                &current_view_for_menu,
+                &status_bar,
+            ) {
+                Ok(false) => status_bar.set_status_text("Open cancelled", 0),
+                Ok(true) => {
+                    if let Some(path) = kpdb_for_menu.borrow().as_ref().and_then(|db| db.db_path.clone()) {
+                        let mut settings = settings_for_menu.borrow_mut();
+                        settings.add_recent_file(path);
+                        settings.save();
+                        if let Some(menu_bar) = frame.get_menu_bar() {
+                            update_recent_menu(&menu_bar, settings.recent_files.as_deref());
+                        }
                     }
                 }
-            }
-            Err(error) => {
-                MessageDialog::builder(&frame, &error, "Open failed")
-                    .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
-                    .build()
-                    .show_modal();
-                status_bar.set_status_text("Could not open database", 0);
-            }
-        },
-        id @ MENU_RECENT_FILE_FIRST..=MENU_RECENT_FILE_LAST => {
-            let index = (id - MENU_RECENT_FILE_FIRST) as usize;
-            let Some(path) = settings_for_menu
-                .borrow()
-                .recent_files
-                .as_ref()
-                .and_then(|recent_files| recent_files.get(index))
-                .cloned()
-            else {
-                return;
-            };
-            match open_database_path(
+                Err(error) => {
+                    MessageDialog::builder(&frame, &error, "Open failed")
+                        .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                        .build()
+                        .show_modal();
+                    status_bar.set_status_text("Could not open database", 0);
+                }
+            },
+            MENU_NEW if !discard_locked_changes(frame, &locked_for_menu) => {}
+            MENU_NEW => match create_new_database(
                 frame,
                 &kpdb_for_menu,
                 &tree_for_menu,
                 &content_for_menu,
                 &current_view_for_menu,
                 &status_bar,
-                path.clone(),
             ) {
+                Ok(false) => status_bar.set_status_text("New database cancelled", 0),
                 Ok(true) => {
-                    let mut settings = settings_for_menu.borrow_mut();
-                    settings.add_recent_file(path);
-                    settings.save();
-                    if let Some(menu_bar) = frame.get_menu_bar() {
-                        update_recent_menu(&menu_bar, settings.recent_files.as_deref());
+                    if let Some(path) = kpdb_for_menu.borrow().as_ref().and_then(|db| db.db_path.clone()) {
+                        let mut settings = settings_for_menu.borrow_mut();
+                        settings.add_recent_file(path);
+                        settings.save();
+                        if let Some(menu_bar) = frame.get_menu_bar() {
+                            update_recent_menu(&menu_bar, settings.recent_files.as_deref());
+                        }
                     }
                 }
-                Ok(false) => status_bar.set_status_text("Open cancelled", 0),
-                Err(error) => status_bar.set_status_text(&format!("Open failed: {error}"), 0),
-            }
-        }
-        MENU_SAVE => match save_if_data_changed(frame, &kpdb_for_menu) {
-            Ok(()) => status_bar.set_status_text("Database saved", 0),
-            Err(error) => status_bar.set_status_text(&format!("Save failed: {error}"), 0),
-        },
-        MENU_SETTINGS => {
-            settings_dlg::show(&frame, &mut settings_for_menu.borrow_mut());
-        }
-        MENU_CLOSE => match close_current_file(
-            frame,
-            &kpdb_for_menu,
-            &tree_for_menu,
-            &content_for_menu,
-            &current_view_for_menu,
-            &status_bar,
-        ) {
-            Ok(()) => status_bar.set_status_text("Current database closed", 0),
-            Err(error) => status_bar.set_status_text(&format!("Close failed: {error}"), 0),
-        },
-        MENU_EXIT => {
-            if let Err(error) = save_if_data_changed(frame, &kpdb_for_menu) {
-                MessageDialog::builder(&frame, &format!("Could not save database: {error}"), "Save failed")
-                    .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
-                    .build()
-                    .show_modal();
-                return;
-            }
-            frame.close(true);
-        }
-        MENU_TREE_NEW_GROUP | MENU_TREE_NEW_ENTRY => {
-            let Some(parent_uuid) = context_node_for_menu.get() else {
-                return;
-            };
-            let Some(parent) = kpdb_for_menu.borrow().as_ref().and_then(|db| db.get_node_by_id(parent_uuid)) else {
-                status_bar.set_status_text("No database loaded", 0);
-                return;
-            };
-            if !node_is_group(&parent) {
-                status_bar.set_status_text("New nodes can only be created in a group", 0);
-                return;
-            }
-            let result = if let Some(db) = kpdb_for_menu.borrow_mut().as_mut() {
-                if event.get_id() == MENU_TREE_NEW_GROUP {
-                    db.create_new_group(parent_uuid)
-                } else {
-                    db.create_new_entry(parent_uuid)
+                Err(error) => {
+                    MessageDialog::builder(&frame, &error, "Create failed")
+                        .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                        .build()
+                        .show_modal();
+                    status_bar.set_status_text("Could not create database", 0);
                 }
-            } else {
-                status_bar.set_status_text("No database loaded", 0);
-                return;
-            };
-            match result {
-                Ok(node) => {
-                    let uuid = node.borrow().get_uuid();
-                    let editor_result = if node_is_group(&node) {
-                        group_view::show_group_editor(&frame, &node, Rc::clone(&kpdb_for_menu))
-                    } else {
-                        entry_view::show_entry_editor(&frame, &node, Rc::clone(&kpdb_for_menu))
-                    };
-                    if editor_result == wxdragon::ID_OK {
-                        status_bar.set_status_text("Node created", 0);
-                    } else {
-                        status_bar.set_status_text("Node created without changes", 0);
+            },
+            MENU_RECENT_FILE_FIRST..=MENU_RECENT_FILE_LAST if !discard_locked_changes(frame, &locked_for_menu) => {}
+            id @ MENU_RECENT_FILE_FIRST..=MENU_RECENT_FILE_LAST => {
+                let index = (id - MENU_RECENT_FILE_FIRST) as usize;
+                let Some(path) = settings_for_menu
+                    .borrow()
+                    .recent_files
+                    .as_ref()
+                    .and_then(|recent_files| recent_files.get(index))
+                    .cloned()
+                else {
+                    return;
+                };
+                match open_database_path(
+                    frame,
+                    &kpdb_for_menu,
+                    &tree_for_menu,
+                    &content_for_menu,
+                    &current_view_for_menu,
+                    &status_bar,
+                    path.clone(),
+                ) {
+                    Ok(true) => {
+                        let mut settings = settings_for_menu.borrow_mut();
+                        settings.add_recent_file(path);
+                        settings.save();
+                        if let Some(menu_bar) = frame.get_menu_bar() {
+                            update_recent_menu(&menu_bar, settings.recent_files.as_deref());
+                        }
                     }
+                    Ok(false) => status_bar.set_status_text("Open cancelled", 0),
+                    Err(error) => status_bar.set_status_text(&format!("Open failed: {error}"), 0),
+                }
+            }
+            MENU_SAVE => match save_if_data_changed(frame, &kpdb_for_menu) {
+                Ok(false) => status_bar.set_status_text("Database saved", 0),
+                Ok(true) => {
+                    let selected_uuid = tree_for_menu
+                        .get_selection()
+                        .and_then(|item| tree_for_menu.get_custom_data(&item))
+                        .and_then(|data| data.downcast_ref::<Uuid>().copied());
                     refresh_tree(
                         frame,
                         &tree_for_menu,
@@ -940,142 +2307,465 @@ fn on_wxdragon_init(app: App) {
                         &content_for_menu,
                         &current_view_for_menu,
                         &status_bar,
-                        Some(uuid),
+                        selected_uuid,
                     );
+                    status_bar.set_status_text("Database updated from disk", 0);
                 }
-                Err(error) => status_bar.set_status_text(&format!("Create failed: {error}"), 0),
-            }
-        }
-        MENU_TREE_EDIT => {
-            let Some(uuid) = context_node_for_menu.get() else {
-                return;
-            };
-            let Some(root_item) = tree_for_menu.get_root_item() else {
-                return;
-            };
-            let Some(item) = find_tree_item(&tree_for_menu, &root_item, uuid) else {
-                return;
-            };
-            show_node_editor_from_tree(
+                Err(error) => status_bar.set_status_text(&format!("Save failed: {error}"), 0),
+            },
+            MENU_LOCK => match lock_database(
                 frame,
+                &kpdb_for_menu,
                 &tree_for_menu,
-                &item,
+                &content_for_menu,
+                &current_view_for_menu,
+                &status_bar,
+                &locked_for_menu,
+            ) {
+                Ok(true) => {}
+                Ok(false) => status_bar.set_status_text("Only a saved database can be locked", 0),
+                Err(error) => status_bar.set_status_text(&format!("Lock failed: {error}"), 0),
+            },
+            MENU_SYNCHRONIZE => match synchronize_database(
+                frame,
                 &kpdb_for_menu,
+                &tree_for_menu,
                 &content_for_menu,
                 &current_view_for_menu,
                 &status_bar,
-            );
-        }
-        MENU_TREE_DELETE => {
-            let Some(uuid) = context_node_for_menu.get() else {
-                return;
-            };
-            let Some(node) = kpdb_for_menu.borrow().as_ref().and_then(|db| db.get_node_by_id(uuid)) else {
-                return;
-            };
-            let Some(parent_uuid) = node.borrow().get_parent() else {
-                status_bar.set_status_text("The database root cannot be deleted", 0);
-                return;
-            };
-            let Some(root_item) = tree_for_menu.get_root_item() else {
-                return;
-            };
-            let Some(tree_item) = find_tree_item(&tree_for_menu, &root_item, uuid) else {
-                return;
-            };
-            let Some(parent_item) = find_tree_item(&tree_for_menu, &root_item, parent_uuid) else {
-                return;
-            };
-            let title = node_title(&node);
-            let dialog = MessageDialog::builder(&frame, &format!("Delete {title}?"), "Confirm deletion")
-                .with_style(MessageDialogStyle::YesNo | MessageDialogStyle::IconWarning)
-                .build();
-            let confirmed = dialog.show_modal() == wxdragon::ID_YES;
-            dialog.destroy();
-            if !confirmed {
-                return;
+            ) {
+                Ok(true) => status_bar.set_status_text("Database synchronized", 0),
+                Ok(false) => status_bar.set_status_text("Synchronization cancelled", 0),
+                Err(error) => {
+                    MessageDialog::builder(&frame, &error, "Synchronization failed")
+                        .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                        .build()
+                        .show_modal();
+                    status_bar.set_status_text("Could not synchronize the database", 0);
+                }
+            },
+            MENU_IMPORT => match import_file(
+                frame,
+                &kpdb_for_menu,
+                &tree_for_menu,
+                &content_for_menu,
+                &current_view_for_menu,
+                &status_bar,
+            ) {
+                Ok(Some(count)) => status_bar.set_status_text(&format!("{count} entries imported"), 0),
+                Ok(None) => status_bar.set_status_text("Import cancelled", 0),
+                Err(error) => {
+                    MessageDialog::builder(&frame, &error, "Import failed")
+                        .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                        .build()
+                        .show_modal();
+                    status_bar.set_status_text("Could not import the file", 0);
+                }
+            },
+            MENU_EXPORT => match export_database(frame, &kpdb_for_menu, &tree_for_menu) {
+                Ok(Some((count, path))) => status_bar.set_status_text(&format!("{count} entries exported to {path}"), 0),
+                Ok(None) => status_bar.set_status_text("Export cancelled", 0),
+                Err(error) => {
+                    MessageDialog::builder(&frame, &error, "Export failed")
+                        .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                        .build()
+                        .show_modal();
+                    status_bar.set_status_text("Could not export the database", 0);
+                }
+            },
+            MENU_HEALTH_REPORT => match show_health_report(
+                frame,
+                &settings_for_menu.borrow(),
+                &tree_for_menu,
+                &kpdb_for_menu,
+                &content_for_menu,
+                &current_view_for_menu,
+                &status_bar,
+            ) {
+                Ok(0) => status_bar.set_status_text("No health problems found", 0),
+                Ok(count) => status_bar.set_status_text(&format!("{count} health problems found"), 0),
+                Err(error) => status_bar.set_status_text(&error, 0),
+            },
+            MENU_BREACH_LIST => {
+                match choose_breach_list(frame, &mut settings_for_menu.borrow_mut()) {
+                    Ok(true) => {}
+                    Ok(false) => return,
+                    Err(error) => {
+                        MessageDialog::builder(&frame, &error, "Breached passwords")
+                            .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                            .build()
+                            .show_modal();
+                        return;
+                    }
+                }
+                match show_health_report(
+                    frame,
+                    &settings_for_menu.borrow(),
+                    &tree_for_menu,
+                    &kpdb_for_menu,
+                    &content_for_menu,
+                    &current_view_for_menu,
+                    &status_bar,
+                ) {
+                    Ok(count) => status_bar.set_status_text(&format!("{count} health problems found"), 0),
+                    Err(error) => status_bar.set_status_text(&error, 0),
+                }
             }
-            let delete_result = {
-                let mut kpdb = kpdb_for_menu.borrow_mut();
-                kpdb.as_mut().map(|db| db.delete_node(uuid))
-            };
-            match delete_result {
-                Some(Ok(())) => {
-                    tree_for_menu.delete(&tree_item);
-                    tree_for_menu.select_item(&parent_item);
-                    let (deleted_node, recycle_bin) = kpdb_for_menu
-                        .borrow()
-                        .as_ref()
-                        .map(|db| {
-                            let deleted_node = db.get_node_by_id(uuid);
-                            let recycle_bin = db.db.as_ref().and_then(|database| database.get_recycle_bin());
-                            (deleted_node, recycle_bin)
-                        })
-                        .unwrap_or((None, None));
-                    if let (Some(deleted_node), Some(recycle_bin)) = (deleted_node, recycle_bin)
-                        && let Some(root_item) = tree_for_menu.get_root_item()
-                    {
-                        let recycle_bin_uuid = recycle_bin.borrow().get_uuid();
-                        let recycle_bin_item = find_tree_item(&tree_for_menu, &root_item, recycle_bin_uuid)
-                            .or_else(|| append_node(&tree_for_menu, &root_item, &recycle_bin, kpdb_for_menu.borrow().as_ref()));
-                        if let Some(recycle_bin_item) = recycle_bin_item {
-                            let deleted_item = find_tree_item(&tree_for_menu, &recycle_bin_item, uuid)
-                                .or_else(|| append_node(&tree_for_menu, &recycle_bin_item, &deleted_node, kpdb_for_menu.borrow().as_ref()));
-                            tree_for_menu.expand(&recycle_bin_item);
-                            if let Some(deleted_item) = deleted_item {
-                                tree_for_menu.ensure_visible(&deleted_item);
+            MENU_CHANGE_MASTER_KEY => match change_master_key(frame, &kpdb_for_menu) {
+                Ok(false) => status_bar.set_status_text("Master key unchanged", 0),
+                Ok(true) => status_bar.set_status_text("Master key changed", 0),
+                Err(error) => {
+                    MessageDialog::builder(&frame, &error, "Change master key failed")
+                        .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                        .build()
+                        .show_modal();
+                    status_bar.set_status_text("Could not change master key", 0);
+                }
+            },
+            MENU_COPY_USERNAME | MENU_COPY_PASSWORD | MENU_COPY_URL => {
+                let Some(uuid) = tree_for_menu
+                    .get_selection()
+                    .and_then(|item| tree_for_menu.get_custom_data(&item))
+                    .and_then(|data| data.downcast_ref::<Uuid>().copied())
+                else {
+                    status_bar.set_status_text("Select an entry first", 0);
+                    return;
+                };
+                let (field, copied) = match event.get_id() {
+                    MENU_COPY_USERNAME => ("UserName", "Username copied"),
+                    MENU_COPY_PASSWORD => ("Password", "Password copied"),
+                    _ => ("URL", "URL copied"),
+                };
+                match copy_entry_field(frame, &kpdb_for_menu, uuid, field) {
+                    Ok(()) => status_bar.set_status_text(copied, 0),
+                    Err(error) => status_bar.set_status_text(&error, 0),
+                }
+            }
+            MENU_COPY_TOTP => {
+                let Some(uuid) = tree_for_menu
+                    .get_selection()
+                    .and_then(|item| tree_for_menu.get_custom_data(&item))
+                    .and_then(|data| data.downcast_ref::<Uuid>().copied())
+                else {
+                    status_bar.set_status_text("Select an entry first", 0);
+                    return;
+                };
+                let node = kpdb_for_menu.borrow().as_ref().and_then(|db| db.get_node_by_id(uuid));
+                let copied = node
+                    .ok_or_else(|| "The entry is no longer in the database".to_string())
+                    .and_then(|node| entry_view::copy_one_time_code(&frame, &node, &kpdb_for_menu));
+                match copied {
+                    Ok(()) => status_bar.set_status_text("One-time code copied", 0),
+                    Err(error) => status_bar.set_status_text(&error, 0),
+                }
+            }
+            MENU_SETTINGS => {
+                settings_dlg::show(&frame, &mut settings_for_menu.borrow_mut());
+            }
+            MENU_CLOSE => match close_current_file(
+                frame,
+                &kpdb_for_menu,
+                &tree_for_menu,
+                &content_for_menu,
+                &current_view_for_menu,
+                &status_bar,
+            ) {
+                Ok(()) => status_bar.set_status_text("Current database closed", 0),
+                Err(error) => status_bar.set_status_text(&format!("Close failed: {error}"), 0),
+            },
+            MENU_EXIT => {
+                if let Err(error) = save_if_data_changed(frame, &kpdb_for_menu) {
+                    MessageDialog::builder(&frame, &format!("Could not save database: {error}"), "Save failed")
+                        .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                        .build()
+                        .show_modal();
+                    return;
+                }
+                frame.close(true);
+            }
+            MENU_TREE_NEW_GROUP | MENU_TREE_NEW_ENTRY => {
+                let Some(parent_uuid) = context_node_for_menu.get() else {
+                    return;
+                };
+                let Some(parent) = kpdb_for_menu.borrow().as_ref().and_then(|db| db.get_node_by_id(parent_uuid)) else {
+                    status_bar.set_status_text("No database loaded", 0);
+                    return;
+                };
+                if !node_is_group(&parent) {
+                    status_bar.set_status_text("New nodes can only be created in a group", 0);
+                    return;
+                }
+                let result = if let Some(db) = kpdb_for_menu.borrow_mut().as_mut() {
+                    if event.get_id() == MENU_TREE_NEW_GROUP {
+                        db.create_new_group(parent_uuid)
+                    } else {
+                        db.create_new_entry(parent_uuid)
+                    }
+                } else {
+                    status_bar.set_status_text("No database loaded", 0);
+                    return;
+                };
+                match result {
+                    Ok(node) => {
+                        let uuid = node.borrow().get_uuid();
+                        let editor_result = if node_is_group(&node) {
+                            group_view::show_group_editor(&frame, &node, Rc::clone(&kpdb_for_menu))
+                        } else {
+                            entry_view::show_entry_editor(&frame, &node, Rc::clone(&kpdb_for_menu))
+                        };
+                        if editor_result == wxdragon::ID_OK {
+                            status_bar.set_status_text("Node created", 0);
+                        } else {
+                            status_bar.set_status_text("Node created without changes", 0);
+                        }
+                        refresh_tree(
+                            frame,
+                            &tree_for_menu,
+                            &kpdb_for_menu,
+                            &content_for_menu,
+                            &current_view_for_menu,
+                            &status_bar,
+                            Some(uuid),
+                        );
+                    }
+                    Err(error) => status_bar.set_status_text(&format!("Create failed: {error}"), 0),
+                }
+            }
+            MENU_TREE_EDIT => {
+                let Some(uuid) = context_node_for_menu.get() else {
+                    return;
+                };
+                let Some(root_item) = tree_for_menu.get_root_item() else {
+                    return;
+                };
+                let Some(item) = find_tree_item(&tree_for_menu, &root_item, uuid) else {
+                    return;
+                };
+                show_node_editor_from_tree(
+                    frame,
+                    &tree_for_menu,
+                    &item,
+                    &kpdb_for_menu,
+                    &content_for_menu,
+                    &current_view_for_menu,
+                    &status_bar,
+                );
+            }
+            MENU_TREE_DELETE => {
+                let Some(uuid) = context_node_for_menu.get() else {
+                    return;
+                };
+                let Some(node) = kpdb_for_menu.borrow().as_ref().and_then(|db| db.get_node_by_id(uuid)) else {
+                    return;
+                };
+                let Some(parent_uuid) = node.borrow().get_parent() else {
+                    status_bar.set_status_text("The database root cannot be deleted", 0);
+                    return;
+                };
+                let Some(root_item) = tree_for_menu.get_root_item() else {
+                    return;
+                };
+                let Some(tree_item) = find_tree_item(&tree_for_menu, &root_item, uuid) else {
+                    return;
+                };
+                let Some(parent_item) = find_tree_item(&tree_for_menu, &root_item, parent_uuid) else {
+                    return;
+                };
+                let title = node_title(&node);
+                let dialog = MessageDialog::builder(&frame, &format!("Delete {title}?"), "Confirm deletion")
+                    .with_style(MessageDialogStyle::YesNo | MessageDialogStyle::IconWarning)
+                    .build();
+                let confirmed = dialog.show_modal() == wxdragon::ID_YES;
+                dialog.destroy();
+                if !confirmed {
+                    return;
+                }
+                let delete_result = {
+                    let mut kpdb = kpdb_for_menu.borrow_mut();
+                    kpdb.as_mut().map(|db| db.delete_node(uuid))
+                };
+                match delete_result {
+                    Some(Ok(())) => {
+                        tree_for_menu.delete(&tree_item);
+                        tree_for_menu.select_item(&parent_item);
+                        let (deleted_node, recycle_bin) = kpdb_for_menu
+                            .borrow()
+                            .as_ref()
+                            .map(|db| {
+                                let deleted_node = db.get_node_by_id(uuid);
+                                let recycle_bin = db.db.as_ref().and_then(|database| database.get_recycle_bin());
+                                (deleted_node, recycle_bin)
+                            })
+                            .unwrap_or((None, None));
+                        if let (Some(deleted_node), Some(recycle_bin)) = (deleted_node, recycle_bin)
+                            && let Some(root_item) = tree_for_menu.get_root_item()
+                        {
+                            let recycle_bin_uuid = recycle_bin.borrow().get_uuid();
+                            let recycle_bin_item = find_tree_item(&tree_for_menu, &root_item, recycle_bin_uuid)
+                                .or_else(|| append_node(&tree_for_menu, &root_item, &recycle_bin, kpdb_for_menu.borrow().as_ref()));
+                            if let Some(recycle_bin_item) = recycle_bin_item {
+                                let deleted_item = find_tree_item(&tree_for_menu, &recycle_bin_item, uuid).or_else(|| {
+                                    append_node(&tree_for_menu, &recycle_bin_item, &deleted_node, kpdb_for_menu.borrow().as_ref())
+                                });
+                                tree_for_menu.expand(&recycle_bin_item);
+                                if let Some(deleted_item) = deleted_item {
+                                    tree_for_menu.ensure_visible(&deleted_item);
+                                }
                             }
                         }
+                        if let Some(parent) = kpdb_for_menu.borrow().as_ref().and_then(|db| db.get_node_by_id(parent_uuid)) {
+                            show_node_view(
+                                &content_for_menu,
+                                frame,
+                                &current_view_for_menu,
+                                &parent,
+                                &tree_for_menu,
+                                &kpdb_for_menu,
+                                &status_bar,
+                            );
+                        }
+                        status_bar.set_status_text("Node deleted", 0);
                     }
-                    if let Some(parent) = kpdb_for_menu.borrow().as_ref().and_then(|db| db.get_node_by_id(parent_uuid)) {
-                        show_node_view(
-                            &content_for_menu,
+                    Some(Err(error)) => status_bar.set_status_text(&format!("Delete failed: {error}"), 0),
+                    None => status_bar.set_status_text("No database loaded", 0),
+                }
+            }
+            MENU_TREE_DUPLICATE => {
+                let Some(uuid) = context_node_for_menu.get() else {
+                    return;
+                };
+                match duplicate_node(frame, &kpdb_for_menu, uuid) {
+                    Ok(Some(copy)) => {
+                        refresh_tree(
                             frame,
+                            &tree_for_menu,
+                            &kpdb_for_menu,
+                            &content_for_menu,
                             &current_view_for_menu,
-                            &parent,
+                            &status_bar,
+                            Some(copy),
+                        );
+                        status_bar.set_status_text("Node duplicated", 0);
+                    }
+                    Ok(None) => {}
+                    Err(error) => status_bar.set_status_text(&format!("Duplicate failed: {error}"), 0),
+                }
+            }
+            MENU_TREE_RESTORE => {
+                let Some(uuid) = context_node_for_menu.get() else {
+                    return;
+                };
+                let restored = kpdb_for_menu
+                    .borrow_mut()
+                    .as_mut()
+                    .ok_or_else(|| "No database loaded".to_string())
+                    .and_then(|db| db.restore_node(uuid).map_err(|error| error.to_string()));
+                match restored {
+                    Ok(_) => {
+                        refresh_tree(
+                            frame,
                             &tree_for_menu,
                             &kpdb_for_menu,
+                            &content_for_menu,
+                            &current_view_for_menu,
                             &status_bar,
+                            Some(uuid),
                         );
+                        status_bar.set_status_text("Node restored", 0);
                     }
-                    status_bar.set_status_text("Node deleted", 0);
+                    Err(error) => status_bar.set_status_text(&format!("Restore failed: {error}"), 0),
                 }
-                Some(Err(error)) => status_bar.set_status_text(&format!("Delete failed: {error}"), 0),
-                None => status_bar.set_status_text("No database loaded", 0),
             }
-        }
-        MENU_TOGGLE_TREE => {
-            let shown = !aui.is_pane_shown(TREE_PANE_NAME);
-            if aui.set_pane_shown(TREE_PANE_NAME, shown) {
-                aui.update();
-                menu_bar_for_toggle.check_item(MENU_TOGGLE_TREE, shown);
-                status_bar.set_status_text("Architecture tree visibility changed", 0);
+            MENU_EMPTY_RECYCLE_BIN => match empty_recycle_bin(frame, &kpdb_for_menu) {
+                Ok(Some(count)) => {
+                    refresh_tree(
+                        frame,
+                        &tree_for_menu,
+                        &kpdb_for_menu,
+                        &content_for_menu,
+                        &current_view_for_menu,
+                        &status_bar,
+                        None,
+                    );
+                    status_bar.set_status_text(&format!("{count} items deleted for good"), 0);
+                }
+                Ok(None) => {}
+                Err(error) => status_bar.set_status_text(&format!("Emptying the recycle bin failed: {error}"), 0),
+            },
+            MENU_RECYCLE_BIN_SETTINGS => match configure_recycle_bin(frame, &kpdb_for_menu) {
+                Ok(true) => status_bar.set_status_text("Recycle bin settings changed", 0),
+                Ok(false) => {}
+                Err(error) => status_bar.set_status_text(&format!("Recycle bin settings not changed: {error}"), 0),
+            },
+            MENU_TOGGLE_TREE => {
+                let shown = !aui.is_pane_shown(TREE_PANE_NAME);
+                if aui.set_pane_shown(TREE_PANE_NAME, shown) {
+                    aui.update();
+                    menu_bar_for_toggle.check_item(MENU_TOGGLE_TREE, shown);
+                    status_bar.set_status_text("Architecture tree visibility changed", 0);
+                }
             }
+            MENU_ABOUT => {
+                MessageDialog::builder(&frame, "A KeePass database viewer.", "About mypass")
+                    .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconInformation)
+                    .build()
+                    .show_modal();
+            }
+            _ => {}
         }
-        MENU_ABOUT => {
-            MessageDialog::builder(&frame, "A KeePass database viewer.", "About mypass")
-                .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconInformation)
-                .build()
-                .show_modal();
-        }
-        _ => {}
     });
 
     let menu_bar_for_open = frame.get_menu_bar().expect("menu bar was just installed");
     frame.on_menu_opened(move |_| {
+        lock::note_activity();
         menu_bar_for_open.check_item(MENU_TOGGLE_TREE, aui.is_pane_shown(TREE_PANE_NAME));
     });
 
     let mut popup_menu = Menu::builder()
         .append_item(MENU_TOGGLE_SHOW, "Open Application", "Open the main application window")
         .append_separator()
-        .append_item(MENU_SETTINGS, "Settings", "Open application settings")
-        .append_item(MENU_ABOUT, "About", "About this application")
-        .append_separator()
-        .append_item(MENU_EXIT, "Exit", "Exit the application")
         .build();
+    popup_menu.append_submenu(
+        Menu::builder().build(),
+        "Copy username",
+        "Copy the username of a recently used entry",
+    );
+    popup_menu.append_submenu(
+        Menu::builder().build(),
+        "Copy password",
+        "Copy the password of a recently used entry",
+    );
+    popup_menu.append_submenu(
+        Menu::builder().build(),
+        "Expiring soon",
+        "Open an entry or group that expired or expires soon",
+    );
+    let autotype_hotkey = register_autotype_hotkey(settings.borrow().autotype_hotkey());
+    let autotype_label = match (&autotype_hotkey, settings.borrow().autotype_hotkey()) {
+        (Some(_), Some(text)) => format!("Auto-Type\t{text}"),
+        _ => "Auto-Type".to_string(),
+    };
+    popup_menu.append(
+        MENU_AUTOTYPE,
+        &autotype_label,
+        "Type the entry that matches the active window",
+        ItemKind::Normal,
+    );
+    popup_menu.append_separator();
+    popup_menu.append(MENU_LOCK, "Lock", "Lock the database", ItemKind::Normal);
+    popup_menu.append(MENU_SETTINGS, "Settings", "Open application settings", ItemKind::Normal);
+    popup_menu.append(MENU_ABOUT, "About", "About this application", ItemKind::Normal);
+    popup_menu.append_separator();
+    popup_menu.append(MENU_EXIT, "Exit", "Exit the application", ItemKind::Normal);
+    update_tray_entries(&popup_menu, &[]);
+    update_tray_expiring(&popup_menu, &[]);
 
     let settings_for_tray = Rc::clone(&settings);
+    let kpdb_for_tray = Rc::clone(&kpdb);
+    let tree_for_tray = tree;
+    let content_for_tray = content;
+    let current_view_for_tray = Rc::clone(&current_view);
+    let locked_for_tray = Rc::clone(&locked);
     let taskbar = TaskBarIcon::builder().with_icon_type(TaskBarIconType::Default).build();
     taskbar.set_popup_menu(&mut popup_menu);
 
@@ -1087,6 +2777,20 @@ fn on_wxdragon_init(app: App) {
                 log::info!("Open Application clicked");
                 frame.show(true);
             }
+            MENU_AUTOTYPE => start_global_autotype(frame, &kpdb_for_tray, &status_bar),
+            MENU_LOCK => {
+                if let Err(error) = lock_database(
+                    frame,
+                    &kpdb_for_tray,
+                    &tree_for_tray,
+                    &content_for_tray,
+                    &current_view_for_tray,
+                    &status_bar,
+                    &locked_for_tray,
+                ) {
+                    log::warn!("Could not lock the database: {error}");
+                }
+            }
             MENU_SETTINGS => {
                 settings_dlg::show(&frame, &mut settings_for_tray.borrow_mut());
             }
@@ -1100,25 +2804,77 @@ fn on_wxdragon_init(app: App) {
                 log::info!("Exit clicked");
                 frame.close(true);
             }
+            MENU_TRAY_USERNAME_FIRST..=MENU_TRAY_USERNAME_LAST | MENU_TRAY_PASSWORD_FIRST..=MENU_TRAY_PASSWORD_LAST => {
+                let (index, field) = if menu_id <= MENU_TRAY_USERNAME_LAST {
+                    (menu_id - MENU_TRAY_USERNAME_FIRST, "UserName")
+                } else {
+                    (menu_id - MENU_TRAY_PASSWORD_FIRST, "Password")
+                };
+                let Some(uuid) = recent_entry_uuid(index as usize) else {
+                    return;
+                };
+                if let Err(error) = copy_entry_field(frame, &kpdb_for_tray, uuid, field) {
+                    log::warn!("Could not copy from the tray menu: {error}");
+                }
+            }
+            MENU_TRAY_EXPIRING_FIRST..=MENU_TRAY_EXPIRING_LAST => {
+                let Some(uuid) = expiring_entry_uuid((menu_id - MENU_TRAY_EXPIRING_FIRST) as usize) else {
+                    return;
+                };
+                frame.show(true);
+                if !reveal_node(
+                    frame,
+                    &tree_for_tray,
+                    &kpdb_for_tray,
+                    &content_for_tray,
+                    &current_view_for_tray,
+                    &status_bar,
+                    uuid,
+                ) {
+                    status_bar.set_status_text("The expiring item is no longer in the database", 0);
+                }
+            }
             _ => {
                 log::warn!("Unknown menu item clicked: {menu_id}");
             }
         }
     });
 
-    if let Some(icon) = application_icon.as_ref() {
-        if !taskbar.set_icon(icon, "mypass") {
-            log::warn!("Failed to install the mypass tray icon");
-        }
-    } else {
-        if let Some(fallback) = Bitmap::new(16, 16)
-            && !taskbar.set_icon(&fallback, "mypass")
-        {
-            log::warn!("Failed to install the fallback mypass tray icon");
-        }
-    }
+    set_tray_icon(&taskbar, application_icon.as_ref(), "mypass");
     TRAY_STATE.with(|state| {
-        *state.borrow_mut() = Some(TrayState { taskbar, popup_menu });
+        *state.borrow_mut() = Some(TrayState {
+            taskbar,
+            popup_menu,
+            recent_entries: Vec::new(),
+            expiring: Vec::new(),
+        });
+    });
+    check_expiring(&kpdb, &status_bar);
+
+    let expiry_timer = Rc::new(Timer::new(&frame));
+    let expiry_timer_for_destroy = Rc::clone(&expiry_timer);
+    let kpdb_for_expiry = Rc::clone(&kpdb);
+    expiry_timer.on_tick(move |_| check_expiring(&kpdb_for_expiry, &status_bar));
+    expiry_timer.start(EXPIRY_CHECK_INTERVAL_MS, false);
+
+    let hotkey_timer = Rc::new(Timer::new(&frame));
+    let hotkey_timer_for_destroy = Rc::clone(&hotkey_timer);
+    let kpdb_for_hotkey = Rc::clone(&kpdb);
+    hotkey_timer.on_tick(move |_| {
+        let Some((_, hotkey)) = autotype_hotkey.as_ref() else {
+            return;
+        };
+        while let Ok(event) = GlobalHotKeyEvent::receiver().try_recv() {
+            if event.id != hotkey.id() || event.state != HotKeyState::Pressed {
+                continue;
+            }
+            start_global_autotype(frame, &kpdb_for_hotkey, &status_bar);
+        }
+    });
+    hotkey_timer.start(100, false);
+    frame.on_destroy(move |_| {
+        hotkey_timer_for_destroy.stop();
+        expiry_timer_for_destroy.stop();
     });
 
     frame.show(true);
@@ -1126,6 +2882,45 @@ fn on_wxdragon_init(app: App) {
         frame.centre();
     }
 
+    // A database passed as the only argument, as "Open with" does, is opened once the window is up.
+    if let Some(database_path) = std::env::args_os().nth(1).map(|path| path.to_string_lossy().into_owned()) {
+        let open_timer = Rc::new(Timer::new(&frame));
+        let open_timer_for_tick = Rc::clone(&open_timer);
+        let kpdb_for_argument = Rc::clone(&kpdb);
+        let current_view_for_argument = Rc::clone(&current_view);
+        let settings_for_argument = Rc::clone(&settings);
+        open_timer.on_tick(move |_| {
+            open_timer_for_tick.stop();
+            match open_database_path(
+                frame,
+                &kpdb_for_argument,
+                &tree,
+                &content,
+                &current_view_for_argument,
+                &status_bar,
+                database_path.clone(),
+            ) {
+                Ok(false) => status_bar.set_status_text("Open cancelled", 0),
+                Ok(true) => {
+                    let mut settings = settings_for_argument.borrow_mut();
+                    settings.add_recent_file(database_path.clone());
+                    settings.save();
+                    if let Some(menu_bar) = frame.get_menu_bar() {
+                        update_recent_menu(&menu_bar, settings.recent_files.as_deref());
+                    }
+                }
+                Err(error) => {
+                    MessageDialog::builder(&frame, &error, "Open failed")
+                        .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconError)
+                        .build()
+                        .show_modal();
+                    status_bar.set_status_text("Could not open database", 0);
+                }
+            }
+        });
+        open_timer.start(100, true);
+    }
+
     let icon_warmup_timer = Rc::new(Timer::new(&frame));
     let icon_warmup_index = Rc::new(Cell::new(0usize));
     let icon_warmup_size = Rc::new(Cell::new(28u32));
@@ -1152,6 +2947,136 @@ fn on_wxdragon_init(app: App) {
         timer_for_destroy.stop();
     });
 
+    let idle_lock_timer = Rc::new(Timer::new(&frame));
+    let idle_lock_timer_for_destroy = Rc::clone(&idle_lock_timer);
+    let kpdb_for_idle = Rc::clone(&kpdb);
+    let current_view_for_idle = Rc::clone(&current_view);
+    let settings_for_idle = Rc::clone(&settings);
+    let locked_for_idle = Rc::clone(&locked);
+    idle_lock_timer.on_tick(move |_| {
+        if modal_dialog_open(frame) {
+            // Locking would pull the database from under the dialog; its closing counts as activity.
+            lock::note_activity();
+            return;
+        }
+        let Ok(kpdb) = kpdb_for_idle.try_borrow() else {
+            return;
+        };
+        if kpdb.is_none() {
+            return;
+        }
+        drop(kpdb);
+        let timeout = settings_for_idle.borrow().idle_lock_timeout();
+        let idle = timeout.is_some_and(|timeout| lock::is_idle(timeout, std::time::Instant::now()));
+        if !idle && !lock::session_locked() {
+            return;
+        }
+        if let Err(error) = lock_database(
+            frame,
+            &kpdb_for_idle,
+            &tree,
+            &content,
+            &current_view_for_idle,
+            &status_bar,
+            &locked_for_idle,
+        ) {
+            log::warn!("Could not lock the idle database: {error}");
+            status_bar.set_status_text(&format!("Could not lock the idle database: {error}"), 0);
+            // Wait a full timeout before trying again instead of asking every tick.
+            lock::note_activity();
+        }
+    });
+    idle_lock_timer.start(5000, false);
+    frame.on_destroy(move |_| {
+        idle_lock_timer_for_destroy.stop();
+    });
+
+    let kpdb_for_iconize = Rc::clone(&kpdb);
+    let current_view_for_iconize = Rc::clone(&current_view);
+    let settings_for_iconize = Rc::clone(&settings);
+    let locked_for_iconize = Rc::clone(&locked);
+    frame.on_iconize(move |_| {
+        if !frame.is_iconized()
+            || !settings_for_iconize.borrow().lock_on_hide.unwrap_or(true)
+            || modal_dialog_open(frame)
+            || kpdb_for_iconize.try_borrow_mut().is_err()
+        {
+            return;
+        }
+        if let Err(error) = lock_database(
+            frame,
+            &kpdb_for_iconize,
+            &tree,
+            &content,
+            &current_view_for_iconize,
+            &status_bar,
+            &locked_for_iconize,
+        ) {
+            log::warn!("Could not lock the minimized database: {error}");
+        }
+    });
+    watch_activity(&frame);
+
+    let disk_watch_timer = Rc::new(Timer::new(&frame));
+    let disk_watch_timer_for_destroy = Rc::clone(&disk_watch_timer);
+    let kpdb_for_watch = Rc::clone(&kpdb);
+    let current_view_for_watch = Rc::clone(&current_view);
+    let ignored_disk_change = Cell::new(None);
+    disk_watch_timer.on_tick(move |_| {
+        // Reloading under an open dialog, such as the entry editor, would leave it editing a detached copy.
+        if disk_change_dlg::is_open() || modal_dialog_open(frame) {
+            return;
+        }
+        // Another dialog may be holding the database mutably; look again on the next tick.
+        let Ok(kpdb) = kpdb_for_watch.try_borrow() else {
+            return;
+        };
+        let Some(db) = kpdb.as_ref() else {
+            return;
+        };
+        let modified = db.disk_modified();
+        if modified.is_none() || modified == ignored_disk_change.get() || !db.changed_on_disk() {
+            return;
+        }
+        let path = db.db_path.clone().unwrap_or_default();
+        let has_unsaved_changes = db.is_data_changed();
+        drop(kpdb);
+        let Some(action) = disk_change_dlg::ask(&frame, &path, has_unsaved_changes, false) else {
+            ignored_disk_change.set(modified);
+            status_bar.set_status_text("The database file was changed by another program", 0);
+            return;
+        };
+        if let Err(error) = apply_disk_change(&kpdb_for_watch, action) {
+            status_bar.set_status_text(&format!("Could not read the changed database: {error}"), 0);
+            return;
+        }
+        let selected_uuid = tree
+            .get_selection()
+            .and_then(|item| tree.get_custom_data(&item))
+            .and_then(|data| data.downcast_ref::<Uuid>().copied());
+        refresh_tree(
+            frame,
+            &tree,
+            &kpdb_for_watch,
+            &content,
+            &current_view_for_watch,
+            &status_bar,
+            selected_uuid,
+        );
+        status_bar.set_status_text(
+            if action == DiskChangeAction::Reload {
+                "Database reloaded from disk"
+            } else {
+                "Changes from disk merged"
+            },
+            0,
+        );
+    });
+    disk_watch_timer.start(3000, false);
+    frame.on_destroy(move |_| {
+        disk_watch_timer_for_destroy.stop();
+    });
+
     let os_shuting_down = Rc::new(Cell::new(false));
     let os_shuting_down_1 = Rc::clone(&os_shuting_down);
     app.on_query_end_session(move |_event| {
@@ -1159,6 +3084,8 @@ fn on_wxdragon_init(app: App) {
     });
 
     let kpdb_for_close = Rc::clone(&kpdb);
+    let current_view_for_close = Rc::clone(&current_view);
+    let locked_for_close = Rc::clone(&locked);
     let settings_for_close = Rc::clone(&settings);
     let tree_pane_for_close = tree_pane;
     let aui_for_close = aui;
@@ -1172,6 +3099,25 @@ fn on_wxdragon_init(app: App) {
             }
             event.veto();
             frame.show(false);
+            if settings_for_close.borrow().lock_on_hide.unwrap_or(true)
+                && let Err(error) = lock_database(
+                    frame,
+                    &kpdb_for_close,
+                    &tree,
+                    &content,
+                    &current_view_for_close,
+                    &status_bar,
+                    &locked_for_close,
+                )
+            {
+                log::warn!("Could not lock the hidden database: {error}");
+            }
+            return;
+        }
+        if !discard_locked_changes(frame, &locked_for_close) {
+            if let wxdragon::WindowEventData::General(event) = &evt {
+                event.veto();
+            }
             return;
         }
         if let Err(error) = save_if_data_changed(frame, &kpdb_for_close) {
@@ -1221,6 +3167,8 @@ fn on_wxdragon_init(app: App) {
                 tray.popup_menu.destroy_menu();
             }
         });
+        clipboard::clear_now();
+        attachments::wipe_temporary_files();
         log::info!("Application destroyed, event is {evt:?}");
     });
 }
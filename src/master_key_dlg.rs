@@ -0,0 +1,181 @@
+use crate::challenge_response;
+use crate::secret::SecretString;
+use wxdragon::{
+    BoxSizer, Button, ButtonEvents, Choice, Dialog, FileDialog, FileDialogStyle, FlexGridSizer, MessageDialog, MessageDialogStyle,
+    Orientation, Size, SizerFlag, StaticText, TextCtrl, TextCtrlStyle, WxWidget,
+};
+
+/// The current credentials the user confirmed and the replacement key.
+pub struct MasterKeyChange {
+    pub current_password: Option<SecretString>,
+    pub current_key_file: Option<String>,
+    pub current_yubikey_slot: Option<u8>,
+    pub new_password: Option<SecretString>,
+    pub new_key_file: Option<String>,
+}
+
+pub fn show(parent: &dyn WxWidget, current_key_file: Option<&str>, current_yubikey_slot: Option<u8>) -> Option<MasterKeyChange> {
+    let dialog = Dialog::builder(parent, "Change master key").with_size(680, 440).build();
+    dialog.set_min_size(Size::new(560, 380));
+    let fields = FlexGridSizer::builder(0, 3).with_vgap(8).with_hgap(8).build();
+    fields.add_growable_col(1, 1);
+
+    let current_password = TextCtrl::builder(&dialog).with_style(TextCtrlStyle::Password).build();
+    let current_key = TextCtrl::builder(&dialog)
+        .with_value(current_key_file.unwrap_or(""))
+        .with_style(TextCtrlStyle::ReadOnly)
+        .build();
+    let current_key_button = Button::builder(&dialog).with_label("Pick key file...").build();
+    let current_yubikey = Choice::builder(&dialog)
+        .with_choices(
+            std::iter::once("None".to_string())
+                .chain(challenge_response::YUBIKEY_SLOTS.iter().map(|slot| format!("YubiKey slot {slot}")))
+                .collect(),
+        )
+        .build();
+    let current_yubikey_index = challenge_response::YUBIKEY_SLOTS
+        .iter()
+        .position(|slot| Some(*slot) == current_yubikey_slot)
+        .map_or(0, |index| index + 1);
+    current_yubikey.set_selection(current_yubikey_index as u32);
+    let new_password = TextCtrl::builder(&dialog).with_style(TextCtrlStyle::Password).build();
+    let repeat_password = TextCtrl::builder(&dialog).with_style(TextCtrlStyle::Password).build();
+    let new_key = TextCtrl::builder(&dialog)
+        .with_value(current_key_file.unwrap_or(""))
+        .with_style(TextCtrlStyle::ReadOnly)
+        .build();
+    let new_key_buttons = BoxSizer::builder(Orientation::Horizontal).build();
+    let new_key_button = Button::builder(&dialog).with_label("Pick key file...").build();
+    let remove_key_button = Button::builder(&dialog).with_label("Remove").build();
+    new_key_buttons.add(&new_key_button, 0, SizerFlag::All, 0);
+    new_key_buttons.add(&remove_key_button, 0, SizerFlag::Left, 4);
+
+    let empty = || StaticText::builder(&dialog).with_label("").build();
+    fields.add(
+        &StaticText::builder(&dialog).with_label("Current password").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    fields.add(&current_password, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    fields.add(&empty(), 0, SizerFlag::All, 4);
+    fields.add(
+        &StaticText::builder(&dialog).with_label("Current key file").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    fields.add(&current_key, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    fields.add(&current_key_button, 0, SizerFlag::All, 4);
+    fields.add(
+        &StaticText::builder(&dialog).with_label("Current challenge-response").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    fields.add(&current_yubikey, 0, SizerFlag::All, 4);
+    fields.add(&empty(), 0, SizerFlag::All, 4);
+    fields.add(
+        &StaticText::builder(&dialog).with_label("New password").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    fields.add(&new_password, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    fields.add(&empty(), 0, SizerFlag::All, 4);
+    fields.add(
+        &StaticText::builder(&dialog).with_label("Repeat password").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    fields.add(&repeat_password, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    fields.add(&empty(), 0, SizerFlag::All, 4);
+    fields.add(
+        &StaticText::builder(&dialog).with_label("New key file").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    fields.add(&new_key, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    fields.add_sizer(&new_key_buttons, 0, SizerFlag::All, 4);
+
+    let root = BoxSizer::builder(Orientation::Vertical).build();
+    root.add_sizer(&fields, 0, SizerFlag::All | SizerFlag::Expand, 12);
+    root.add(
+        &StaticText::builder(&dialog)
+            .with_label("Leave the new password empty or remove the key file to stop using it. At least one must remain.")
+            .build(),
+        0,
+        SizerFlag::All,
+        12,
+    );
+
+    for (button, target) in [(current_key_button, current_key), (new_key_button, new_key)] {
+        button.on_click(move |_| {
+            let key_file_dialog = FileDialog::builder(&target)
+                .with_message("Choose a KeePass key file")
+                .with_style(FileDialogStyle::Open | FileDialogStyle::FileMustExist)
+                .with_wildcard("Key files (*.*)|*.*")
+                .build();
+            if key_file_dialog.show_modal() == wxdragon::ID_OK
+                && let Some(path) = key_file_dialog.get_path()
+            {
+                target.set_value(&path);
+            }
+        });
+    }
+    remove_key_button.on_click(move |_| new_key.set_value(""));
+
+    let actions = BoxSizer::builder(Orientation::Horizontal).build();
+    let spacer = StaticText::builder(&dialog).with_label("").build();
+    let cancel = Button::builder(&dialog).with_id(wxdragon::ID_CANCEL).with_label("Cancel").build();
+    let ok = Button::builder(&dialog).with_label("OK").build();
+    actions.add(&spacer, 1, SizerFlag::Expand, 0);
+    actions.add(&cancel, 0, SizerFlag::All, 4);
+    actions.add(&ok, 0, SizerFlag::All, 4);
+    root.add_sizer(&actions, 0, SizerFlag::All | SizerFlag::Expand, 8);
+    dialog.set_sizer(root, true);
+    dialog.set_escape_id(wxdragon::ID_CANCEL);
+
+    let dialog_for_cancel = dialog;
+    cancel.on_click(move |_| dialog_for_cancel.end_modal(wxdragon::ID_CANCEL));
+    let dialog_for_ok = dialog;
+    ok.on_click(move |_| {
+        let new_value = SecretString::new(new_password.get_value());
+        let problem = if new_value != SecretString::new(repeat_password.get_value()) {
+            Some("The new passwords do not match.")
+        } else if new_value.is_empty() && new_key.get_value().trim().is_empty() {
+            Some("Set a new password, a key file, or both.")
+        } else {
+            None
+        };
+        if let Some(problem) = problem {
+            MessageDialog::builder(&dialog_for_ok, problem, "Change master key")
+                .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconWarning)
+                .build()
+                .show_modal();
+            return;
+        }
+        dialog_for_ok.end_modal(wxdragon::ID_OK);
+    });
+
+    dialog.center();
+    let result = dialog.show_modal();
+    let non_empty = |value: String| (!value.trim().is_empty()).then_some(value);
+    let change = MasterKeyChange {
+        current_password: SecretString::non_empty(current_password.get_value()),
+        current_key_file: non_empty(current_key.get_value()),
+        current_yubikey_slot: current_yubikey
+            .get_selection()
+            .and_then(|selection| (selection as usize).checked_sub(1))
+            .and_then(|index| challenge_response::YUBIKEY_SLOTS.get(index).copied()),
+        new_password: SecretString::non_empty(new_password.get_value()),
+        new_key_file: non_empty(new_key.get_value()),
+    };
+    for control in [current_password, new_password, repeat_password] {
+        control.set_value("");
+    }
+    dialog.destroy();
+    (result == wxdragon::ID_OK).then_some(change)
+}
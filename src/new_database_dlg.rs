@@ -0,0 +1,244 @@
+use crate::secret::SecretString;
+use keepass_ng::{
+    DatabaseConfig,
+    config::{KdfConfig, OuterCipherConfig},
+};
+use wxdragon::{
+    BoxSizer, Button, ButtonEvents, Choice, Dialog, FileDialog, FileDialogStyle, FlexGridSizer, MessageDialog, MessageDialogStyle,
+    Notebook, Orientation, Panel, Size, SizerFlag, StaticText, TextCtrl, TextCtrlStyle, WxWidget,
+};
+
+/// Everything the wizard collected for a brand-new database.
+pub struct NewDatabaseOptions {
+    pub path: String,
+    pub password: Option<SecretString>,
+    pub key_file: Option<String>,
+    pub config: DatabaseConfig,
+}
+
+const CIPHERS: [&str; 3] = ["AES-256", "ChaCha20", "Twofish"];
+const KDFS: [&str; 3] = ["Argon2d", "Argon2id", "AES-KDF"];
+
+pub fn show(parent: &dyn WxWidget) -> Option<NewDatabaseOptions> {
+    let dialog = Dialog::builder(parent, "New KeePass database").with_size(640, 420).build();
+    dialog.set_min_size(Size::new(560, 400));
+    let notebook = Notebook::builder(&dialog).build();
+
+    let credentials_page = Panel::builder(&notebook).build();
+    let credentials_grid = FlexGridSizer::builder(0, 3).with_vgap(8).with_hgap(8).build();
+    credentials_grid.add_growable_col(1, 1);
+    let path = TextCtrl::builder(&credentials_page).build();
+    let path_button = Button::builder(&credentials_page).with_label("Browse...").build();
+    let password = TextCtrl::builder(&credentials_page).with_style(TextCtrlStyle::Password).build();
+    let confirm = TextCtrl::builder(&credentials_page).with_style(TextCtrlStyle::Password).build();
+    let key_file = TextCtrl::builder(&credentials_page).with_style(TextCtrlStyle::ReadOnly).build();
+    let key_file_button = Button::builder(&credentials_page).with_label("Pick key file...").build();
+    for (label, control, button) in [
+        ("Database file", &path, Some(&path_button)),
+        ("Master password", &password, None),
+        ("Repeat password", &confirm, None),
+        ("Key file", &key_file, Some(&key_file_button)),
+    ] {
+        credentials_grid.add(
+            &StaticText::builder(&credentials_page).with_label(label).build(),
+            0,
+            SizerFlag::All,
+            4,
+        );
+        credentials_grid.add(control, 1, SizerFlag::All | SizerFlag::Expand, 4);
+        match button {
+            Some(button) => credentials_grid.add(button, 0, SizerFlag::All, 4),
+            None => credentials_grid.add(&StaticText::builder(&credentials_page).with_label("").build(), 0, SizerFlag::All, 4),
+        }
+    }
+    let credentials_sizer = BoxSizer::builder(Orientation::Vertical).build();
+    credentials_sizer.add_sizer(&credentials_grid, 0, SizerFlag::All | SizerFlag::Expand, 12);
+    credentials_sizer.add(
+        &StaticText::builder(&credentials_page)
+            .with_label("Use a password, a key file, or both. Losing them means losing the database.")
+            .build(),
+        0,
+        SizerFlag::All,
+        12,
+    );
+    credentials_page.set_sizer(credentials_sizer, true);
+
+    let encryption_page = Panel::builder(&notebook).build();
+    let encryption_grid = FlexGridSizer::builder(0, 2).with_vgap(8).with_hgap(8).build();
+    encryption_grid.add_growable_col(1, 1);
+    let cipher = Choice::builder(&encryption_page)
+        .with_choices(CIPHERS.iter().map(|name| name.to_string()).collect())
+        .build();
+    cipher.set_selection(0);
+    let kdf = Choice::builder(&encryption_page)
+        .with_choices(KDFS.iter().map(|name| name.to_string()).collect())
+        .build();
+    kdf.set_selection(0);
+    let iterations = TextCtrl::builder(&encryption_page).with_value("10").build();
+    let memory = TextCtrl::builder(&encryption_page).with_value("64").build();
+    let parallelism = TextCtrl::builder(&encryption_page).with_value("2").build();
+    for (label, control) in [
+        ("Iterations / rounds", &iterations),
+        ("Memory (MiB)", &memory),
+        ("Parallelism", &parallelism),
+    ] {
+        encryption_grid.add(
+            &StaticText::builder(&encryption_page).with_label(label).build(),
+            0,
+            SizerFlag::All,
+            4,
+        );
+        encryption_grid.add(control, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    }
+    encryption_grid.add(
+        &StaticText::builder(&encryption_page).with_label("Cipher").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    encryption_grid.add(&cipher, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    encryption_grid.add(
+        &StaticText::builder(&encryption_page).with_label("Key derivation").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    encryption_grid.add(&kdf, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    encryption_page.set_sizer(encryption_grid, true);
+
+    let memory_for_kdf = memory;
+    let parallelism_for_kdf = parallelism;
+    let iterations_for_kdf = iterations;
+    kdf.on_selection_changed(move |event| {
+        let is_argon2 = event.get_selection().is_some_and(|selection| selection < 2);
+        memory_for_kdf.enable(is_argon2);
+        parallelism_for_kdf.enable(is_argon2);
+        iterations_for_kdf.set_value(if is_argon2 { "10" } else { "100000" });
+    });
+
+    let path_for_picker = path;
+    path_button.on_click(move |_| {
+        let database_dialog = FileDialog::builder(&path_for_picker)
+            .with_message("Save the new KeePass database as")
+            .with_style(FileDialogStyle::Save | FileDialogStyle::OverwritePrompt)
+            .with_wildcard("KeePass database (*.kdbx)|*.kdbx|All files (*.*)|*.*")
+            .build();
+        if database_dialog.show_modal() == wxdragon::ID_OK
+            && let Some(mut selected) = database_dialog.get_path()
+        {
+            if std::path::Path::new(&selected).extension().is_none() {
+                selected.push_str(".kdbx");
+            }
+            path_for_picker.set_value(&selected);
+        }
+    });
+    let key_file_for_picker = key_file;
+    key_file_button.on_click(move |_| {
+        let key_file_dialog = FileDialog::builder(&key_file_for_picker)
+            .with_message("Choose a KeePass key file")
+            .with_style(FileDialogStyle::Open | FileDialogStyle::FileMustExist)
+            .with_wildcard("Key files (*.*)|*.*")
+            .build();
+        if key_file_dialog.show_modal() == wxdragon::ID_OK
+            && let Some(selected) = key_file_dialog.get_path()
+        {
+            key_file_for_picker.set_value(&selected);
+        }
+    });
+
+    notebook.add_page(&credentials_page, "Credentials", true, None);
+    notebook.add_page(&encryption_page, "Encryption", false, None);
+    let root = BoxSizer::builder(Orientation::Vertical).build();
+    root.add(&notebook, 1, SizerFlag::All | SizerFlag::Expand, 8);
+    let actions = BoxSizer::builder(Orientation::Horizontal).build();
+    let spacer = StaticText::builder(&dialog).with_label("").build();
+    let cancel = Button::builder(&dialog).with_id(wxdragon::ID_CANCEL).with_label("Cancel").build();
+    let ok = Button::builder(&dialog).with_label("Create").build();
+    actions.add(&spacer, 1, SizerFlag::Expand, 0);
+    actions.add(&cancel, 0, SizerFlag::All, 4);
+    actions.add(&ok, 0, SizerFlag::All, 4);
+    root.add_sizer(&actions, 0, SizerFlag::All | SizerFlag::Expand, 8);
+    dialog.set_sizer(root, true);
+    dialog.set_escape_id(wxdragon::ID_CANCEL);
+
+    let dialog_for_cancel = dialog;
+    cancel.on_click(move |_| dialog_for_cancel.end_modal(wxdragon::ID_CANCEL));
+    let dialog_for_ok = dialog;
+    ok.on_click(move |_| {
+        let password_value = SecretString::new(password.get_value());
+        let problem = if path.get_value().trim().is_empty() {
+            Some("Choose where to save the new database.")
+        } else if password_value != SecretString::new(confirm.get_value()) {
+            Some("The passwords do not match.")
+        } else if password_value.is_empty() && key_file.get_value().trim().is_empty() {
+            Some("Set a master password, a key file, or both.")
+        } else {
+            None
+        };
+        if let Some(problem) = problem {
+            MessageDialog::builder(&dialog_for_ok, problem, "New database")
+                .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconWarning)
+                .build()
+                .show_modal();
+            return;
+        }
+        dialog_for_ok.end_modal(wxdragon::ID_OK);
+    });
+
+    dialog.center();
+    if dialog.show_modal() != wxdragon::ID_OK {
+        password.set_value("");
+        confirm.set_value("");
+        dialog.destroy();
+        return None;
+    }
+    let iterations = iterations.get_value().trim().parse::<u64>().unwrap_or(10).max(1);
+    let memory = memory.get_value().trim().parse::<u64>().unwrap_or(64).max(1);
+    let parallelism = parallelism.get_value().trim().parse::<u32>().unwrap_or(2).max(1);
+    let mut config = DatabaseConfig::default();
+    config.outer_cipher_config = match cipher.get_selection().unwrap_or(0) {
+        1 => OuterCipherConfig::ChaCha20,
+        2 => OuterCipherConfig::Twofish,
+        _ => OuterCipherConfig::AES256,
+    };
+    config.kdf_config = kdf_config(
+        kdf.get_selection().unwrap_or(0) as usize,
+        iterations,
+        memory * 1024 * 1024,
+        parallelism,
+    );
+    let key_file = key_file.get_value();
+    let options = NewDatabaseOptions {
+        path: path.get_value().trim().to_string(),
+        password: SecretString::non_empty(password.get_value()),
+        key_file: (!key_file.trim().is_empty()).then_some(key_file),
+        config,
+    };
+    password.set_value("");
+    confirm.set_value("");
+    dialog.destroy();
+    Some(options)
+}
+
+fn kdf_config(selection: usize, iterations: u64, memory: u64, parallelism: u32) -> KdfConfig {
+    // The default configuration carries the Argon2 version the library writes, so reuse it.
+    let version = match DatabaseConfig::default().kdf_config {
+        KdfConfig::Argon2 { version, .. } | KdfConfig::Argon2id { version, .. } => Some(version),
+        _ => None,
+    };
+    match (selection, version) {
+        (0, Some(version)) => KdfConfig::Argon2 {
+            iterations,
+            memory,
+            parallelism,
+            version,
+        },
+        (1, Some(version)) => KdfConfig::Argon2id {
+            iterations,
+            memory,
+            parallelism,
+            version,
+        },
+        _ => KdfConfig::Aes { rounds: iterations },
+    }
+}
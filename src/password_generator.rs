@@ -0,0 +1,339 @@
+use crate::error::Result;
+use rand::{Rng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::LazyLock};
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+const LOOK_ALIKES: &str = "Il1|O0o`'\"";
+const WORDLIST: &str = include_str!("../res/wordlist.txt");
+const COMMON_PASSWORDS: &str = include_str!("../res/common-passwords.txt");
+/// Dictionary words shorter than this are not worth looking for.
+const MIN_WORD_LENGTH: usize = 4;
+const MAX_WORD_LENGTH: usize = 20;
+
+/// The passphrase words and common passwords, with the bits it takes to guess one from its list.
+static DICTIONARY: LazyLock<HashMap<String, f64>> = LazyLock::new(|| {
+    let mut dictionary = HashMap::new();
+    for list in [WORDLIST, COMMON_PASSWORDS] {
+        let words = list
+            .lines()
+            .map(str::trim)
+            .filter(|word| word.chars().count() >= MIN_WORD_LENGTH)
+            .collect::<Vec<_>>();
+        let bits = (words.len() as f64).log2();
+        for word in words {
+            let known = dictionary.entry(word.chars().map(unleet).collect::<String>()).or_insert(bits);
+            *known = known.min(bits);
+        }
+    }
+    dictionary
+});
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GeneratorMode {
+    #[default]
+    Characters,
+    Passphrase,
+}
+
+/// A named set of generator options, stored in `Settings`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PasswordProfile {
+    pub name: String,
+    #[serde(default)]
+    pub mode: GeneratorMode,
+    pub length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    #[serde(default)]
+    pub exclude_look_alikes: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub custom_characters: String,
+    pub word_count: usize,
+    pub separator: String,
+}
+
+impl Default for PasswordProfile {
+    fn default() -> Self {
+        Self {
+            name: "Default".to_string(),
+            mode: GeneratorMode::Characters,
+            length: 20,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+            exclude_look_alikes: false,
+            custom_characters: String::new(),
+            word_count: 6,
+            separator: "-".to_string(),
+        }
+    }
+}
+
+impl PasswordProfile {
+    /// The character classes this profile draws from, without duplicates.
+    fn classes(&self) -> Vec<Vec<char>> {
+        let mut seen = Vec::new();
+        let mut classes = Vec::new();
+        for (enabled, class) in [
+            (self.lowercase, LOWERCASE),
+            (self.uppercase, UPPERCASE),
+            (self.digits, DIGITS),
+            (self.symbols, SYMBOLS),
+            (true, self.custom_characters.as_str()),
+        ] {
+            if !enabled {
+                continue;
+            }
+            let class = class
+                .chars()
+                .filter(|c| !(self.exclude_look_alikes && LOOK_ALIKES.contains(*c)))
+                .filter(|c| !seen.contains(c))
+                .collect::<Vec<_>>();
+            seen.extend(&class);
+            if !class.is_empty() {
+                classes.push(class);
+            }
+        }
+        classes
+    }
+
+    pub fn generate(&self) -> Result<String> {
+        let mut rng = rand::rng();
+        if self.mode == GeneratorMode::Passphrase {
+            let words = wordlist();
+            if self.word_count == 0 {
+                return Err("A passphrase needs at least one word".into());
+            }
+            let phrase = (0..self.word_count)
+                .map(|_| words[rng.random_range(0..words.len())])
+                .collect::<Vec<_>>();
+            return Ok(phrase.join(&self.separator));
+        }
+
+        let classes = self.classes();
+        if classes.is_empty() {
+            return Err("Select at least one group of characters".into());
+        }
+        if self.length < classes.len() {
+            return Err(format!("The length must be at least {} to use every selected group", classes.len()).into());
+        }
+        let pool = classes.concat();
+        // One character from every class first, so each selected class is guaranteed to appear.
+        let mut password = classes
+            .iter()
+            .map(|class| class[rng.random_range(0..class.len())])
+            .collect::<Vec<_>>();
+        while password.len() < self.length {
+            password.push(pool[rng.random_range(0..pool.len())]);
+        }
+        password.shuffle(&mut rng);
+        Ok(password.into_iter().collect())
+    }
+
+    /// The entropy in bits of a value produced by this profile.
+    pub fn entropy_bits(&self) -> f64 {
+        match self.mode {
+            GeneratorMode::Passphrase => self.word_count as f64 * (wordlist().len() as f64).log2(),
+            GeneratorMode::Characters => {
+                let pool = self.classes().iter().map(Vec::len).sum::<usize>();
+                if pool == 0 {
+                    0.0
+                } else {
+                    self.length as f64 * (pool as f64).log2()
+                }
+            }
+        }
+    }
+}
+
+fn wordlist() -> Vec<&'static str> {
+    WORDLIST.lines().map(str::trim).filter(|word| !word.is_empty()).collect()
+}
+
+/// Rough entropy of an arbitrary password.
+///
+/// Each character counts by the size of the character classes the password uses, except that dictionary words
+/// and common passwords, also capitalised or with look-alike digits, runs of one character and sequences such as
+/// "abcd" or "4321" count as a single guess.
+pub fn estimate_entropy(password: &str) -> f64 {
+    let chars = password.chars().collect::<Vec<_>>();
+    let character_bits = match character_pool(password) {
+        0 => 0.0,
+        pool => (pool as f64).log2(),
+    };
+    let mut bits = 0.0;
+    let mut index = 0;
+    while index < chars.len() {
+        let (length, token_bits) = dictionary_match(&chars[index..])
+            .or_else(|| run_match(&chars[index..], character_bits))
+            .unwrap_or((1, character_bits));
+        bits += token_bits;
+        index += length;
+    }
+    bits
+}
+
+/// How many characters the classes used by `password` hold.
+fn character_pool(password: &str) -> usize {
+    let mut pool = 0usize;
+    if password.chars().any(|c| c.is_ascii_lowercase()) {
+        pool += LOWERCASE.len();
+    }
+    if password.chars().any(|c| c.is_ascii_uppercase()) {
+        pool += UPPERCASE.len();
+    }
+    if password.chars().any(|c| c.is_ascii_digit()) {
+        pool += DIGITS.len();
+    }
+    if password.chars().any(|c| SYMBOLS.contains(c) || c == ' ') {
+        pool += SYMBOLS.len() + 1;
+    }
+    if !password.is_ascii() {
+        pool += 100;
+    }
+    pool
+}
+
+/// The longest dictionary word at the start of `chars`, with its length and bits.
+fn dictionary_match(chars: &[char]) -> Option<(usize, f64)> {
+    (MIN_WORD_LENGTH..=chars.len().min(MAX_WORD_LENGTH)).rev().find_map(|length| {
+        let candidate = &chars[..length];
+        let bits = DICTIONARY.get(&candidate.iter().copied().map(unleet).collect::<String>())?;
+        // Capitals and look-alike digits each roughly double the guesses.
+        let capitalised = candidate.iter().any(|c| c.is_uppercase());
+        let substituted = candidate.iter().any(|c| !c.is_alphabetic());
+        Some((length, bits + f64::from(u8::from(capitalised)) + f64::from(u8::from(substituted))))
+    })
+}
+
+/// A run of one repeated character or a sequence such as "abcd" or "9876" at the start of `chars`, at least three
+/// characters long, with its length and bits.
+fn run_match(chars: &[char], character_bits: f64) -> Option<(usize, f64)> {
+    let step = |left: char, right: char| i64::from(u32::from(right)) - i64::from(u32::from(left));
+    let first_step = step(*chars.first()?, *chars.get(1)?);
+    if first_step.abs() > 1 {
+        return None;
+    }
+    let length = 1 + chars.windows(2).take_while(|pair| step(pair[0], pair[1]) == first_step).count();
+    if length < 3 {
+        return None;
+    }
+    // Guessing a run takes its first character, its length and, for a sequence, its direction.
+    let direction_bits = if first_step == 0 { 0.0 } else { 1.0 };
+    Some((length, character_bits + (length as f64).log2() + direction_bits))
+}
+
+/// Undoes the usual look-alike substitutions and lower-cases, so "P@55w0rd" is found as "password".
+fn unleet(c: char) -> char {
+    match c {
+        '@' | '4' => 'a',
+        '3' => 'e',
+        '1' | '!' => 'i',
+        '0' => 'o',
+        '$' | '5' => 's',
+        '7' => 't',
+        c => c.to_lowercase().next().unwrap_or(c),
+    }
+}
+
+/// A short human-readable rating for an entropy value.
+pub fn entropy_rating(bits: f64) -> &'static str {
+    if bits < 40.0 {
+        "weak"
+    } else if bits < 64.0 {
+        "fair"
+    } else if bits < 100.0 {
+        "strong"
+    } else {
+        "very strong"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GeneratorMode, LOOK_ALIKES, PasswordProfile, estimate_entropy, wordlist};
+
+    #[test]
+    fn generated_passwords_use_every_selected_class() {
+        let profile = PasswordProfile {
+            length: 8,
+            symbols: false,
+            exclude_look_alikes: true,
+            ..PasswordProfile::default()
+        };
+        for _ in 0..50 {
+            let password = profile.generate().unwrap();
+            assert_eq!(password.chars().count(), 8);
+            assert!(password.chars().any(|c| c.is_ascii_lowercase()));
+            assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+            assert!(password.chars().any(|c| c.is_ascii_digit()));
+            assert!(!password.chars().any(|c| LOOK_ALIKES.contains(c) || !c.is_ascii_alphanumeric()));
+        }
+    }
+
+    #[test]
+    fn custom_characters_and_invalid_profiles() {
+        let profile = PasswordProfile {
+            lowercase: false,
+            uppercase: false,
+            digits: false,
+            symbols: false,
+            custom_characters: "ab".to_string(),
+            length: 12,
+            ..PasswordProfile::default()
+        };
+        assert!(profile.generate().unwrap().chars().all(|c| c == 'a' || c == 'b'));
+        assert!((profile.entropy_bits() - 12.0).abs() < 1e-9);
+
+        let empty = PasswordProfile {
+            custom_characters: String::new(),
+            ..profile
+        };
+        assert!(empty.generate().is_err());
+    }
+
+    #[test]
+    fn passphrases_come_from_the_word_list() {
+        let profile = PasswordProfile {
+            mode: GeneratorMode::Passphrase,
+            word_count: 5,
+            separator: " ".to_string(),
+            ..PasswordProfile::default()
+        };
+        let words = wordlist();
+        assert_eq!(words.len(), 2048);
+        let phrase = profile.generate().unwrap();
+        assert_eq!(phrase.split(' ').count(), 5);
+        assert!(phrase.split(' ').all(|word| words.contains(&word)));
+        assert!((profile.entropy_bits() - 55.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn estimates_grow_with_length_and_variety() {
+        assert_eq!(estimate_entropy(""), 0.0);
+        assert!(estimate_entropy("password") < estimate_entropy("passwordpassword"));
+        assert!(estimate_entropy("password") < estimate_entropy("pa55Word!"));
+    }
+
+    #[test]
+    fn estimates_see_through_common_patterns() {
+        for weak in [
+            "Password1!",
+            "password123456",
+            "aaaaaaaaaa",
+            "P@55w0rd",
+            "qwerty2024",
+            "correct horse",
+        ] {
+            assert!(estimate_entropy(weak) < 40.0, "{weak}: {}", estimate_entropy(weak));
+        }
+        assert!(estimate_entropy("k7#Vq9!mZ2@xL4$w") > 100.0);
+    }
+}
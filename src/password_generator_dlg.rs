@@ -0,0 +1,233 @@
+use crate::{
+    password_generator::{GeneratorMode, PasswordProfile, entropy_rating, estimate_entropy},
+    secret::SecretString,
+    settings::Settings,
+};
+use std::rc::Rc;
+use wxdragon::{
+    BoxSizer, Button, ButtonEvents, CheckBox, Choice, Dialog, FlexGridSizer, MessageDialog, MessageDialogStyle, Orientation, Size,
+    SizerFlag, StaticText, TextCtrl, TextEvents, WxWidget,
+};
+
+/// Shows the generator and returns the accepted password, if any.
+pub fn show(parent: &dyn WxWidget) -> Option<SecretString> {
+    let settings = Settings::shared();
+    let profiles = settings.borrow().password_profiles.clone().unwrap_or_default();
+    let initial = profiles.first().cloned().unwrap_or_default();
+
+    let dialog = Dialog::builder(parent, "Generate password").with_size(620, 520).build();
+    dialog.set_min_size(Size::new(560, 500));
+    let grid = FlexGridSizer::builder(0, 2).with_vgap(6).with_hgap(12).build();
+    grid.add_growable_col(1, 1);
+
+    let mut profile_names = profiles.iter().map(|profile| profile.name.clone()).collect::<Vec<_>>();
+    if profile_names.is_empty() {
+        profile_names.push(initial.name.clone());
+    }
+    let profile_choice = Choice::builder(&dialog).with_choices(profile_names).build();
+    profile_choice.set_selection(0);
+    let profile_name = TextCtrl::builder(&dialog).with_value(&initial.name).build();
+    let mode = Choice::builder(&dialog)
+        .with_choices(vec!["Characters".to_string(), "Passphrase".to_string()])
+        .build();
+    let length = TextCtrl::builder(&dialog).build();
+    let uppercase = CheckBox::builder(&dialog).with_label("Upper case (A-Z)").build();
+    let lowercase = CheckBox::builder(&dialog).with_label("Lower case (a-z)").build();
+    let digits = CheckBox::builder(&dialog).with_label("Digits (0-9)").build();
+    let symbols = CheckBox::builder(&dialog).with_label("Special (!, $, %, ...)").build();
+    let exclude_look_alikes = CheckBox::builder(&dialog)
+        .with_label("Exclude look-alike characters (l, 1, O, 0, ...)")
+        .build();
+    let custom_characters = TextCtrl::builder(&dialog).build();
+    let word_count = TextCtrl::builder(&dialog).build();
+    let separator = TextCtrl::builder(&dialog).build();
+    let output = TextCtrl::builder(&dialog).build();
+    let regenerate = Button::builder(&dialog).with_label("Generate").build();
+    let entropy = StaticText::builder(&dialog).with_label("").build();
+
+    let classes = BoxSizer::builder(Orientation::Vertical).build();
+    for check in [&uppercase, &lowercase, &digits, &symbols, &exclude_look_alikes] {
+        classes.add(check, 0, SizerFlag::All, 2);
+    }
+    let output_controls = BoxSizer::builder(Orientation::Horizontal).build();
+    output_controls.add(&output, 1, SizerFlag::All | SizerFlag::Expand, 0);
+    output_controls.add(&regenerate, 0, SizerFlag::Left, 4);
+    let profile_controls = BoxSizer::builder(Orientation::Horizontal).build();
+    let save_profile = Button::builder(&dialog).with_label("Save profile").build();
+    let delete_profile = Button::builder(&dialog).with_label("Delete profile").build();
+    profile_controls.add(&profile_name, 1, SizerFlag::All | SizerFlag::Expand, 0);
+    profile_controls.add(&save_profile, 0, SizerFlag::Left, 4);
+    profile_controls.add(&delete_profile, 0, SizerFlag::Left, 4);
+
+    let label = |text: &str| StaticText::builder(&dialog).with_label(text).build();
+    grid.add(&label("Profile"), 0, SizerFlag::All, 4);
+    grid.add(&profile_choice, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    grid.add(&label("Profile name"), 0, SizerFlag::All, 4);
+    grid.add_sizer(&profile_controls, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    grid.add(&label("Mode"), 0, SizerFlag::All, 4);
+    grid.add(&mode, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    grid.add(&label("Length"), 0, SizerFlag::All, 4);
+    grid.add(&length, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    grid.add(&label("Characters"), 0, SizerFlag::All, 4);
+    grid.add_sizer(&classes, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    grid.add(&label("Also use"), 0, SizerFlag::All, 4);
+    grid.add(&custom_characters, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    grid.add(&label("Words"), 0, SizerFlag::All, 4);
+    grid.add(&word_count, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    grid.add(&label("Word separator"), 0, SizerFlag::All, 4);
+    grid.add(&separator, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    grid.add(&label("Password"), 0, SizerFlag::All, 4);
+    grid.add_sizer(&output_controls, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    grid.add(&label("Entropy"), 0, SizerFlag::All, 4);
+    grid.add(&entropy, 1, SizerFlag::All | SizerFlag::Expand, 4);
+
+    let apply_profile = Rc::new(move |profile: &PasswordProfile| {
+        profile_name.set_value(&profile.name);
+        mode.set_selection(if profile.mode == GeneratorMode::Passphrase { 1 } else { 0 });
+        length.set_value(&profile.length.to_string());
+        uppercase.set_value(profile.uppercase);
+        lowercase.set_value(profile.lowercase);
+        digits.set_value(profile.digits);
+        symbols.set_value(profile.symbols);
+        exclude_look_alikes.set_value(profile.exclude_look_alikes);
+        custom_characters.set_value(&profile.custom_characters);
+        word_count.set_value(&profile.word_count.to_string());
+        separator.set_value(&profile.separator);
+    });
+    let read_profile = Rc::new(move || PasswordProfile {
+        name: profile_name.get_value().trim().to_string(),
+        mode: if mode.get_selection() == Some(1) {
+            GeneratorMode::Passphrase
+        } else {
+            GeneratorMode::Characters
+        },
+        length: length.get_value().trim().parse().unwrap_or(0),
+        lowercase: lowercase.get_value(),
+        uppercase: uppercase.get_value(),
+        digits: digits.get_value(),
+        symbols: symbols.get_value(),
+        exclude_look_alikes: exclude_look_alikes.get_value(),
+        custom_characters: custom_characters.get_value(),
+        word_count: word_count.get_value().trim().parse().unwrap_or(0),
+        separator: separator.get_value(),
+    });
+    let read_profile_for_generate = Rc::clone(&read_profile);
+    let show_entropy = move |bits: f64| entropy.set_label(&format!("{bits:.0} bits ({})", entropy_rating(bits)));
+    let generate = Rc::new(move || {
+        let profile = read_profile_for_generate();
+        match profile.generate() {
+            Ok(password) => {
+                output.set_value(SecretString::new(password).expose());
+                // Rate what the profile can produce; the output alone overrates passphrases.
+                show_entropy(profile.entropy_bits());
+            }
+            Err(error) => entropy.set_label(&error.to_string()),
+        }
+    });
+    // A password typed over the output can only be rated by what it looks like.
+    output.on_text_updated(move |_| show_entropy(estimate_entropy(SecretString::new(output.get_value()).expose())));
+    apply_profile(&initial);
+    generate();
+
+    let generate_for_click = Rc::clone(&generate);
+    regenerate.on_click(move |_| generate_for_click());
+    let generate_for_mode = Rc::clone(&generate);
+    mode.on_selection_changed(move |_| generate_for_mode());
+    for check in [uppercase, lowercase, digits, symbols, exclude_look_alikes] {
+        let generate = Rc::clone(&generate);
+        check.on_toggled(move |_| generate());
+    }
+    for text in [length, custom_characters, word_count, separator] {
+        let generate = Rc::clone(&generate);
+        text.on_text_updated(move |_| generate());
+    }
+
+    let apply_profile_for_choice = Rc::clone(&apply_profile);
+    let generate_for_choice = Rc::clone(&generate);
+    let settings_for_choice = Rc::clone(&settings);
+    profile_choice.on_selection_changed(move |event| {
+        let Some(selection) = event.get_selection() else {
+            return;
+        };
+        let profile = settings_for_choice
+            .borrow()
+            .password_profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(selection as usize).cloned());
+        if let Some(profile) = profile {
+            apply_profile_for_choice(&profile);
+            generate_for_choice();
+        }
+    });
+    let read_profile_for_save = Rc::clone(&read_profile);
+    let settings_for_save = Rc::clone(&settings);
+    let dialog_for_save = dialog;
+    save_profile.on_click(move |_| {
+        let profile = read_profile_for_save();
+        if profile.name.is_empty() {
+            MessageDialog::builder(&dialog_for_save, "Give the profile a name first.", "Save profile")
+                .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconWarning)
+                .build()
+                .show_modal();
+            return;
+        }
+        let mut settings = settings_for_save.borrow_mut();
+        settings.save_password_profile(profile.clone());
+        settings.save();
+        let names = settings.password_profiles.iter().flatten().map(|profile| profile.name.clone());
+        profile_choice.clear();
+        for name in names {
+            profile_choice.append(&name);
+        }
+        if let Some(index) = settings
+            .password_profiles
+            .iter()
+            .flatten()
+            .position(|existing| existing.name == profile.name)
+        {
+            profile_choice.set_selection(index as _);
+        }
+    });
+    let settings_for_delete = Rc::clone(&settings);
+    delete_profile.on_click(move |_| {
+        let name = profile_name.get_value();
+        let mut settings = settings_for_delete.borrow_mut();
+        if let Some(profiles) = settings.password_profiles.as_mut() {
+            profiles.retain(|profile| profile.name.trim() != name.trim());
+        }
+        settings.save();
+        profile_choice.clear();
+        for profile in settings.password_profiles.iter().flatten() {
+            profile_choice.append(&profile.name);
+        }
+        if let Some(profile) = settings.password_profiles.iter().flatten().next() {
+            profile_choice.set_selection(0);
+            apply_profile(profile);
+        }
+    });
+
+    let root = BoxSizer::builder(Orientation::Vertical).build();
+    root.add_sizer(&grid, 1, SizerFlag::All | SizerFlag::Expand, 12);
+    let actions = BoxSizer::builder(Orientation::Horizontal).build();
+    let spacer = StaticText::builder(&dialog).with_label("").build();
+    let cancel = Button::builder(&dialog).with_id(wxdragon::ID_CANCEL).with_label("Cancel").build();
+    let ok = Button::builder(&dialog).with_label("Use password").build();
+    actions.add(&spacer, 1, SizerFlag::Expand, 0);
+    actions.add(&cancel, 0, SizerFlag::All, 4);
+    actions.add(&ok, 0, SizerFlag::All, 4);
+    root.add_sizer(&actions, 0, SizerFlag::All | SizerFlag::Expand, 8);
+    dialog.set_sizer(root, true);
+    dialog.set_escape_id(wxdragon::ID_CANCEL);
+
+    let dialog_for_cancel = dialog;
+    cancel.on_click(move |_| dialog_for_cancel.end_modal(wxdragon::ID_CANCEL));
+    let dialog_for_ok = dialog;
+    ok.on_click(move |_| dialog_for_ok.end_modal(wxdragon::ID_OK));
+
+    dialog.center();
+    let result = dialog.show_modal();
+    let password = SecretString::non_empty(output.get_value());
+    output.set_value("");
+    dialog.destroy();
+    password.filter(|_| result == wxdragon::ID_OK)
+}
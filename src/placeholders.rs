@@ -0,0 +1,339 @@
+use crate::error::Result;
+use crate::keepass::KpDb;
+use chrono::{Local, Utc};
+use keepass_ng::{
+    Uuid,
+    db::{Entry, NodeIterator, NodePtr, node_is_group},
+};
+use url::Url;
+
+/// Placeholders nested deeper than this are reported like a cycle.
+const MAX_DEPTH: usize = 32;
+
+/// `text` with the placeholders of `entry` resolved; `root` is searched for `{REF:...}` targets.
+pub fn resolve(root: Option<&NodePtr>, entry: &Entry, text: &str) -> Result<String> {
+    Resolver { root, stack: Vec::new() }.text(entry, text)
+}
+
+/// The value of the KDBX field `field` of `entry` with its placeholders resolved.
+pub fn resolve_field(root: Option<&NodePtr>, entry: &Entry, field: &str) -> Result<String> {
+    Resolver { root, stack: Vec::new() }.field(entry, field)
+}
+
+/// Like `resolve_field`, but falls back to the stored value when it cannot be resolved.
+pub fn resolved_field(kpdb: Option<&KpDb>, entry: &Entry, field: &str) -> String {
+    let root = kpdb.and_then(KpDb::get_root);
+    resolve_field(root.as_ref(), entry, field).unwrap_or_else(|error| {
+        log::warn!("Could not resolve the {field} field: {error}");
+        entry.get(field).unwrap_or("").to_string()
+    })
+}
+
+struct Resolver<'a> {
+    root: Option<&'a NodePtr>,
+    /// The entry fields being resolved, outermost first.
+    stack: Vec<(Uuid, String)>,
+}
+
+impl Resolver<'_> {
+    fn field(&mut self, entry: &Entry, field: &str) -> Result<String> {
+        let key = (entry.get_uuid(), field.to_string());
+        if self.stack.contains(&key) || self.stack.len() >= MAX_DEPTH {
+            return Err(format!("The placeholders in the {field} field refer back to it").into());
+        }
+        self.stack.push(key);
+        let value = self.text(entry, entry.get(field).unwrap_or(""));
+        self.stack.pop();
+        value
+    }
+
+    fn text(&mut self, entry: &Entry, text: &str) -> Result<String> {
+        let mut resolved = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('{') {
+            resolved.push_str(&environment(&rest[..start]));
+            let after = &rest[start + 1..];
+            let value = match after.find('}') {
+                Some(end) => self.placeholder(entry, &after[..end])?.map(|value| (value, end)),
+                None => None,
+            };
+            match value {
+                Some((value, end)) => {
+                    resolved.push_str(&value);
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    resolved.push('{');
+                    rest = after;
+                }
+            }
+        }
+        resolved.push_str(&environment(rest));
+        Ok(resolved)
+    }
+
+    /// The value of `{name}`, or `None` for unknown placeholders, which are kept as they are.
+    fn placeholder(&mut self, entry: &Entry, name: &str) -> Result<Option<String>> {
+        let upper = name.to_ascii_uppercase();
+        if let Some(field) = standard_field(&upper) {
+            return self.field(entry, field).map(Some);
+        }
+        if upper == "UUID" {
+            return Ok(Some(hex(entry.get_uuid())));
+        }
+        if let Some(field) = strip_prefix_ignore_case(name, "S:") {
+            if entry.get(field).is_none() {
+                return Ok(None);
+            }
+            return self.field(entry, field).map(Some);
+        }
+        if let Some(part) = upper.strip_prefix("URL:") {
+            let url = self.field(entry, "URL")?;
+            return Ok(url_part(&url, part));
+        }
+        if let Some(reference) = strip_prefix_ignore_case(name, "REF:") {
+            return self.reference(reference);
+        }
+        Ok(date_time(&upper))
+    }
+
+    /// Resolves `W@S:text`: field `W` of the first entry whose field `S` contains `text`, or whose UUID is `text`.
+    fn reference(&mut self, reference: &str) -> Result<Option<String>> {
+        let mut chars = reference.chars();
+        let (Some(wanted), Some('@'), Some(search_in), Some(':')) = (chars.next(), chars.next(), chars.next(), chars.next()) else {
+            return Ok(None);
+        };
+        let Some(target) = self.find(search_in.to_ascii_uppercase(), chars.as_str()) else {
+            return Ok(None);
+        };
+        match wanted.to_ascii_uppercase() {
+            'I' => Ok(Some(hex(target.get_uuid()))),
+            code => match reference_field(code) {
+                Some(field) => self.field(&target, field).map(Some),
+                None => Ok(None),
+            },
+        }
+    }
+
+    fn find(&self, search_in: char, text: &str) -> Option<Entry> {
+        let needle = text.to_lowercase();
+        let matches = |entry: &Entry| match search_in {
+            'I' => Uuid::parse_str(text).is_ok_and(|uuid| uuid == entry.get_uuid()),
+            'O' => entry
+                .fields
+                .keys()
+                .filter(|name| standard_field(&name.to_ascii_uppercase()).is_none())
+                .any(|name| contains(entry.get(name), &needle)),
+            code => reference_field(code).is_some_and(|field| contains(entry.get(field), &needle)),
+        };
+        NodeIterator::new(self.root?)
+            .filter(|node| !node_is_group(node))
+            .find_map(|node| node.borrow().downcast_ref::<Entry>().filter(|entry| matches(entry)).cloned())
+    }
+}
+
+fn standard_field(upper: &str) -> Option<&'static str> {
+    match upper {
+        "TITLE" => Some("Title"),
+        "USERNAME" => Some("UserName"),
+        "PASSWORD" => Some("Password"),
+        "URL" => Some("URL"),
+        "NOTES" => Some("Notes"),
+        _ => None,
+    }
+}
+
+/// The field a one-letter reference code stands for.
+fn reference_field(code: char) -> Option<&'static str> {
+    match code {
+        'T' => Some("Title"),
+        'U' => Some("UserName"),
+        'P' => Some("Password"),
+        'A' => Some("URL"),
+        'N' => Some("Notes"),
+        _ => None,
+    }
+}
+
+fn contains(value: Option<&str>, needle: &str) -> bool {
+    value.is_some_and(|value| value.to_lowercase().contains(needle))
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    text.get(..prefix.len())
+        .filter(|start| start.eq_ignore_ascii_case(prefix))
+        .map(|_| &text[prefix.len()..])
+}
+
+/// UUIDs are written the way KeePass writes them in references: 32 upper-case hex digits.
+fn hex(uuid: Uuid) -> String {
+    uuid.simple().to_string().to_uppercase()
+}
+
+fn url_part(url: &str, part: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok();
+    let value = match part {
+        "RMVSCM" => match url.split_once("://") {
+            Some((_, rest)) => rest.to_string(),
+            None => url.to_string(),
+        },
+        "SCM" => parsed.map(|url| url.scheme().to_string()).unwrap_or_default(),
+        "HOST" => parsed.and_then(|url| url.host_str().map(str::to_string)).unwrap_or_default(),
+        "PORT" => parsed
+            .and_then(|url| url.port_or_known_default())
+            .map(|port| port.to_string())
+            .unwrap_or_default(),
+        "PATH" => parsed.map(|url| url.path().to_string()).unwrap_or_default(),
+        "QUERY" => parsed
+            .and_then(|url| url.query().map(|query| format!("?{query}")))
+            .unwrap_or_default(),
+        "USERINFO" => parsed
+            .filter(|url| !url.username().is_empty())
+            .map(|url| match url.password() {
+                Some(password) => format!("{}:{password}", url.username()),
+                None => url.username().to_string(),
+            })
+            .unwrap_or_default(),
+        "USERNAME" => parsed.map(|url| url.username().to_string()).unwrap_or_default(),
+        "PASSWORD" => parsed.and_then(|url| url.password().map(str::to_string)).unwrap_or_default(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// `{DT_...}` in local time and `{DT_UTC_...}` in UTC.
+fn date_time(upper: &str) -> Option<String> {
+    let (utc, name) = match upper.strip_prefix("DT_UTC_") {
+        Some(name) => (true, name),
+        None => (false, upper.strip_prefix("DT_")?),
+    };
+    let format = match name {
+        "SIMPLE" => "%Y%m%d%H%M%S",
+        "YEAR" => "%Y",
+        "MONTH" => "%m",
+        "DAY" => "%d",
+        "HOUR" => "%H",
+        "MINUTE" => "%M",
+        "SECOND" => "%S",
+        _ => return None,
+    };
+    Some(if utc {
+        Utc::now().format(format).to_string()
+    } else {
+        Local::now().format(format).to_string()
+    })
+}
+
+/// Replaces `%NAME%` with environment variable `NAME`; unknown names are kept as they are.
+fn environment(text: &str) -> String {
+    let mut expanded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('%') {
+        let after = &rest[start + 1..];
+        let variable = after
+            .find('%')
+            .map(|end| &after[..end])
+            .filter(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '(' | ')')))
+            .and_then(|name| std::env::var(name).ok().map(|value| (name.len(), value)));
+        match variable {
+            Some((length, value)) => {
+                expanded.push_str(&rest[..start]);
+                expanded.push_str(&value);
+                rest = &after[length + 1..];
+            }
+            None => {
+                expanded.push_str(&rest[..=start]);
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{hex, resolve, resolve_field};
+    use crate::keepass::KpDb;
+    use keepass_ng::db::{Entry, Value, with_node, with_node_mut};
+
+    #[test]
+    fn resolves_entry_placeholders_and_references() {
+        let mut kpdb = KpDb::new();
+        let root_node = kpdb.get_root().unwrap();
+        let root = root_node.borrow().get_uuid();
+        let database = kpdb.create_new_entry(root).unwrap();
+        with_node_mut::<Entry, _, _>(&database, |entry| {
+            entry.set_title(Some("Database"));
+            entry.set_username(Some("dba"));
+            entry.set_password(Some("s3cret"));
+            entry.set_url(Some("https://admin:pw@db.example.com:8443/console?tab=1"));
+            entry.fields.insert("Region".to_string(), Value::Unprotected("eu".to_string()));
+        });
+        let database_uuid = database.borrow().get_uuid();
+        let copy = kpdb.create_new_entry(root).unwrap();
+        with_node_mut::<Entry, _, _>(&copy, |entry| {
+            entry.set_title(Some("Replica"));
+            entry.set_username(Some(&format!("{{REF:U@I:{}}}", hex(database_uuid))));
+            entry.set_password(Some("{REF:P@T:database}"));
+            entry.set_notes(Some("{USERNAME} in {S:Region} {unknown} {REF:P@T:nothing}"));
+        });
+
+        let database = with_node::<Entry, _, _>(&database, Entry::clone).unwrap();
+        let copy = with_node::<Entry, _, _>(&copy, Entry::clone).unwrap();
+        let root = Some(&root_node);
+        assert_eq!(resolve_field(root, &copy, "UserName").unwrap(), "dba");
+        assert_eq!(resolve_field(root, &copy, "Password").unwrap(), "s3cret");
+        assert_eq!(
+            resolve_field(root, &copy, "Notes").unwrap(),
+            "dba in {S:Region} {unknown} {REF:P@T:nothing}"
+        );
+        assert_eq!(
+            resolve(root, &database, "{URL:SCM} {URL:HOST}:{URL:PORT}{URL:PATH}{URL:QUERY}").unwrap(),
+            "https db.example.com:8443/console?tab=1"
+        );
+        assert_eq!(
+            resolve(root, &database, "{S:Region} {url:rmvscm} {URL:USERINFO} {TiTlE}").unwrap(),
+            "eu admin:pw@db.example.com:8443/console?tab=1 admin:pw Database"
+        );
+        assert_eq!(resolve(root, &database, "{DT_YEAR}").unwrap().len(), 4);
+        assert_eq!(resolve(root, &database, "{DT_UTC_SIMPLE}").unwrap().len(), 14);
+    }
+
+    #[test]
+    fn expands_environment_variables() {
+        let entry = Entry::default();
+        let path = std::env::var("PATH").unwrap();
+        assert_eq!(resolve(None, &entry, "%PATH%").unwrap(), path);
+        assert_eq!(resolve(None, &entry, "100% sure, 50%").unwrap(), "100% sure, 50%");
+        assert_eq!(
+            resolve(None, &entry, "%NO_SUCH_VARIABLE_FOR_MYPASS%").unwrap(),
+            "%NO_SUCH_VARIABLE_FOR_MYPASS%"
+        );
+    }
+
+    #[test]
+    fn detects_reference_cycles() {
+        let mut kpdb = KpDb::new();
+        let root_node = kpdb.get_root().unwrap();
+        let root = root_node.borrow().get_uuid();
+        let first = kpdb.create_new_entry(root).unwrap();
+        let second = kpdb.create_new_entry(root).unwrap();
+        let first_uuid = first.borrow().get_uuid();
+        let second_uuid = second.borrow().get_uuid();
+        with_node_mut::<Entry, _, _>(&first, |entry| {
+            entry.set_password(Some(&format!("{{REF:P@I:{}}}", hex(second_uuid))));
+            entry.set_username(Some("{PASSWORD}"));
+            entry.set_notes(Some("{NOTES}"));
+        });
+        with_node_mut::<Entry, _, _>(&second, |entry| {
+            entry.set_password(Some(&format!("{{REF:U@I:{}}}", hex(first_uuid))));
+        });
+
+        let first = with_node::<Entry, _, _>(&first, Entry::clone).unwrap();
+        let root = Some(&root_node);
+        assert!(resolve_field(root, &first, "Password").is_err());
+        assert!(resolve_field(root, &first, "Notes").is_err());
+        assert!(resolve(root, &first, "{TITLE}").is_ok());
+    }
+}
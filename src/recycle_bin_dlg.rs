@@ -0,0 +1,66 @@
+use keepass_ng::Uuid;
+use wxdragon::{BoxSizer, Button, ButtonEvents, CheckBox, Choice, Dialog, FlexGridSizer, Orientation, SizerFlag, StaticText, WxWidget};
+
+/// The recycle bin settings the user confirmed.
+pub struct RecycleBinSettings {
+    pub enabled: bool,
+    /// `None` lets the next delete create a "Recycle Bin" group.
+    pub group: Option<Uuid>,
+}
+
+/// `groups` are the candidate groups with their paths; `current` is what the database uses now.
+pub fn show(parent: &dyn WxWidget, groups: &[(Uuid, String)], enabled: bool, current: Option<Uuid>) -> Option<RecycleBinSettings> {
+    let dialog = Dialog::builder(parent, "Recycle bin").with_size(520, 220).build();
+    let grid = FlexGridSizer::builder(0, 2).with_vgap(8).with_hgap(8).build();
+    grid.add_growable_col(1, 1);
+
+    let use_bin = CheckBox::builder(&dialog)
+        .with_label("Move deleted entries and groups to a recycle bin")
+        .with_value(enabled)
+        .build();
+    let mut choices = vec!["(create when needed)".to_string()];
+    choices.extend(groups.iter().map(|(_, path)| path.clone()));
+    let group = Choice::builder(&dialog).with_choices(choices).build();
+    let selected = current
+        .and_then(|current| groups.iter().position(|(uuid, _)| *uuid == current))
+        .map_or(0, |index| index + 1);
+    group.set_selection(selected as u32);
+    group.enable(enabled);
+    use_bin.on_toggled(move |event| group.enable(event.is_checked()));
+
+    grid.add(&StaticText::builder(&dialog).with_label("").build(), 0, SizerFlag::All, 4);
+    grid.add(&use_bin, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    grid.add(&StaticText::builder(&dialog).with_label("Bin group").build(), 0, SizerFlag::All, 4);
+    grid.add(&group, 1, SizerFlag::All | SizerFlag::Expand, 4);
+
+    let root = BoxSizer::builder(Orientation::Vertical).build();
+    root.add_sizer(&grid, 1, SizerFlag::All | SizerFlag::Expand, 12);
+    let actions = BoxSizer::builder(Orientation::Horizontal).build();
+    let spacer = StaticText::builder(&dialog).with_label("").build();
+    let cancel = Button::builder(&dialog).with_id(wxdragon::ID_CANCEL).with_label("Cancel").build();
+    let ok = Button::builder(&dialog).with_label("OK").build();
+    actions.add(&spacer, 1, SizerFlag::Expand, 0);
+    actions.add(&cancel, 0, SizerFlag::All, 4);
+    actions.add(&ok, 0, SizerFlag::All, 4);
+    root.add_sizer(&actions, 0, SizerFlag::All | SizerFlag::Expand, 8);
+    dialog.set_sizer(root, true);
+    dialog.set_escape_id(wxdragon::ID_CANCEL);
+
+    let dialog_for_cancel = dialog;
+    cancel.on_click(move |_| dialog_for_cancel.end_modal(wxdragon::ID_CANCEL));
+    let dialog_for_ok = dialog;
+    ok.on_click(move |_| dialog_for_ok.end_modal(wxdragon::ID_OK));
+
+    dialog.center();
+    let result = dialog.show_modal();
+    let settings = RecycleBinSettings {
+        enabled: use_bin.get_value(),
+        group: group
+            .get_selection()
+            .and_then(|selection| (selection as usize).checked_sub(1))
+            .and_then(|index| groups.get(index))
+            .map(|(uuid, _)| *uuid),
+    };
+    dialog.destroy();
+    (result == wxdragon::ID_OK).then_some(settings)
+}
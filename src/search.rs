@@ -0,0 +1,121 @@
+use crate::error::Result;
+use keepass_ng::db::{Entry, NodeIterator, NodePtr, node_is_group};
+use regex::{Regex, RegexBuilder};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub case_sensitive: bool,
+    pub regex: bool,
+}
+
+/// A compiled search query; plain queries are treated as literal text.
+pub struct Matcher {
+    regex: Regex,
+}
+
+impl Matcher {
+    pub fn new(query: &str, options: SearchOptions) -> Result<Self> {
+        let pattern = if options.regex { query.to_string() } else { regex::escape(query) };
+        let regex = RegexBuilder::new(&pattern).case_insensitive(!options.case_sensitive).build()?;
+        Ok(Self { regex })
+    }
+
+    pub fn is_match(&self, value: &str) -> bool {
+        self.regex.is_match(value)
+    }
+
+    /// Matches title, username, URL, notes, tags and custom attributes of an entry.
+    pub fn matches_entry(&self, entry: &Entry) -> bool {
+        [entry.get_title(), entry.get_username(), entry.get_url(), entry.get_notes()]
+            .into_iter()
+            .flatten()
+            .any(|value| self.is_match(value))
+            || entry.get_tags().iter().any(|tag| self.is_match(tag))
+            || entry
+                .additional_attributes()
+                .iter()
+                .any(|(name, value)| self.is_match(name.as_ref()) || self.is_match(value.as_ref()))
+    }
+}
+
+/// Walks the whole tree below `root` and returns every entry that matches the query.
+pub fn search_entries(root: &NodePtr, query: &str, options: SearchOptions) -> Result<Vec<NodePtr>> {
+    let matcher = Matcher::new(query, options)?;
+    Ok(NodeIterator::new(root)
+        .filter(|node| !node_is_group(node))
+        .filter(|node| {
+            node.borrow()
+                .downcast_ref::<Entry>()
+                .is_some_and(|entry| matcher.matches_entry(entry))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Matcher, SearchOptions, search_entries};
+    use crate::keepass::KpDb;
+    use keepass_ng::db::{Entry, with_node_mut};
+
+    #[test]
+    fn plain_queries_are_literal_and_case_insensitive_by_default() {
+        let matcher = Matcher::new("a.c", SearchOptions::default()).unwrap();
+        assert!(matcher.is_match("xA.Cx"));
+        assert!(!matcher.is_match("abc"));
+
+        let matcher = Matcher::new(
+            "a.c",
+            SearchOptions {
+                case_sensitive: true,
+                regex: true,
+            },
+        )
+        .unwrap();
+        assert!(matcher.is_match("abc"));
+        assert!(!matcher.is_match("ABC"));
+        assert!(
+            Matcher::new(
+                "(",
+                SearchOptions {
+                    case_sensitive: false,
+                    regex: true
+                }
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn finds_entries_by_any_field() {
+        let mut kpdb = KpDb::new();
+        let root = kpdb.get_root().unwrap();
+        let root_uuid = root.borrow().get_uuid();
+        let group = kpdb.create_new_group(root_uuid).unwrap();
+        let entry = kpdb.create_new_entry(group.borrow().get_uuid()).unwrap();
+        with_node_mut::<Entry, _, _>(&entry, |entry| {
+            entry.set_title(Some("Mail"));
+            entry.set_username(Some("alice"));
+            entry.get_tags_mut().push("work".to_string());
+        });
+        let other = kpdb.create_new_entry(root_uuid).unwrap();
+        with_node_mut::<Entry, _, _>(&other, |entry| entry.set_title(Some("Bank")));
+
+        let found = search_entries(&root, "ALICE", SearchOptions::default()).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].borrow().get_uuid(), entry.borrow().get_uuid());
+        assert_eq!(search_entries(&root, "work", SearchOptions::default()).unwrap().len(), 1);
+        assert_eq!(
+            search_entries(
+                &root,
+                "^(Mail|Bank)$",
+                SearchOptions {
+                    case_sensitive: true,
+                    regex: true
+                }
+            )
+            .unwrap()
+            .len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{entry_view, find_tree_item, group_view::list_image_index, keepass::KpDb, node_title, placeholders};
+use keepass_ng::{
+    Uuid,
+    db::{Entry, NodePtr},
+};
+use std::{cell::RefCell, rc::Rc};
+use wxdragon::{
+    BoxSizer, Frame, HasItemData, ImageList, ListColumnFormat, ListCtrl, ListCtrlStyle, Orientation, Panel, SizerFlag, StaticText,
+    StatusBar, TreeCtrl, WxWidget, image_list_type,
+};
+
+pub fn build_search_view(
+    parent: &Panel,
+    frame: Frame,
+    query: &str,
+    results: &[NodePtr],
+    tree: &TreeCtrl,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+    status_bar: &StatusBar,
+) {
+    let sizer = BoxSizer::builder(Orientation::Vertical).build();
+    let title = StaticText::builder(parent)
+        .with_label(&format!("{} entries match \"{query}\"", results.len()))
+        .build();
+    sizer.add(&title, 0, SizerFlag::All | SizerFlag::Expand, 12);
+
+    let list = ListCtrl::builder(parent)
+        .with_style(ListCtrlStyle::Report | ListCtrlStyle::SingleSel | ListCtrlStyle::HRules | ListCtrlStyle::VRules)
+        .build();
+    let image_list = ImageList::new(20, 20, false, 0);
+    let row_icons = results
+        .iter()
+        .map(|node| list_image_index(&image_list, node, kpdb))
+        .collect::<Vec<_>>();
+    list.set_image_list(image_list, image_list_type::SMALL);
+    list.insert_column(0, "Title", ListColumnFormat::Left, 180);
+    list.insert_column(1, "Username", ListColumnFormat::Left, 140);
+    list.insert_column(2, "URL", ListColumnFormat::Left, 200);
+    list.insert_column(3, "Group", ListColumnFormat::Left, -1);
+    for (index, node) in results.iter().enumerate() {
+        let row = index as i64;
+        if list.insert_item(row, &node_title(node), row_icons[index]) < 0 {
+            continue;
+        }
+        list.set_custom_data(row as u64, node.borrow().get_uuid());
+        if let Some(entry) = node.borrow().downcast_ref::<Entry>() {
+            let resolved = |field: &str| placeholders::resolved_field(kpdb.borrow().as_ref(), entry, field);
+            list.set_item_text_by_column(row, 1, &resolved("UserName"));
+            list.set_item_text_by_column(row, 2, &resolved("URL"));
+        }
+        let group_path = kpdb
+            .borrow()
+            .as_ref()
+            .map(|db| db.get_group_path(node).join(" / "))
+            .unwrap_or_default();
+        list.set_item_text_by_column(row, 3, &group_path);
+    }
+    sizer.add(&list, 1, SizerFlag::All | SizerFlag::Expand, 4);
+
+    let details = Panel::builder(parent).build();
+    sizer.add(&details, 2, SizerFlag::All | SizerFlag::Expand, 0);
+    let current_result = Rc::new(RefCell::new(None::<Panel>));
+
+    let tree_for_activation = *tree;
+    let kpdb_for_activation = Rc::clone(kpdb);
+    let status_bar_for_activation = *status_bar;
+    list.on_item_activated(move |event| {
+        let row = event.get_item_index();
+        if row < 0 {
+            return;
+        }
+        let Some(data) = list.get_custom_data(row as u64) else {
+            return;
+        };
+        let Some(uuid) = data.downcast_ref::<Uuid>() else {
+            return;
+        };
+        show_entry_details(frame, &details, &current_result, *uuid, &tree_for_activation, &kpdb_for_activation);
+        status_bar_for_activation.set_status_text("Search result selected", 0);
+    });
+
+    parent.set_sizer(sizer, true);
+}
+
+/// Shows entry `uuid` in `details`, replacing the entry shown before, and scrolls the tree to it.
+pub(crate) fn show_entry_details(
+    frame: Frame,
+    details: &Panel,
+    current_result: &Rc<RefCell<Option<Panel>>>,
+    uuid: Uuid,
+    tree: &TreeCtrl,
+    kpdb: &Rc<RefCell<Option<KpDb>>>,
+) {
+    let Some(node) = kpdb.borrow().as_ref().and_then(|db| db.get_node_by_id(uuid)) else {
+        return;
+    };
+    if let Some(root_item) = tree.get_root_item()
+        && let Some(tree_item) = find_tree_item(tree, &root_item, uuid)
+    {
+        tree.ensure_visible(&tree_item);
+    }
+    if let Some(old_view) = current_result.borrow_mut().take() {
+        old_view.destroy();
+    }
+    let details_for_refresh = *details;
+    let current_result_for_refresh = Rc::clone(current_result);
+    let tree_for_refresh = *tree;
+    let kpdb_for_refresh = Rc::clone(kpdb);
+    let refresh = Rc::new(move || {
+        show_entry_details(
+            frame,
+            &details_for_refresh,
+            &current_result_for_refresh,
+            uuid,
+            &tree_for_refresh,
+            &kpdb_for_refresh,
+        )
+    });
+    let new_view = Panel::builder(details).build();
+    entry_view::build_entry_view(&new_view, frame, &node, refresh, Rc::clone(kpdb));
+    let details_sizer = BoxSizer::builder(Orientation::Vertical).build();
+    details_sizer.add(&new_view, 1, SizerFlag::All | SizerFlag::Expand, 0);
+    details.set_sizer(details_sizer, true);
+    details.layout();
+    *current_result.borrow_mut() = Some(new_view);
+}
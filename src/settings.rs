@@ -1,8 +1,18 @@
+use crate::password_generator::PasswordProfile;
+use crate::secret::SecretString;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
 
 pub const MAX_RECENT_FILES: usize = 10;
+pub const DEFAULT_CLIPBOARD_CLEAR_SECONDS: u64 = 12;
+pub const DEFAULT_IDLE_LOCK_SECONDS: u64 = 300;
+pub const DEFAULT_AUTOTYPE_HOTKEY: &str = "Ctrl+Alt+A";
+pub const DEFAULT_PASSWORD_MAX_AGE_DAYS: u64 = 365;
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub enum ProxyProtocol {
@@ -20,7 +30,7 @@ pub struct ProxySettings {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub username: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub password: Option<String>,
+    pub password: Option<SecretString>,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -37,9 +47,35 @@ pub struct Settings {
     pub recent_files: Option<Vec<String>>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub proxy: Option<ProxySettings>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_profiles: Option<Vec<PasswordProfile>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clipboard_clear_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_lock_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock_on_hide: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autotype_hotkey: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_max_age_days: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breach_list_path: Option<String>,
+    /// The YubiKey slot each recent database was opened with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yubikey_slots: Option<BTreeMap<String, u8>>,
+}
+
+thread_local! {
+    static SHARED: Rc<RefCell<Settings>> = Rc::new(RefCell::new(Settings::load()));
 }
 
 impl Settings {
+    /// The settings instance shared by the GUI thread, loaded from disk on first use.
+    pub fn shared() -> Rc<RefCell<Settings>> {
+        SHARED.with(Rc::clone)
+    }
+
     fn path() -> Option<PathBuf> {
         dirs::config_dir().map(|path| path.join("mypass").join("settings.json"))
     }
@@ -82,11 +118,65 @@ impl Settings {
         recent_files.insert(0, path);
         recent_files.truncate(MAX_RECENT_FILES);
     }
+
+    /// The YubiKey slot `path` was last opened with.
+    pub fn yubikey_slot(&self, path: &str) -> Option<u8> {
+        self.yubikey_slots.as_ref()?.get(path).copied()
+    }
+
+    /// Remembers the YubiKey slot of `path`, forgetting the ones of databases no longer in the recent files.
+    pub fn remember_yubikey_slot(&mut self, path: &str, slot: Option<u8>) {
+        let recent_files = self.recent_files.clone().unwrap_or_default();
+        let slots = self.yubikey_slots.get_or_insert_with(BTreeMap::new);
+        slots.retain(|known, _| known == path || recent_files.contains(known));
+        match slot {
+            Some(slot) => slots.insert(path.to_string(), slot),
+            None => slots.remove(path),
+        };
+        if slots.is_empty() {
+            self.yubikey_slots = None;
+        }
+    }
+
+    /// How long copied values stay on the clipboard; `None` means they are never cleared.
+    pub fn clipboard_timeout(&self) -> Option<Duration> {
+        let seconds = self.clipboard_clear_seconds.unwrap_or(DEFAULT_CLIPBOARD_CLEAR_SECONDS);
+        (seconds > 0).then(|| Duration::from_secs(seconds))
+    }
+
+    /// How long the application may sit idle before the database is locked; `None` disables the idle lock.
+    pub fn idle_lock_timeout(&self) -> Option<Duration> {
+        let seconds = self.idle_lock_seconds.unwrap_or(DEFAULT_IDLE_LOCK_SECONDS);
+        (seconds > 0).then(|| Duration::from_secs(seconds))
+    }
+
+    /// The global hotkey that starts Auto-Type; an empty setting turns it off.
+    pub fn autotype_hotkey(&self) -> Option<&str> {
+        let hotkey = self.autotype_hotkey.as_deref().unwrap_or(DEFAULT_AUTOTYPE_HOTKEY).trim();
+        (!hotkey.is_empty()).then_some(hotkey)
+    }
+
+    /// After how many days the health report calls a password old; `None` turns the check off.
+    pub fn password_max_age_days(&self) -> Option<u64> {
+        let days = self.password_max_age_days.unwrap_or(DEFAULT_PASSWORD_MAX_AGE_DAYS);
+        (days > 0).then_some(days)
+    }
+
+    /// Adds a password generator profile, replacing any existing profile with the same name.
+    pub fn save_password_profile(&mut self, profile: PasswordProfile) {
+        let profiles = self.password_profiles.get_or_insert_with(Vec::new);
+        match profiles.iter_mut().find(|existing| existing.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => profiles.push(profile),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{MAX_RECENT_FILES, Settings};
+    use super::{DEFAULT_CLIPBOARD_CLEAR_SECONDS, MAX_RECENT_FILES, Settings};
+    use crate::password_generator::PasswordProfile;
+    use std::time::Duration;
 
     #[test]
     fn recent_files_are_unique_and_limited() {
@@ -101,4 +191,41 @@ mod tests {
         assert_eq!(recent_files[0], "file-5");
         assert!(!recent_files.contains(&"file-0".to_string()));
     }
+
+    #[test]
+    fn yubikey_slots_are_remembered_for_recent_files() {
+        let mut settings = Settings::default();
+        settings.remember_yubikey_slot("team.kdbx", Some(2));
+        settings.add_recent_file("team.kdbx");
+        settings.remember_yubikey_slot("old.kdbx", Some(1));
+        assert_eq!(settings.yubikey_slot("team.kdbx"), Some(2));
+        settings.remember_yubikey_slot("new.kdbx", None);
+        assert_eq!(settings.yubikey_slot("old.kdbx"), None);
+        settings.remember_yubikey_slot("team.kdbx", None);
+        assert_eq!(settings.yubikey_slots, None);
+    }
+
+    #[test]
+    fn password_profiles_are_replaced_by_name() {
+        let mut settings = Settings::default();
+        settings.save_password_profile(PasswordProfile::default());
+        settings.save_password_profile(PasswordProfile {
+            length: 32,
+            ..PasswordProfile::default()
+        });
+        let profiles = settings.password_profiles.as_ref().expect("profiles should exist");
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].length, 32);
+    }
+
+    #[test]
+    fn clipboard_timeout_defaults_and_zero_disables() {
+        let mut settings = Settings::default();
+        assert_eq!(
+            settings.clipboard_timeout(),
+            Some(Duration::from_secs(DEFAULT_CLIPBOARD_CLEAR_SECONDS))
+        );
+        settings.clipboard_clear_seconds = Some(0);
+        assert_eq!(settings.clipboard_timeout(), None);
+    }
 }
@@ -1,14 +1,77 @@
-use crate::settings::{ProxyProtocol, ProxySettings, Settings};
+use crate::secret::SecretString;
+use crate::settings::{
+    DEFAULT_AUTOTYPE_HOTKEY, DEFAULT_CLIPBOARD_CLEAR_SECONDS, DEFAULT_IDLE_LOCK_SECONDS, DEFAULT_PASSWORD_MAX_AGE_DAYS, ProxyProtocol,
+    ProxySettings, Settings,
+};
 use wxdragon::{
-    BoxSizer, ButtonEvents, Choice, Dialog, FlexGridSizer, Notebook, Orientation, Panel, SizerFlag, StaticText, TextCtrl, TextCtrlStyle,
-    WxWidget,
+    BoxSizer, ButtonEvents, CheckBox, Choice, Dialog, FlexGridSizer, Notebook, Orientation, Panel, SizerFlag, StaticText, TextCtrl,
+    TextCtrlStyle, WxWidget,
 };
 
 pub fn show(parent: &dyn WxWidget, settings: &mut Settings) -> bool {
-    let dialog = Dialog::builder(parent, "Settings").with_size(520, 360).build();
+    let dialog = Dialog::builder(parent, "Settings").with_size(520, 440).build();
     let notebook = Notebook::builder(&dialog).build();
     let general_page = Panel::builder(&notebook).build();
-    general_page.set_sizer(BoxSizer::builder(Orientation::Vertical).build(), true);
+    let general_grid = FlexGridSizer::builder(0, 2).with_vgap(8).with_hgap(8).build();
+    general_grid.add_growable_col(1, 1);
+    let clipboard_seconds = TextCtrl::builder(&general_page)
+        .with_value(
+            &settings
+                .clipboard_clear_seconds
+                .unwrap_or(DEFAULT_CLIPBOARD_CLEAR_SECONDS)
+                .to_string(),
+        )
+        .build();
+    clipboard_seconds.set_tooltip("Use 0 to keep copied values on the clipboard");
+    general_grid.add(
+        &StaticText::builder(&general_page)
+            .with_label("Clear clipboard after (seconds)")
+            .build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    general_grid.add(&clipboard_seconds, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    let idle_lock_seconds = TextCtrl::builder(&general_page)
+        .with_value(&settings.idle_lock_seconds.unwrap_or(DEFAULT_IDLE_LOCK_SECONDS).to_string())
+        .build();
+    idle_lock_seconds.set_tooltip("Use 0 to never lock because of inactivity");
+    general_grid.add(
+        &StaticText::builder(&general_page).with_label("Lock after idle (seconds)").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    general_grid.add(&idle_lock_seconds, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    let lock_on_hide = CheckBox::builder(&general_page)
+        .with_label("Lock when minimized or hidden to the tray")
+        .with_value(settings.lock_on_hide.unwrap_or(true))
+        .build();
+    general_grid.add(&StaticText::builder(&general_page).with_label("").build(), 0, SizerFlag::All, 4);
+    general_grid.add(&lock_on_hide, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    let autotype_hotkey = TextCtrl::builder(&general_page)
+        .with_value(settings.autotype_hotkey.as_deref().unwrap_or(DEFAULT_AUTOTYPE_HOTKEY))
+        .build();
+    autotype_hotkey.set_tooltip("For example Ctrl+Alt+A; leave empty to turn it off. Takes effect after a restart");
+    general_grid.add(
+        &StaticText::builder(&general_page).with_label("Auto-Type hotkey").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    general_grid.add(&autotype_hotkey, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    let password_max_age = TextCtrl::builder(&general_page)
+        .with_value(&settings.password_max_age_days.unwrap_or(DEFAULT_PASSWORD_MAX_AGE_DAYS).to_string())
+        .build();
+    password_max_age.set_tooltip("The health report lists passwords older than this; use 0 to skip the check");
+    general_grid.add(
+        &StaticText::builder(&general_page).with_label("Password max age (days)").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    general_grid.add(&password_max_age, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    general_page.set_sizer(general_grid, true);
     notebook.add_page(&general_page, "General", true, None);
 
     let proxy_page = Panel::builder(&notebook).build();
@@ -30,7 +93,7 @@ pub fn show(parent: &dyn WxWidget, settings: &mut Settings) -> bool {
         .with_value(proxy.username.as_deref().unwrap_or(""))
         .build();
     let password = TextCtrl::builder(&proxy_page)
-        .with_value(proxy.password.as_deref().unwrap_or(""))
+        .with_value(proxy.password.as_ref().map(SecretString::expose).unwrap_or(""))
         .with_style(TextCtrlStyle::Password)
         .build();
     proxy_grid.add(
@@ -101,8 +164,13 @@ pub fn show(parent: &dyn WxWidget, settings: &mut Settings) -> bool {
         host: host.get_value(),
         port: port.get_value(),
         username: (!username.get_value().is_empty()).then_some(username.get_value()),
-        password: (!password.get_value().is_empty()).then_some(password.get_value()),
+        password: SecretString::non_empty(password.get_value()),
     });
+    settings.clipboard_clear_seconds = clipboard_seconds.get_value().trim().parse().ok();
+    settings.idle_lock_seconds = idle_lock_seconds.get_value().trim().parse().ok();
+    settings.lock_on_hide = Some(lock_on_hide.get_value());
+    settings.autotype_hotkey = Some(autotype_hotkey.get_value().trim().to_string());
+    settings.password_max_age_days = password_max_age.get_value().trim().parse().ok();
     settings.save();
     dialog.destroy();
     true
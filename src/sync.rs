@@ -0,0 +1,164 @@
+use crate::{error::Result, keepass::KpDb};
+use keepass_ng::{
+    Uuid,
+    db::{MergeEventType, NodeIterator, NodePtr, node_is_group},
+};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Updated,
+    Moved,
+    Deleted,
+}
+
+impl ChangeKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "Added",
+            ChangeKind::Updated => "Updated",
+            ChangeKind::Moved => "Moved",
+            ChangeKind::Deleted => "Deleted",
+        }
+    }
+}
+
+/// One node that a merge added, changed, moved or removed in the target database.
+#[derive(Clone, Debug)]
+pub struct MergeChange {
+    pub kind: ChangeKind,
+    pub is_group: bool,
+    pub uuid: Uuid,
+    pub title: String,
+    /// Group path of the node, joined with " / ".
+    pub path: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MergeSummary {
+    pub changes: Vec<MergeChange>,
+    pub warnings: Vec<String>,
+}
+
+impl MergeSummary {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn count(&self, kind: ChangeKind) -> usize {
+        self.changes.iter().filter(|change| change.kind == kind).count()
+    }
+
+    /// Appends the result of a later merge into the same target.
+    pub fn extend(&mut self, other: MergeSummary) {
+        self.changes.extend(other.changes);
+        self.warnings.extend(other.warnings);
+    }
+
+    /// A one-line count such as "2 added, 1 updated, 0 moved, 0 deleted".
+    pub fn describe(&self) -> String {
+        [ChangeKind::Added, ChangeKind::Updated, ChangeKind::Moved, ChangeKind::Deleted]
+            .iter()
+            .map(|kind| format!("{} {}", self.count(*kind), kind.label().to_lowercase()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Merges `source` into `target` and lists what changed in `target`.
+pub fn merge(target: &mut KpDb, source: &KpDb) -> Result<MergeSummary> {
+    // Deleted nodes are gone after the merge, so remember how they were called before it.
+    let before = describe_nodes(target);
+    let source_db = source.db.as_ref().ok_or("No database")?;
+    let target_db = target.db.as_mut().ok_or("No database")?;
+    let log = target_db.merge(source_db).map_err(|error| error.to_string())?;
+
+    let changes = log
+        .events
+        .iter()
+        .map(|event| {
+            let (kind, is_group) = match event.event_type {
+                MergeEventType::EntryCreated => (ChangeKind::Added, false),
+                MergeEventType::EntryUpdated => (ChangeKind::Updated, false),
+                MergeEventType::EntryLocationUpdated => (ChangeKind::Moved, false),
+                MergeEventType::EntryDeleted => (ChangeKind::Deleted, false),
+                MergeEventType::GroupCreated => (ChangeKind::Added, true),
+                MergeEventType::GroupUpdated => (ChangeKind::Updated, true),
+                MergeEventType::GroupLocationUpdated => (ChangeKind::Moved, true),
+                MergeEventType::GroupDeleted => (ChangeKind::Deleted, true),
+            };
+            let (title, path) = target
+                .get_node_by_id(event.node_uuid)
+                .map(|node| node_description(target, &node))
+                .or_else(|| before.get(&event.node_uuid).cloned())
+                .unwrap_or_else(|| (event.node_uuid.to_string(), String::new()));
+            MergeChange {
+                kind,
+                is_group,
+                uuid: event.node_uuid,
+                title,
+                path,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if !changes.is_empty() {
+        target.mark_data_changed();
+    }
+    Ok(MergeSummary {
+        changes,
+        warnings: log.warnings,
+    })
+}
+
+fn describe_nodes(kpdb: &KpDb) -> HashMap<Uuid, (String, String)> {
+    let Some(root) = kpdb.get_root() else {
+        return HashMap::new();
+    };
+    NodeIterator::new(&root)
+        .map(|node| (node.borrow().get_uuid(), node_description(kpdb, &node)))
+        .collect()
+}
+
+fn node_description(kpdb: &KpDb, node: &NodePtr) -> (String, String) {
+    let title = node.borrow().get_title().unwrap_or("").to_string();
+    let title = if title.trim().is_empty() {
+        if node_is_group(node) { "(unnamed group)" } else { "(no title)" }.to_string()
+    } else {
+        title
+    };
+    (title, kpdb.get_group_path(node).join(" / "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChangeKind, merge};
+    use crate::keepass::KpDb;
+    use keepass_ng::db::{Entry, Group, with_node_mut};
+
+    #[test]
+    fn reports_entries_added_by_the_other_database() {
+        let mut target = KpDb::new();
+        target.password = Some("secret".into());
+        let root = target.get_root().unwrap().borrow().get_uuid();
+        let group = target.create_new_group(root).unwrap();
+        with_node_mut::<Group, _, _>(&group, |group| group.set_title(Some("Deploy")));
+        let group = group.borrow().get_uuid();
+
+        let mut other = target.deep_copy().unwrap();
+        let entry = other.create_new_entry(group).unwrap();
+        with_node_mut::<Entry, _, _>(&entry, |entry| entry.set_title(Some("Registry")));
+        let entry = entry.borrow().get_uuid();
+
+        let summary = merge(&mut target, &other).unwrap();
+        assert_eq!(summary.count(ChangeKind::Added), 1);
+        assert_eq!(summary.describe(), "1 added, 0 updated, 0 moved, 0 deleted");
+        let change = &summary.changes[0];
+        assert_eq!((change.uuid, change.is_group), (entry, false));
+        assert_eq!((change.title.as_str(), change.path.as_str()), ("Registry", "Deploy"));
+        assert!(target.get_node_by_id(entry).is_some());
+
+        assert!(merge(&mut target, &other).unwrap().is_empty());
+    }
+}
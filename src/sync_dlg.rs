@@ -0,0 +1,75 @@
+use crate::sync::MergeSummary;
+use wxdragon::{
+    BoxSizer, Button, ButtonEvents, Dialog, ListColumnFormat, ListCtrl, ListCtrlStyle, Orientation, Size, SizerFlag, StaticText, TextCtrl,
+    TextCtrlStyle, WxWidget,
+};
+
+/// Lists the changes of a synchronization; true when the user wants the merged database saved.
+pub fn confirm(parent: &dyn WxWidget, source_name: &str, summary: &MergeSummary) -> bool {
+    let dialog = Dialog::builder(parent, "Synchronize with file").with_size(720, 480).build();
+    dialog.set_min_size(Size::new(560, 360));
+
+    let root = BoxSizer::builder(Orientation::Vertical).build();
+    root.add(
+        &StaticText::builder(&dialog)
+            .with_label(&format!("Merging {source_name}: {}.", summary.describe()))
+            .build(),
+        0,
+        SizerFlag::All,
+        12,
+    );
+
+    let changes = ListCtrl::builder(&dialog)
+        .with_style(ListCtrlStyle::Report | ListCtrlStyle::SingleSel | ListCtrlStyle::VRules | ListCtrlStyle::HRules)
+        .build();
+    changes.insert_column(0, "Change", ListColumnFormat::Left, 90);
+    changes.insert_column(1, "Type", ListColumnFormat::Left, 70);
+    changes.insert_column(2, "Title", ListColumnFormat::Left, 220);
+    changes.insert_column(3, "Group", ListColumnFormat::Left, -1);
+    for (row, change) in summary.changes.iter().enumerate() {
+        let row = row as i64;
+        if changes.insert_item(row, change.kind.label(), None) < 0 {
+            continue;
+        }
+        changes.set_item_text_by_column(row, 1, if change.is_group { "Group" } else { "Entry" });
+        changes.set_item_text_by_column(row, 2, &change.title);
+        changes.set_item_text_by_column(row, 3, &change.path);
+    }
+    root.add(&changes, 1, SizerFlag::All | SizerFlag::Expand, 12);
+
+    if !summary.warnings.is_empty() {
+        let warnings = TextCtrl::builder(&dialog)
+            .with_value(&summary.warnings.join("\n"))
+            .with_style(TextCtrlStyle::MultiLine | TextCtrlStyle::ReadOnly)
+            .build();
+        warnings.set_min_size(Size::new(-1, 80));
+        root.add(
+            &StaticText::builder(&dialog).with_label("Warnings").build(),
+            0,
+            SizerFlag::Left | SizerFlag::Right,
+            12,
+        );
+        root.add(&warnings, 0, SizerFlag::All | SizerFlag::Expand, 12);
+    }
+
+    let actions = BoxSizer::builder(Orientation::Horizontal).build();
+    let spacer = StaticText::builder(&dialog).with_label("").build();
+    let cancel = Button::builder(&dialog).with_id(wxdragon::ID_CANCEL).with_label("Cancel").build();
+    let save = Button::builder(&dialog).with_label("Save merged database").build();
+    actions.add(&spacer, 1, SizerFlag::Expand, 0);
+    actions.add(&cancel, 0, SizerFlag::All, 4);
+    actions.add(&save, 0, SizerFlag::All, 4);
+    root.add_sizer(&actions, 0, SizerFlag::All | SizerFlag::Expand, 8);
+    dialog.set_sizer(root, true);
+    dialog.set_escape_id(wxdragon::ID_CANCEL);
+
+    let dialog_for_cancel = dialog;
+    cancel.on_click(move |_| dialog_for_cancel.end_modal(wxdragon::ID_CANCEL));
+    let dialog_for_save = dialog;
+    save.on_click(move |_| dialog_for_save.end_modal(wxdragon::ID_OK));
+
+    dialog.center();
+    let result = dialog.show_modal();
+    dialog.destroy();
+    result == wxdragon::ID_OK
+}
@@ -0,0 +1,509 @@
+use crate::error::Result;
+use hmac::{Hmac, Mac, digest::KeyInit};
+use keepass_ng::db::{Entry, Value};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+
+/// The KeePassXC field holding an `otpauth://` URI.
+pub const OTP_FIELD: &str = "otp";
+/// The Base32 secret of KeePassXC's legacy TOTP fields.
+pub const LEGACY_SEED_FIELD: &str = "TOTP Seed";
+/// `period;digits` of KeePassXC's legacy TOTP fields, where the digits may be `S` for Steam codes.
+pub const LEGACY_SETTINGS_FIELD: &str = "TOTP Settings";
+
+pub const DEFAULT_PERIOD: u64 = 30;
+pub const DEFAULT_DIGITS: u32 = 6;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const STEAM_ALPHABET: &[u8; 26] = b"23456789BCDFGHJKMNPQRTVWXY";
+const STEAM_DIGITS: u32 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 3] = [Algorithm::Sha1, Algorithm::Sha256, Algorithm::Sha512];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::Sha1 => "SHA1",
+            Algorithm::Sha256 => "SHA256",
+            Algorithm::Sha512 => "SHA512",
+        }
+    }
+
+    fn parse(name: &str) -> Result<Self> {
+        match name.to_uppercase().replace('-', "").as_str() {
+            "SHA1" => Ok(Algorithm::Sha1),
+            "SHA256" => Ok(Algorithm::Sha256),
+            "SHA512" => Ok(Algorithm::Sha512),
+            _ => Err(format!("Unsupported OTP algorithm {name}").into()),
+        }
+    }
+}
+
+/// Whether codes change with time or with a counter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Totp { period: u64 },
+    Hotp { counter: u64 },
+}
+
+/// One-time password settings of an entry.
+#[derive(Clone)]
+pub struct Otp {
+    secret: Zeroizing<Vec<u8>>,
+    pub algorithm: Algorithm,
+    pub digits: u32,
+    pub kind: Kind,
+    /// Steam Guard codes: five characters from Steam's alphabet instead of decimal digits.
+    pub steam: bool,
+    pub issuer: String,
+    pub account: String,
+}
+
+impl Otp {
+    /// TOTP with the usual settings for the Base32 `secret`.
+    pub fn new(secret: &str) -> Result<Self> {
+        let secret = base32_decode(secret)?;
+        if secret.is_empty() {
+            return Err("The OTP secret is empty".into());
+        }
+        Ok(Self {
+            secret: Zeroizing::new(secret),
+            algorithm: Algorithm::Sha1,
+            digits: DEFAULT_DIGITS,
+            kind: Kind::Totp { period: DEFAULT_PERIOD },
+            steam: false,
+            issuer: String::new(),
+            account: String::new(),
+        })
+    }
+
+    /// Reads an `otpauth://` URI, a KeeOTP `key=...&step=...` string or a bare Base32 secret.
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        if text.starts_with("otpauth://") {
+            Self::parse_uri(text)
+        } else if text.starts_with("key=") || text.contains("&key=") {
+            Self::parse_keeotp(text)
+        } else {
+            Self::new(text)
+        }
+    }
+
+    fn parse_uri(uri: &str) -> Result<Self> {
+        let url = url::Url::parse(uri)?;
+        let mut otp = None;
+        let mut kind = match url.host_str().map(str::to_lowercase).as_deref() {
+            Some("totp") => Kind::Totp { period: DEFAULT_PERIOD },
+            Some("hotp") => Kind::Hotp { counter: 0 },
+            _ => return Err("Only totp and hotp otpauth URIs are supported".into()),
+        };
+        let (mut algorithm, mut digits, mut steam, mut issuer) = (Algorithm::Sha1, DEFAULT_DIGITS, false, String::new());
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "secret" => otp = Some(Self::new(&value)?),
+                "algorithm" => algorithm = Algorithm::parse(&value)?,
+                "digits" => digits = parse_number(&value, "digits")?,
+                "period" => {
+                    kind = Kind::Totp {
+                        period: parse_number(&value, "period")?,
+                    }
+                }
+                "counter" => {
+                    kind = Kind::Hotp {
+                        counter: parse_number(&value, "counter")?,
+                    }
+                }
+                "issuer" => issuer = value.into_owned(),
+                "encoder" => steam = value.eq_ignore_ascii_case("steam"),
+                _ => {}
+            }
+        }
+        let mut otp = otp.ok_or("The otpauth URI has no secret")?;
+        // The label is "issuer:account" or just the account.
+        let label = percent_decode(url.path().trim_start_matches('/'));
+        let (label_issuer, account) = label.split_once(':').unwrap_or(("", label.as_str()));
+        if issuer.is_empty() {
+            issuer = label_issuer.trim().to_string();
+        }
+        otp.account = account.trim().to_string();
+        otp.issuer = issuer;
+        otp.algorithm = algorithm;
+        otp.kind = kind;
+        otp.steam = steam;
+        otp.digits = if steam { STEAM_DIGITS } else { digits };
+        otp.validate()
+    }
+
+    fn parse_keeotp(text: &str) -> Result<Self> {
+        let mut otp = None;
+        let (mut algorithm, mut digits, mut period, mut counter, mut hotp) = (Algorithm::Sha1, DEFAULT_DIGITS, DEFAULT_PERIOD, 0, false);
+        for (key, value) in url::form_urlencoded::parse(text.as_bytes()) {
+            match key.as_ref() {
+                "key" => otp = Some(Self::new(&value)?),
+                "step" => period = parse_number(&value, "step")?,
+                "size" => digits = parse_number(&value, "size")?,
+                "otpHashMode" => algorithm = Algorithm::parse(&value)?,
+                "type" => hotp = value.eq_ignore_ascii_case("hotp"),
+                "counter" => counter = parse_number(&value, "counter")?,
+                _ => {}
+            }
+        }
+        let mut otp = otp.ok_or("The OTP settings have no key")?;
+        otp.algorithm = algorithm;
+        otp.digits = digits;
+        otp.kind = if hotp { Kind::Hotp { counter } } else { Kind::Totp { period } };
+        otp.validate()
+    }
+
+    /// Reads KeePassXC's legacy `TOTP Seed` and `TOTP Settings` fields.
+    pub fn from_legacy(seed: &str, settings: Option<&str>) -> Result<Self> {
+        let mut otp = Self::new(seed)?;
+        let Some(settings) = settings.filter(|settings| !settings.trim().is_empty()) else {
+            return Ok(otp);
+        };
+        let mut parts = settings.split(';').map(str::trim);
+        if let Some(period) = parts.next() {
+            otp.kind = Kind::Totp {
+                period: parse_number(period, "period")?,
+            };
+        }
+        match parts.next() {
+            Some("S") => otp.set_steam(),
+            Some(digits) => otp.digits = parse_number(digits, "digits")?,
+            None => {}
+        }
+        if let Some(algorithm) = parts.next() {
+            otp.algorithm = Algorithm::parse(algorithm)?;
+        }
+        otp.validate()
+    }
+
+    /// The OTP settings stored with `entry`, if it has any.
+    pub fn from_entry(entry: &Entry) -> Option<Result<Self>> {
+        if let Some(otp) = entry.get(OTP_FIELD).filter(|otp| !otp.trim().is_empty()) {
+            return Some(Self::parse(otp));
+        }
+        let seed = entry.get(LEGACY_SEED_FIELD).filter(|seed| !seed.trim().is_empty())?;
+        Some(Self::from_legacy(seed, entry.get(LEGACY_SETTINGS_FIELD)))
+    }
+
+    fn validate(self) -> Result<Self> {
+        if !(1..=10).contains(&self.digits) {
+            return Err(format!("{} digits are not supported", self.digits).into());
+        }
+        if self.kind == (Kind::Totp { period: 0 }) {
+            return Err("The TOTP period must be at least one second".into());
+        }
+        Ok(self)
+    }
+
+    /// Switches to Steam Guard codes.
+    pub fn set_steam(&mut self) {
+        self.steam = true;
+        self.digits = STEAM_DIGITS;
+    }
+
+    /// The code at `unix_time`; HOTP codes ignore the time and use the counter.
+    pub fn code_at(&self, unix_time: u64) -> String {
+        let counter = match self.kind {
+            Kind::Totp { period } => unix_time / period,
+            Kind::Hotp { counter } => counter,
+        };
+        let mut value = truncate(&sign(self.algorithm, &self.secret, &counter.to_be_bytes()));
+        if self.steam {
+            (0..STEAM_DIGITS)
+                .map(|_| {
+                    let c = STEAM_ALPHABET[value as usize % STEAM_ALPHABET.len()];
+                    value /= STEAM_ALPHABET.len() as u32;
+                    c as char
+                })
+                .collect()
+        } else {
+            let code = u64::from(value) % 10u64.pow(self.digits);
+            format!("{code:0width$}", width = self.digits as usize)
+        }
+    }
+
+    pub fn code(&self) -> String {
+        self.code_at(unix_now())
+    }
+
+    /// Seconds until the TOTP code at `unix_time` changes; `None` for HOTP.
+    pub fn remaining_at(&self, unix_time: u64) -> Option<u64> {
+        match self.kind {
+            Kind::Totp { period } => Some(period - unix_time % period),
+            Kind::Hotp { .. } => None,
+        }
+    }
+
+    /// Moves an HOTP counter on to the next code; TOTP settings are left alone.
+    pub fn advance(&mut self) {
+        if let Kind::Hotp { counter } = &mut self.kind {
+            *counter += 1;
+        }
+    }
+
+    pub fn secret_base32(&self) -> String {
+        base32_encode(&self.secret)
+    }
+
+    /// The `otpauth://` URI KeePassXC stores in the `otp` field.
+    pub fn to_uri(&self) -> String {
+        let encode = |text: &str| url::form_urlencoded::byte_serialize(text.as_bytes()).collect::<String>();
+        let (kind, kind_parameter) = match self.kind {
+            Kind::Totp { period } => ("totp", format!("period={period}")),
+            Kind::Hotp { counter } => ("hotp", format!("counter={counter}")),
+        };
+        let label = match (self.issuer.as_str(), self.account.as_str()) {
+            ("", account) => encode(account),
+            (issuer, account) => format!("{}:{}", encode(issuer), encode(account)),
+        };
+        let mut uri = format!(
+            "otpauth://{kind}/{label}?secret={}&{kind_parameter}&digits={}&algorithm={}",
+            self.secret_base32(),
+            self.digits,
+            self.algorithm.name()
+        );
+        if !self.issuer.is_empty() {
+            uri.push_str(&format!("&issuer={}", encode(&self.issuer)));
+        }
+        if self.steam {
+            uri.push_str("&encoder=steam");
+        }
+        uri
+    }
+}
+
+/// The code to enter now for `entry`, and whether its stored HOTP counter moved on to the next code.
+pub fn take_code(entry: &mut Entry) -> Result<(String, bool)> {
+    let mut otp = Otp::from_entry(entry).ok_or("The entry has no one-time password")??;
+    let code = otp.code();
+    if matches!(otp.kind, Kind::Totp { .. }) {
+        return Ok((code, false));
+    }
+    otp.advance();
+    entry
+        .fields
+        .insert(OTP_FIELD.to_string(), Value::Protected(otp.to_uri().as_bytes().into()));
+    Ok((code, true))
+}
+
+/// `code` split in two halves for reading, like `123 456`.
+pub fn display_code(code: &str) -> String {
+    if code.len() < 6 || !code.chars().all(|c| c.is_ascii_digit()) {
+        return code.to_string();
+    }
+    let (left, right) = code.split_at(code.len() / 2);
+    format!("{left} {right}")
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, name: &str) -> Result<T> {
+    value.trim().parse().map_err(|_| format!("Invalid OTP {name}: {value}").into())
+}
+
+fn sign(algorithm: Algorithm, key: &[u8], message: &[u8]) -> Vec<u8> {
+    fn mac<M: Mac + KeyInit>(key: &[u8], message: &[u8]) -> Vec<u8> {
+        let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC takes keys of any length");
+        mac.update(message);
+        mac.finalize().into_bytes().to_vec()
+    }
+    match algorithm {
+        Algorithm::Sha1 => mac::<Hmac<Sha1>>(key, message),
+        Algorithm::Sha256 => mac::<Hmac<Sha256>>(key, message),
+        Algorithm::Sha512 => mac::<Hmac<Sha512>>(key, message),
+    }
+}
+
+/// The dynamic truncation of RFC 4226: 31 bits taken at the offset named by the last nibble.
+fn truncate(digest: &[u8]) -> u32 {
+    let offset = usize::from(digest[digest.len() - 1] & 0x0f);
+    u32::from_be_bytes([digest[offset], digest[offset + 1], digest[offset + 2], digest[offset + 3]]) & 0x7fff_ffff
+}
+
+/// Decodes RFC 4648 Base32, ignoring case, padding, spaces and dashes.
+pub fn base32_decode(text: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in text.chars().filter(|c| !matches!(c, ' ' | '-' | '=')) {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|letter| *letter as char == c.to_ascii_uppercase())
+            .ok_or_else(|| format!("'{c}' is not a Base32 character"))?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Ok(bytes)
+}
+
+/// Encodes `bytes` as unpadded RFC 4648 Base32.
+pub fn base32_encode(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for byte in bytes {
+        buffer = (buffer << 8) | u32::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            text.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+        buffer &= (1 << bits) - 1;
+    }
+    if bits > 0 {
+        text.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    text
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Algorithm, Kind, OTP_FIELD, Otp, base32_decode, base32_encode, display_code, take_code};
+    use crate::keepass::KpDb;
+    use keepass_ng::db::{Entry, Node, Value, with_node_mut};
+
+    /// The shared secrets of RFC 6238 appendix B, one per hash.
+    const RFC_SECRETS: [(Algorithm, &[u8]); 3] = [
+        (Algorithm::Sha1, b"12345678901234567890"),
+        (Algorithm::Sha256, b"12345678901234567890123456789012"),
+        (
+            Algorithm::Sha512,
+            b"1234567890123456789012345678901234567890123456789012345678901234",
+        ),
+    ];
+
+    fn rfc_otp(algorithm: Algorithm, secret: &[u8]) -> Otp {
+        let uri = format!(
+            "otpauth://totp/RFC%206238:tester?secret={}&algorithm={}&digits=8&period=30",
+            base32_encode(secret),
+            algorithm.name()
+        );
+        Otp::parse(&uri).unwrap()
+    }
+
+    #[test]
+    fn matches_rfc_6238_test_vectors() {
+        let vectors: [(u64, [&str; 3]); 6] = [
+            (59, ["94287082", "46119246", "90693936"]),
+            (1111111109, ["07081804", "68084774", "25091201"]),
+            (1111111111, ["14050471", "67062674", "99943326"]),
+            (1234567890, ["89005924", "91819424", "93441116"]),
+            (2000000000, ["69279037", "90698825", "38618901"]),
+            (20000000000, ["65353130", "77737706", "47863826"]),
+        ];
+        for (time, codes) in vectors {
+            for ((algorithm, secret), code) in RFC_SECRETS.iter().zip(codes) {
+                assert_eq!(rfc_otp(*algorithm, secret).code_at(time), code, "{algorithm:?} at {time}");
+            }
+        }
+        let otp = rfc_otp(Algorithm::Sha1, RFC_SECRETS[0].1);
+        assert_eq!((otp.issuer.as_str(), otp.account.as_str()), ("RFC 6238", "tester"));
+        assert_eq!(otp.remaining_at(59), Some(1));
+    }
+
+    #[test]
+    fn matches_rfc_4226_hotp_values() {
+        let mut otp = Otp::new(&base32_encode(b"12345678901234567890")).unwrap();
+        otp.kind = Kind::Hotp { counter: 0 };
+        let codes = (0..10)
+            .map(|_| {
+                let code = otp.code_at(0);
+                otp.advance();
+                code
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            codes,
+            [
+                "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583", "399871", "520489"
+            ]
+        );
+        assert!(otp.to_uri().contains("counter=10"));
+    }
+
+    #[test]
+    fn taking_hotp_codes_moves_the_counter_on() {
+        let mut kpdb = KpDb::new();
+        let root = kpdb.get_root().unwrap().borrow().get_uuid();
+        let entry = kpdb.create_new_entry(root).unwrap();
+        let uri = format!("otpauth://hotp/Test?secret={}&counter=0", base32_encode(b"12345678901234567890"));
+        let codes = with_node_mut::<Entry, _, _>(&entry, |entry| {
+            entry.fields.insert(OTP_FIELD.to_string(), Value::Unprotected(uri));
+            [take_code(entry).unwrap(), take_code(entry).unwrap()]
+        })
+        .unwrap();
+        assert_eq!(codes, [("755224".to_string(), true), ("287082".to_string(), true)]);
+        assert_eq!(display_code(&codes[0].0), "755 224");
+        assert_eq!(display_code("8WQ2K"), "8WQ2K");
+    }
+
+    #[test]
+    fn reads_legacy_and_keeotp_settings() {
+        let otp = Otp::from_legacy("JBSW Y3DP EHPK 3PXP", Some("60;8")).unwrap();
+        assert_eq!((otp.kind, otp.digits), (Kind::Totp { period: 60 }, 8));
+        let steam = Otp::from_legacy("JBSWY3DPEHPK3PXP", Some("30;S")).unwrap();
+        assert!(steam.steam);
+        assert_eq!(steam.code_at(59).len(), 5);
+        let keeotp = Otp::parse("key=JBSWY3DPEHPK3PXP&step=45&size=7&otpHashMode=Sha256").unwrap();
+        assert_eq!(
+            (keeotp.kind, keeotp.digits, keeotp.algorithm),
+            (Kind::Totp { period: 45 }, 7, Algorithm::Sha256)
+        );
+
+        let round_trip = Otp::parse(&steam.to_uri()).unwrap();
+        assert_eq!(round_trip.code_at(1234567890), steam.code_at(1234567890));
+        assert!(Otp::parse("not base32!").is_err());
+        assert!(Otp::parse("otpauth://totp/x?secret=JBSWY3DP&period=0").is_err());
+    }
+
+    #[test]
+    fn round_trips_base32() {
+        for length in 0..12 {
+            let bytes = (0..length).map(|byte| byte * 37).collect::<Vec<u8>>();
+            assert_eq!(base32_decode(&base32_encode(&bytes)).unwrap(), bytes);
+        }
+        assert_eq!(base32_decode("mzxw6===").unwrap(), b"foo");
+    }
+}
@@ -0,0 +1,163 @@
+use crate::error::Result;
+use crate::totp::{Algorithm, DEFAULT_PERIOD, Kind, Otp};
+use std::{cell::Cell, rc::Rc};
+use wxdragon::{
+    BoxSizer, Button, ButtonEvents, Choice, Dialog, FlexGridSizer, MessageDialog, MessageDialogStyle, Orientation, SizerFlag, StaticText,
+    TextCtrl, WxWidget,
+};
+
+const DIGIT_CHOICES: [&str; 4] = ["6", "7", "8", "Steam (5 characters)"];
+
+/// Sets up the one-time password of an entry from a secret or an `otpauth://` URI.
+///
+/// `current` is the stored `otp` value; `title` and `username` label new URIs for authenticator apps.
+/// Returns `Some(None)` when the user removed the one-time password and `None` when they cancelled.
+pub fn show(parent: &dyn WxWidget, current: Option<&str>, title: &str, username: &str) -> Option<Option<String>> {
+    let dialog = Dialog::builder(parent, "Set up TOTP").with_size(560, 280).build();
+    let current_otp = current.and_then(|current| Otp::parse(current).ok());
+    // The stored value goes back in as it is, so OK keeps its kind, counter, digits and issuer.
+    let secret = TextCtrl::builder(&dialog).with_value(current.unwrap_or("")).build();
+    secret.set_tooltip("The Base32 key shown by the website, or the otpauth:// URI behind its QR code");
+    let algorithm = Choice::builder(&dialog)
+        .with_choices(Algorithm::ALL.iter().map(|algorithm| algorithm.name().to_string()).collect())
+        .build();
+    let digits = Choice::builder(&dialog)
+        .with_choices(DIGIT_CHOICES.iter().map(|choice| choice.to_string()).collect())
+        .build();
+    let period = TextCtrl::builder(&dialog).build();
+    let (algorithm_index, digits_index, period_value) = match &current_otp {
+        Some(otp) => (
+            Algorithm::ALL.iter().position(|algorithm| *algorithm == otp.algorithm).unwrap_or(0),
+            if otp.steam {
+                3
+            } else {
+                DIGIT_CHOICES
+                    .iter()
+                    .position(|choice| *choice == otp.digits.to_string())
+                    .unwrap_or(0)
+            },
+            match otp.kind {
+                Kind::Totp { period } => period,
+                Kind::Hotp { .. } => DEFAULT_PERIOD,
+            },
+        ),
+        None => (0, 0, DEFAULT_PERIOD),
+    };
+    algorithm.set_selection(algorithm_index as u32);
+    digits.set_selection(digits_index as u32);
+    period.set_value(&period_value.to_string());
+    let enable_settings = move || {
+        let uses_uri = is_uri(&secret.get_value());
+        algorithm.enable(!uses_uri);
+        digits.enable(!uses_uri);
+        period.enable(!uses_uri);
+    };
+    enable_settings();
+    secret.on_text_updated(move |_| enable_settings());
+
+    let grid = FlexGridSizer::builder(0, 2).with_vgap(8).with_hgap(8).build();
+    grid.add_growable_col(1, 1);
+    grid.add(
+        &StaticText::builder(&dialog).with_label("Secret or URI").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    grid.add(&secret, 1, SizerFlag::All | SizerFlag::Expand, 4);
+    grid.add(&StaticText::builder(&dialog).with_label("Algorithm").build(), 0, SizerFlag::All, 4);
+    grid.add(&algorithm, 0, SizerFlag::All, 4);
+    grid.add(&StaticText::builder(&dialog).with_label("Digits").build(), 0, SizerFlag::All, 4);
+    grid.add(&digits, 0, SizerFlag::All, 4);
+    grid.add(
+        &StaticText::builder(&dialog).with_label("Period (seconds)").build(),
+        0,
+        SizerFlag::All,
+        4,
+    );
+    grid.add(&period, 0, SizerFlag::All, 4);
+    let hint = StaticText::builder(&dialog)
+        .with_label("Algorithm, digits and period are taken from the URI when one is pasted.")
+        .build();
+
+    let root = BoxSizer::builder(Orientation::Vertical).build();
+    root.add_sizer(&grid, 1, SizerFlag::All | SizerFlag::Expand, 12);
+    root.add(&hint, 0, SizerFlag::Left | SizerFlag::Right, 16);
+    let actions = BoxSizer::builder(Orientation::Horizontal).build();
+    let remove = Button::builder(&dialog).with_label("Remove").build();
+    remove.enable(current.is_some());
+    let spacer = StaticText::builder(&dialog).with_label("").build();
+    let cancel = Button::builder(&dialog).with_id(wxdragon::ID_CANCEL).with_label("Cancel").build();
+    let ok = Button::builder(&dialog).with_label("OK").build();
+    actions.add(&remove, 0, SizerFlag::All, 4);
+    actions.add(&spacer, 1, SizerFlag::Expand, 0);
+    actions.add(&cancel, 0, SizerFlag::All, 4);
+    actions.add(&ok, 0, SizerFlag::All, 4);
+    root.add_sizer(&actions, 0, SizerFlag::All | SizerFlag::Expand, 8);
+    dialog.set_sizer(root, true);
+    dialog.set_escape_id(wxdragon::ID_CANCEL);
+
+    let title = title.to_string();
+    let username = username.to_string();
+    let read = Rc::new(move || -> Result<Otp> {
+        let text = secret.get_value();
+        let text = text.trim();
+        if is_uri(text) {
+            return Otp::parse(text);
+        }
+        let mut otp = Otp::new(text)?;
+        otp.algorithm = algorithm
+            .get_selection()
+            .and_then(|selection| Algorithm::ALL.get(selection as usize).copied())
+            .unwrap_or(Algorithm::Sha1);
+        match digits.get_selection().unwrap_or(0) {
+            3 => otp.set_steam(),
+            selection => otp.digits = DIGIT_CHOICES[selection as usize].parse().unwrap_or(6),
+        }
+        let seconds = period.get_value().trim().parse::<u64>().ok().filter(|seconds| *seconds > 0);
+        otp.kind = Kind::Totp {
+            period: seconds.ok_or("The period must be a whole number of seconds")?,
+        };
+        otp.issuer = title.clone();
+        otp.account = username.clone();
+        Ok(otp)
+    });
+
+    let removed = Rc::new(Cell::new(false));
+    let removed_for_button = Rc::clone(&removed);
+    let dialog_for_remove = dialog;
+    remove.on_click(move |_| {
+        removed_for_button.set(true);
+        dialog_for_remove.end_modal(wxdragon::ID_OK);
+    });
+    let dialog_for_cancel = dialog;
+    cancel.on_click(move |_| dialog_for_cancel.end_modal(wxdragon::ID_CANCEL));
+    let dialog_for_ok = dialog;
+    let read_for_ok = Rc::clone(&read);
+    ok.on_click(move |_| match read_for_ok() {
+        Ok(_) => dialog_for_ok.end_modal(wxdragon::ID_OK),
+        Err(error) => {
+            MessageDialog::builder(&dialog_for_ok, &error.to_string(), "Set up TOTP")
+                .with_style(MessageDialogStyle::OK | MessageDialogStyle::IconWarning)
+                .build()
+                .show_modal();
+        }
+    });
+
+    dialog.center();
+    let result = dialog.show_modal();
+    let otp = if removed.get() {
+        Some(None)
+    } else if result == wxdragon::ID_OK {
+        read().ok().map(|otp| Some(otp.to_uri()))
+    } else {
+        None
+    };
+    dialog.destroy();
+    otp
+}
+
+/// Whether `text` carries its own settings, as `otpauth://` URIs and KeeOTP strings do.
+fn is_uri(text: &str) -> bool {
+    let text = text.trim();
+    text.starts_with("otpauth://") || text.starts_with("key=")
+}